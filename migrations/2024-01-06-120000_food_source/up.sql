@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE foods ADD COLUMN source VARCHAR;
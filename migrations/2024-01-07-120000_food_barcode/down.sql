@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX foods_barcode_unique;
+ALTER TABLE foods DROP COLUMN barcode;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE foods ADD COLUMN barcode VARCHAR;
+CREATE UNIQUE INDEX foods_barcode_unique ON foods (barcode);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE foods DROP COLUMN data_source_id;
+DROP TABLE data_sources;
@@ -0,0 +1,23 @@
+-- Your SQL goes here
+CREATE TABLE data_sources (
+    id INTEGER PRIMARY KEY NOT NULL,
+    kind VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    trusted BOOLEAN NOT NULL DEFAULT 0,
+    CONSTRAINT data_source_unique UNIQUE (kind, name)
+);
+
+ALTER TABLE foods ADD COLUMN data_source_id INTEGER REFERENCES data_sources(id);
+
+-- Foods imported from FoodData Central before sources were tracked carry
+-- the dataset name in their attribution.
+INSERT INTO data_sources (kind, name, trusted)
+SELECT DISTINCT 'usda', substr(source, 1, instr(source, ', FDC ID') - 1), 1
+FROM foods
+WHERE source LIKE 'USDA FoodData Central (%), FDC ID %';
+
+UPDATE foods SET data_source_id = (
+    SELECT id FROM data_sources
+    WHERE kind = 'usda' AND name = substr(foods.source, 1, instr(foods.source, ', FDC ID') - 1)
+)
+WHERE source LIKE 'USDA FoodData Central (%), FDC ID %';
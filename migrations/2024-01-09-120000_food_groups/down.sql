@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE foods DROP COLUMN food_group_id;
+DROP TABLE food_groups;
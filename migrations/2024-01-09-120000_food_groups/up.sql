@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE food_groups (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    CONSTRAINT name_unique UNIQUE (name)
+);
+
+ALTER TABLE foods ADD COLUMN food_group_id INTEGER REFERENCES food_groups(id);
@@ -0,0 +1,24 @@
+-- Your SQL goes here
+-- External content index over food names. remove_diacritics folds å, ä and ö
+-- so that "lok" matches "Lök".
+CREATE VIRTUAL TABLE foods_fts USING fts5(
+    name,
+    content='foods',
+    content_rowid='id',
+    tokenize='unicode61 remove_diacritics 2'
+);
+
+INSERT INTO foods_fts(foods_fts) VALUES ('rebuild');
+
+CREATE TRIGGER foods_fts_insert AFTER INSERT ON foods BEGIN
+    INSERT INTO foods_fts(rowid, name) VALUES (new.id, new.name);
+END;
+
+CREATE TRIGGER foods_fts_delete AFTER DELETE ON foods BEGIN
+    INSERT INTO foods_fts(foods_fts, rowid, name) VALUES ('delete', old.id, old.name);
+END;
+
+CREATE TRIGGER foods_fts_update AFTER UPDATE OF name ON foods BEGIN
+    INSERT INTO foods_fts(foods_fts, rowid, name) VALUES ('delete', old.id, old.name);
+    INSERT INTO foods_fts(rowid, name) VALUES (new.id, new.name);
+END;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- One row per distinct term in the food name index, used for typo correction
+CREATE VIRTUAL TABLE foods_fts_vocab USING fts5vocab(foods_fts, row);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE foods DROP COLUMN fiber;
+ALTER TABLE foods DROP COLUMN carbohydrates;
+ALTER TABLE foods DROP COLUMN fat;
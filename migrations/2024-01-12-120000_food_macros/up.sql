@@ -0,0 +1,6 @@
+-- Your SQL goes here
+-- Per 100 g in hundredths of a gram like protein, NULL when the source
+-- doesn't report the nutrient.
+ALTER TABLE foods ADD COLUMN fat INTEGER;
+ALTER TABLE foods ADD COLUMN carbohydrates INTEGER;
+ALTER TABLE foods ADD COLUMN fiber INTEGER;
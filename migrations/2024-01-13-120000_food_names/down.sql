@@ -0,0 +1,37 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER food_names_fts_update;
+DROP TRIGGER food_names_fts_delete;
+DROP TRIGGER food_names_fts_insert;
+DROP TABLE foods_fts_vocab;
+DROP TRIGGER foods_fts_update;
+DROP TRIGGER foods_fts_delete;
+DROP TRIGGER foods_fts_insert;
+DROP TABLE foods_fts;
+
+CREATE VIRTUAL TABLE foods_fts USING fts5(
+    name,
+    content='foods',
+    content_rowid='id',
+    tokenize='unicode61 remove_diacritics 2'
+);
+
+INSERT INTO foods_fts(foods_fts) VALUES ('rebuild');
+
+CREATE VIRTUAL TABLE foods_fts_vocab USING fts5vocab(foods_fts, row);
+
+CREATE TRIGGER foods_fts_insert AFTER INSERT ON foods BEGIN
+    INSERT INTO foods_fts(rowid, name) VALUES (new.id, new.name);
+END;
+
+CREATE TRIGGER foods_fts_delete AFTER DELETE ON foods BEGIN
+    INSERT INTO foods_fts(foods_fts, rowid, name) VALUES ('delete', old.id, old.name);
+END;
+
+CREATE TRIGGER foods_fts_update AFTER UPDATE OF name ON foods BEGIN
+    INSERT INTO foods_fts(foods_fts, rowid, name) VALUES ('delete', old.id, old.name);
+    INSERT INTO foods_fts(rowid, name) VALUES (new.id, new.name);
+END;
+
+ALTER TABLE users DROP COLUMN language;
+
+DROP TABLE food_names;
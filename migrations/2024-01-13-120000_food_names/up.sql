@@ -0,0 +1,65 @@
+-- Your SQL goes here
+-- Names of foods in other languages than the one in foods.name, which is
+-- Swedish for everything imported from Livsmedelsverket.
+CREATE TABLE food_names (
+    id INTEGER PRIMARY KEY NOT NULL,
+    food_id INTEGER NOT NULL REFERENCES foods(id) ON DELETE CASCADE,
+    language TEXT NOT NULL,
+    name TEXT NOT NULL,
+    UNIQUE(food_id, language)
+);
+
+ALTER TABLE users ADD COLUMN language TEXT NOT NULL DEFAULT 'sv';
+
+-- The index now covers the translated names too. They live in another table,
+-- so it keeps its own copy of the text instead of being an external content
+-- index over foods.
+DROP TABLE foods_fts_vocab;
+DROP TRIGGER foods_fts_update;
+DROP TRIGGER foods_fts_delete;
+DROP TRIGGER foods_fts_insert;
+DROP TABLE foods_fts;
+
+CREATE VIRTUAL TABLE foods_fts USING fts5(
+    name,
+    translations,
+    tokenize='unicode61 remove_diacritics 2'
+);
+
+INSERT INTO foods_fts(rowid, name, translations)
+    SELECT id, name, '' FROM foods;
+
+CREATE VIRTUAL TABLE foods_fts_vocab USING fts5vocab(foods_fts, row);
+
+CREATE TRIGGER foods_fts_insert AFTER INSERT ON foods BEGIN
+    INSERT INTO foods_fts(rowid, name, translations) VALUES (new.id, new.name, '');
+END;
+
+CREATE TRIGGER foods_fts_delete AFTER DELETE ON foods BEGIN
+    DELETE FROM foods_fts WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER foods_fts_update AFTER UPDATE OF name ON foods BEGIN
+    UPDATE foods_fts SET name = new.name WHERE rowid = new.id;
+END;
+
+CREATE TRIGGER food_names_fts_insert AFTER INSERT ON food_names BEGIN
+    UPDATE foods_fts SET translations = (
+        SELECT group_concat(name, ' ') FROM food_names WHERE food_id = new.food_id
+    ) WHERE rowid = new.food_id;
+END;
+
+CREATE TRIGGER food_names_fts_delete AFTER DELETE ON food_names BEGIN
+    UPDATE foods_fts SET translations = coalesce((
+        SELECT group_concat(name, ' ') FROM food_names WHERE food_id = old.food_id
+    ), '') WHERE rowid = old.food_id;
+END;
+
+CREATE TRIGGER food_names_fts_update AFTER UPDATE ON food_names BEGIN
+    UPDATE foods_fts SET translations = coalesce((
+        SELECT group_concat(name, ' ') FROM food_names WHERE food_id = old.food_id
+    ), '') WHERE rowid = old.food_id;
+    UPDATE foods_fts SET translations = (
+        SELECT group_concat(name, ' ') FROM food_names WHERE food_id = new.food_id
+    ) WHERE rowid = new.food_id;
+END;
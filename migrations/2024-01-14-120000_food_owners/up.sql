@@ -0,0 +1,6 @@
+-- Your SQL goes here
+-- The user who created a food. Imported reference foods have no owner and
+-- can only be changed by admins.
+ALTER TABLE foods ADD COLUMN owner_id INTEGER REFERENCES users(id);
+
+ALTER TABLE users ADD COLUMN admin BOOLEAN NOT NULL DEFAULT 0;
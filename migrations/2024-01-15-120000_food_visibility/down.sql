@@ -0,0 +1,41 @@
+-- This file should undo anything in `up.sql`
+-- Fails if two owners have foods with the same name or barcode.
+CREATE TABLE foods_old (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    calories INTEGER NOT NULL,
+    protein INTEGER NOT NULL,
+    source VARCHAR,
+    barcode VARCHAR,
+    data_source_id INTEGER REFERENCES data_sources(id),
+    food_group_id INTEGER REFERENCES food_groups(id),
+    fat INTEGER,
+    carbohydrates INTEGER,
+    fiber INTEGER,
+    owner_id INTEGER REFERENCES users(id),
+    CONSTRAINT name_unique UNIQUE (name)
+);
+
+INSERT INTO foods_old (id, name, calories, protein, source, barcode, data_source_id, food_group_id, fat, carbohydrates, fiber, owner_id)
+    SELECT id, name, calories, protein, source, barcode, data_source_id, food_group_id, fat, carbohydrates, fiber, owner_id FROM foods;
+
+DROP TABLE foods;
+ALTER TABLE foods_old RENAME TO foods;
+
+CREATE UNIQUE INDEX foods_barcode_unique ON foods (barcode);
+
+CREATE TRIGGER foods_fts_insert AFTER INSERT ON foods BEGIN
+    INSERT INTO foods_fts(rowid, name, translations) VALUES (new.id, new.name, '');
+END;
+
+CREATE TRIGGER foods_fts_delete AFTER DELETE ON foods BEGIN
+    DELETE FROM foods_fts WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER foods_fts_update AFTER UPDATE OF name ON foods BEGIN
+    UPDATE foods_fts SET name = new.name WHERE rowid = new.id;
+END;
+
+ALTER TABLE users DROP COLUMN household_id;
+
+DROP TABLE households;
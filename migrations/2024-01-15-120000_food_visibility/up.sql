@@ -0,0 +1,55 @@
+-- Your SQL goes here
+-- Users in the same household can share foods with each other.
+CREATE TABLE households (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    CONSTRAINT name_unique UNIQUE (name)
+);
+
+ALTER TABLE users ADD COLUMN household_id INTEGER REFERENCES households(id);
+
+-- Names and barcodes now only have to be unique among the foods of one owner,
+-- or among the imported foods that have none. SQLite can't drop the old
+-- name_unique constraint, so the table is rebuilt.
+CREATE TABLE foods_new (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    calories INTEGER NOT NULL,
+    protein INTEGER NOT NULL,
+    source VARCHAR,
+    barcode VARCHAR,
+    data_source_id INTEGER REFERENCES data_sources(id),
+    food_group_id INTEGER REFERENCES food_groups(id),
+    fat INTEGER,
+    carbohydrates INTEGER,
+    fiber INTEGER,
+    owner_id INTEGER REFERENCES users(id),
+    -- 'private', 'household' or 'public'. Foods without owner are public.
+    visibility VARCHAR NOT NULL DEFAULT 'public'
+);
+
+-- Foods users already created become private, like new ones do.
+INSERT INTO foods_new (id, name, calories, protein, source, barcode, data_source_id, food_group_id, fat, carbohydrates, fiber, owner_id, visibility)
+    SELECT id, name, calories, protein, source, barcode, data_source_id, food_group_id, fat, carbohydrates, fiber, owner_id,
+        CASE WHEN owner_id IS NULL THEN 'public' ELSE 'private' END
+    FROM foods;
+
+DROP TABLE foods;
+ALTER TABLE foods_new RENAME TO foods;
+
+CREATE UNIQUE INDEX foods_name_unique ON foods (name) WHERE owner_id IS NULL;
+CREATE UNIQUE INDEX foods_owner_name_unique ON foods (owner_id, name) WHERE owner_id IS NOT NULL;
+CREATE UNIQUE INDEX foods_barcode_unique ON foods (barcode) WHERE owner_id IS NULL;
+CREATE UNIQUE INDEX foods_owner_barcode_unique ON foods (owner_id, barcode) WHERE owner_id IS NOT NULL;
+
+CREATE TRIGGER foods_fts_insert AFTER INSERT ON foods BEGIN
+    INSERT INTO foods_fts(rowid, name, translations) VALUES (new.id, new.name, '');
+END;
+
+CREATE TRIGGER foods_fts_delete AFTER DELETE ON foods BEGIN
+    DELETE FROM foods_fts WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER foods_fts_update AFTER UPDATE OF name ON foods BEGIN
+    UPDATE foods_fts SET name = new.name WHERE rowid = new.id;
+END;
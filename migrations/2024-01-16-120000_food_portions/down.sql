@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE foods DROP COLUMN density;
+
+DROP TABLE food_portions;
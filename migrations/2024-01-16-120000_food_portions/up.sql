@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- Named units of a food such as "egg" or "slice", in hundredths of a gram per
+-- unit like the nutrient columns.
+CREATE TABLE food_portions (
+    id INTEGER PRIMARY KEY NOT NULL,
+    food_id INTEGER NOT NULL REFERENCES foods(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    grams INTEGER NOT NULL,
+    UNIQUE(food_id, name)
+);
+
+-- Hundredths of a gram per ml, so 103 for milk. Needed to enter amounts in
+-- dl, msk and other volumes, NULL if unknown.
+ALTER TABLE foods ADD COLUMN density INTEGER;
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+-- A recipe is a food whose nutrients are derived from its ingredients. The
+-- cooked weight, in hundredths of a gram, accounts for water lost or gained
+-- while cooking. NULL means it weighs as much as its raw ingredients.
+CREATE TABLE recipes (
+    food_id INTEGER PRIMARY KEY NOT NULL REFERENCES foods(id) ON DELETE CASCADE,
+    cooked_weight INTEGER
+);
+
+-- The amount is kept as typed, e.g. "2 dl", so that grams can be worked out
+-- again when the density or portions of the ingredient change.
+CREATE TABLE recipe_ingredients (
+    id INTEGER PRIMARY KEY NOT NULL,
+    recipe_id INTEGER NOT NULL REFERENCES recipes(food_id) ON DELETE CASCADE,
+    food_id INTEGER NOT NULL REFERENCES foods(id),
+    amount VARCHAR NOT NULL,
+    grams INTEGER NOT NULL
+);
+
+CREATE INDEX recipe_ingredients_food ON recipe_ingredients(food_id);
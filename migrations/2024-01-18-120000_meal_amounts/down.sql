@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE meal_food_relations DROP COLUMN grams;
+ALTER TABLE meal_food_relations DROP COLUMN amount;
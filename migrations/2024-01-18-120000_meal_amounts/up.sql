@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- How much of each food a meal contains, as typed (e.g. "150" or "2 dl") and
+-- converted to hundredths of a gram. Meals saved before this get 100 g.
+ALTER TABLE meal_food_relations ADD COLUMN amount VARCHAR NOT NULL DEFAULT '100';
+ALTER TABLE meal_food_relations ADD COLUMN grams INTEGER NOT NULL DEFAULT 10000;
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE meals_old (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    CONSTRAINT name_unique UNIQUE (name)
+);
+
+INSERT OR IGNORE INTO meals_old (id, name) SELECT id, name FROM meals;
+
+DROP TABLE meals;
+ALTER TABLE meals_old RENAME TO meals;
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+-- Meals belong to the user who saved them and names only have to be unique
+-- among the meals of one user. SQLite can't drop the old name_unique
+-- constraint, so the table is rebuilt. Meals saved before this have no owner
+-- and aren't shown to anyone.
+CREATE TABLE meals_new (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    owner_id INTEGER REFERENCES users(id)
+);
+
+INSERT INTO meals_new (id, name) SELECT id, name FROM meals;
+
+DROP TABLE meals;
+ALTER TABLE meals_new RENAME TO meals;
+
+CREATE UNIQUE INDEX meals_owner_name_unique ON meals (owner_id, name);
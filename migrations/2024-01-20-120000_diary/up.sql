@@ -0,0 +1,18 @@
+-- Your SQL goes here
+-- What a user ate on a day. An entry is either an amount of a food or a
+-- number of servings of a saved meal.
+CREATE TABLE diary_entries (
+    id INTEGER PRIMARY KEY NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    date DATE NOT NULL,
+    slot VARCHAR NOT NULL,
+    food_id INTEGER REFERENCES foods(id),
+    meal_id INTEGER REFERENCES meals(id),
+    amount VARCHAR NOT NULL,
+    quantity INTEGER NOT NULL,
+    CHECK ((food_id IS NULL) <> (meal_id IS NULL))
+);
+
+CREATE INDEX diary_entries_user_date ON diary_entries (user_id, date);
+CREATE INDEX diary_entries_food ON diary_entries (food_id);
+CREATE INDEX diary_entries_meal ON diary_entries (meal_id);
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- Daily targets of a user. Weekday 0 applies to every day, 1 to 7 (Monday to
+-- Sunday) override its values on that day. A missing value means no target.
+CREATE TABLE targets (
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    weekday INTEGER NOT NULL,
+    calories INTEGER,
+    protein INTEGER,
+    fat INTEGER,
+    carbohydrates INTEGER,
+    fiber INTEGER,
+    PRIMARY KEY (user_id, weekday)
+);
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- What energy expenditure is estimated from. Height is in hundredths of a cm,
+-- body fat in hundredths of a percent and the weekly change the user aims for
+-- in hundredths of a kg, negative to lose weight.
+CREATE TABLE profiles (
+    user_id INTEGER PRIMARY KEY NOT NULL REFERENCES users(id),
+    sex VARCHAR NOT NULL,
+    birth_date DATE NOT NULL,
+    height INTEGER NOT NULL,
+    activity VARCHAR NOT NULL,
+    body_fat INTEGER,
+    weekly_change INTEGER NOT NULL DEFAULT 0
+);
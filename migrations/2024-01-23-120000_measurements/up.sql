@@ -0,0 +1,10 @@
+-- Your SQL goes here
+-- Body weight of a user in hundredths of a kg, at most once a day.
+CREATE TABLE measurements (
+    id INTEGER PRIMARY KEY NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    date DATE NOT NULL,
+    weight INTEGER NOT NULL
+);
+
+CREATE UNIQUE INDEX measurements_user_date_unique ON measurements (user_id, date);
@@ -0,0 +1,17 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE measurements_old (
+    id INTEGER PRIMARY KEY NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    date DATE NOT NULL,
+    weight INTEGER NOT NULL
+);
+
+INSERT INTO measurements_old (id, user_id, date, weight)
+    SELECT id, user_id, date, weight FROM measurements WHERE weight IS NOT NULL;
+
+DROP TABLE measurements;
+ALTER TABLE measurements_old RENAME TO measurements;
+
+CREATE UNIQUE INDEX measurements_user_date_unique ON measurements (user_id, date);
+
+ALTER TABLE users DROP COLUMN weight_unit;
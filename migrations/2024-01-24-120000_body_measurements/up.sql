@@ -0,0 +1,26 @@
+-- Your SQL goes here
+-- Unit weights are shown and entered in, 'kg' or 'lb'. They're stored in kg.
+ALTER TABLE users ADD COLUMN weight_unit TEXT NOT NULL DEFAULT 'kg';
+
+-- Measurements can now be any of weight, waist and body fat, so weight is
+-- nullable. SQLite can't drop NOT NULL from a column, so rebuild the table.
+CREATE TABLE measurements_new (
+    id INTEGER PRIMARY KEY NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    date DATE NOT NULL,
+    -- Hundredths of a kg.
+    weight INTEGER,
+    -- Waist circumference in hundredths of a cm.
+    waist INTEGER,
+    -- Body fat in hundredths of a percent.
+    body_fat INTEGER,
+    CHECK(weight IS NOT NULL OR waist IS NOT NULL OR body_fat IS NOT NULL)
+);
+
+INSERT INTO measurements_new (id, user_id, date, weight)
+    SELECT id, user_id, date, weight FROM measurements;
+
+DROP TABLE measurements;
+ALTER TABLE measurements_new RENAME TO measurements;
+
+CREATE UNIQUE INDEX measurements_user_date_unique ON measurements (user_id, date);
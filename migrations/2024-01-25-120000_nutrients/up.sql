@@ -0,0 +1,20 @@
+-- Your SQL goes here
+-- Nutrients besides those kept on foods, such as vitamins and minerals, named
+-- as in the database of Livsmedelsverket.
+CREATE TABLE nutrients (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    -- "g", "mg" or "µg"
+    unit TEXT NOT NULL
+);
+
+-- Amount of a nutrient in 100 g of a food, in hundredths of its unit. Foods
+-- without a row for a nutrient have an unknown amount of it.
+CREATE TABLE food_nutrients (
+    food_id INTEGER NOT NULL REFERENCES foods(id) ON DELETE CASCADE,
+    nutrient_id INTEGER NOT NULL REFERENCES nutrients(id),
+    amount INTEGER NOT NULL,
+    PRIMARY KEY (food_id, nutrient_id)
+);
+
+CREATE INDEX food_nutrients_nutrient_id ON food_nutrients (nutrient_id);
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+-- What a user plans to eat on a day, like a diary entry. An entry is either
+-- an amount of a food, usually a recipe, or a number of servings of a saved
+-- meal.
+CREATE TABLE planned_entries (
+    id INTEGER PRIMARY KEY NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    date DATE NOT NULL,
+    slot VARCHAR NOT NULL,
+    food_id INTEGER REFERENCES foods(id),
+    meal_id INTEGER REFERENCES meals(id),
+    amount VARCHAR NOT NULL,
+    quantity INTEGER NOT NULL,
+    CHECK ((food_id IS NULL) <> (meal_id IS NULL))
+);
+
+CREATE INDEX planned_entries_user_date ON planned_entries (user_id, date);
+CREATE INDEX planned_entries_food ON planned_entries (food_id);
+CREATE INDEX planned_entries_meal ON planned_entries (meal_id);
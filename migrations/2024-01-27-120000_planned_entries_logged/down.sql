@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE planned_entries DROP COLUMN logged_entry_id;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- The diary entry a planned entry was logged as, so that it is logged only
+-- once. Removing the diary entry lets it be logged again.
+ALTER TABLE planned_entries
+    ADD COLUMN logged_entry_id INTEGER REFERENCES diary_entries(id) ON DELETE SET NULL;
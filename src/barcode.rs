@@ -0,0 +1,45 @@
+use simple_error::{SimpleError, SimpleResult};
+
+/// Validates an EAN-8, UPC-A, EAN-13 or GTIN-14 code and returns it in the
+/// form it is stored in the database. UPC-A codes are widened to EAN-13 and
+/// GTIN-14 codes with a zero indicator digit are narrowed to EAN-13, so the
+/// same product matches regardless of how it was scanned.
+pub fn normalize(code: &str) -> SimpleResult<String> {
+    let code = code.trim();
+    if code.is_empty() {
+        return Err(SimpleError::new("Barcode is empty"));
+    }
+    if !code.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(SimpleError::new("Barcode may only contain digits"));
+    }
+    if ![8, 12, 13, 14].contains(&code.len()) {
+        return Err(SimpleError::new(format!(
+            "Barcode has {} digits, expected 8, 12, 13 or 14",
+            code.len()
+        )));
+    }
+
+    let digits: Vec<u32> = code.bytes().map(|b| (b - b'0') as u32).collect();
+    let (check, payload) = digits.split_last().unwrap();
+    if check_digit(payload) != *check {
+        return Err(SimpleError::new("Barcode check digit is invalid"));
+    }
+
+    Ok(match code.len() {
+        12 => format!("0{}", code),
+        14 if code.starts_with('0') => code[1..].to_owned(),
+        _ => code.to_owned(),
+    })
+}
+
+/// GS1 check digit, the payload digits are weighted 3 and 1 alternately
+/// starting from the rightmost one.
+fn check_digit(payload: &[u32]) -> u32 {
+    let sum: u32 = payload
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d })
+        .sum();
+    (10 - sum % 10) % 10
+}
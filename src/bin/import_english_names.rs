@@ -0,0 +1,73 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use clap::Parser;
+use diesel::{
+    r2d2, replace_into, Connection, ExpressionMethods, NullableExpressionMethods, QueryDsl,
+    RunQueryDsl, SqliteConnection, TextExpressionMethods,
+};
+use track_notes::language::Language;
+use track_notes::schema::{food_names, foods};
+
+/// Imports English food names from the English export of the Livsmedelsverket
+/// food database. It has the same layout as the Swedish file read by
+/// populate_db, so foods are matched on their food number.
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Cli {
+    path: PathBuf,
+}
+
+fn main() {
+    let manager = r2d2::ConnectionManager::<SqliteConnection>::new("diesel_demo.sqlite");
+    let pool = r2d2::Pool::builder()
+        .build(manager)
+        .expect("Database url should be a valid path to a SQLite DB file");
+
+    let mut conn = pool.get().expect("couldnt get db conn from pool");
+
+    let cli = Cli::parse();
+
+    let contents = fs::read_to_string(cli.path).unwrap();
+
+    let food_ids: HashMap<String, i32> = foods::table
+        .filter(foods::source.like("Livsmedelsnummer %"))
+        .select((foods::source.assume_not_null(), foods::id))
+        .load(&mut conn)
+        .unwrap()
+        .into_iter()
+        .collect();
+
+    let mut names = vec![];
+    let mut unknown = 0;
+    for r in contents.lines().skip(3) {
+        let cells: Vec<&str> = r.split(';').map(|x| x.trim()).collect();
+        if cells.len() < 2 {
+            continue;
+        }
+        let name = cells[0].trim_matches('"');
+        match food_ids.get(&format!("Livsmedelsnummer {}", cells[1])) {
+            Some(food_id) if !name.is_empty() => names.push((
+                food_names::food_id.eq(*food_id),
+                food_names::language.eq(Language::English.code()),
+                food_names::name.eq(name.to_owned()),
+            )),
+            _ => unknown += 1,
+        }
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        for chunk in names.chunks(1000) {
+            replace_into(food_names::table)
+                .values(chunk)
+                .execute(conn)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    println!(
+        "Imported {} English names, skipped {} rows without a matching food",
+        names.len(),
+        unknown
+    );
+}
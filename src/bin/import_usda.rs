@@ -0,0 +1,242 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use clap::Parser;
+use diesel::{
+    insert_or_ignore_into, r2d2, Connection, ExpressionMethods, RunQueryDsl, SqliteConnection,
+};
+use serde::Deserialize;
+use track_notes::sources::{self, SourceKind};
+use track_notes::{fixed, schema};
+
+/// Imports the Foundation and SR Legacy CSV bundles from USDA FoodData Central.
+///
+/// Each path should be an extracted bundle directory containing `food.csv`,
+/// `nutrient.csv` and `food_nutrient.csv`.
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Cli {
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct UsdaFood {
+    fdc_id: i32,
+    data_type: String,
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct UsdaNutrient {
+    id: i32,
+    name: String,
+    unit_name: String,
+}
+
+#[derive(Deserialize)]
+struct UsdaFoodNutrient {
+    fdc_id: i32,
+    nutrient_id: i32,
+    amount: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Nutrient {
+    Calories,
+    Protein,
+    Fat,
+    Carbohydrates,
+    Fiber,
+}
+
+/// USDA nutrient ids mapped to our columns, together with the unit we expect
+/// them in. When several ids map to the same column the one listed first wins,
+/// Foundation foods usually only report the Atwater energies.
+const NUTRIENT_MAP: [(i32, &str, Nutrient); 7] = [
+    (1008, "KCAL", Nutrient::Calories),
+    (2048, "KCAL", Nutrient::Calories),
+    (2047, "KCAL", Nutrient::Calories),
+    (1003, "G", Nutrient::Protein),
+    (1004, "G", Nutrient::Fat),
+    (1005, "G", Nutrient::Carbohydrates),
+    (1079, "G", Nutrient::Fiber),
+];
+
+/// Rows per insert statement, keeps us below SQLite's bound parameter limit.
+const CHUNK_SIZE: usize = 1000;
+
+#[derive(Default)]
+struct NutrientValues {
+    calories: Option<(usize, i32)>,
+    protein: Option<(usize, i32)>,
+    fat: Option<(usize, i32)>,
+    carbohydrates: Option<(usize, i32)>,
+    fiber: Option<(usize, i32)>,
+}
+
+impl NutrientValues {
+    fn set(&mut self, nutrient: Nutrient, priority: usize, amount: i32) {
+        let slot = match nutrient {
+            Nutrient::Calories => &mut self.calories,
+            Nutrient::Protein => &mut self.protein,
+            Nutrient::Fat => &mut self.fat,
+            Nutrient::Carbohydrates => &mut self.carbohydrates,
+            Nutrient::Fiber => &mut self.fiber,
+        };
+        match slot {
+            Some((p, _)) if *p <= priority => {}
+            _ => *slot = Some((priority, amount)),
+        }
+    }
+}
+
+/// A food ready to be inserted, amounts are in hundredths per 100 g.
+struct ImportRow {
+    description: String,
+    fdc_id: i32,
+    dataset: &'static str,
+    calories: i32,
+    protein: i32,
+    fat: Option<i32>,
+    carbohydrates: Option<i32>,
+    fiber: Option<i32>,
+}
+
+fn data_type_label(data_type: &str) -> Option<&'static str> {
+    match data_type {
+        "foundation_food" => Some("Foundation"),
+        "sr_legacy_food" => Some("SR Legacy"),
+        _ => None,
+    }
+}
+
+fn read_bundle(path: &PathBuf) -> Vec<ImportRow> {
+    let mut nutrients = HashMap::new();
+    for n in csv::Reader::from_path(path.join("nutrient.csv"))
+        .unwrap()
+        .deserialize::<UsdaNutrient>()
+    {
+        let n = n.unwrap();
+        nutrients.insert(n.id, n);
+    }
+
+    let mut mapping = HashMap::new();
+    for (priority, (id, unit, nutrient)) in NUTRIENT_MAP.iter().enumerate() {
+        match nutrients.get(id) {
+            Some(n) if n.unit_name.eq_ignore_ascii_case(unit) => {
+                mapping.insert(*id, (priority, *nutrient));
+            }
+            Some(n) => println!(
+                "Skipping nutrient {} ({}), expected unit {} but found {}",
+                id, n.name, unit, n.unit_name
+            ),
+            None => println!("Nutrient {} is missing from {:?}", id, path),
+        }
+    }
+
+    let mut foods = HashMap::new();
+    for f in csv::Reader::from_path(path.join("food.csv"))
+        .unwrap()
+        .deserialize::<UsdaFood>()
+    {
+        let f = f.unwrap();
+        if data_type_label(&f.data_type).is_some() {
+            foods.insert(f.fdc_id, f);
+        }
+    }
+
+    let mut values: HashMap<i32, NutrientValues> = HashMap::new();
+    for fn_row in csv::Reader::from_path(path.join("food_nutrient.csv"))
+        .unwrap()
+        .deserialize::<UsdaFoodNutrient>()
+    {
+        let fn_row = fn_row.unwrap();
+        if !foods.contains_key(&fn_row.fdc_id) {
+            continue;
+        }
+        if let (Some((priority, nutrient)), Some(Ok(amount))) = (
+            mapping.get(&fn_row.nutrient_id),
+            fn_row.amount.as_deref().map(fixed::parse),
+        ) {
+            values
+                .entry(fn_row.fdc_id)
+                .or_default()
+                .set(*nutrient, *priority, amount);
+        }
+    }
+
+    let mut rows = vec![];
+    for (fdc_id, food) in &foods {
+        match values.get(fdc_id) {
+            Some(NutrientValues {
+                calories: Some((_, calories)),
+                protein: Some((_, protein)),
+                fat,
+                carbohydrates,
+                fiber,
+            }) => rows.push(ImportRow {
+                description: food.description.clone(),
+                fdc_id: *fdc_id,
+                dataset: data_type_label(&food.data_type).unwrap(),
+                calories: *calories,
+                protein: *protein,
+                fat: fat.map(|(_, v)| v),
+                carbohydrates: carbohydrates.map(|(_, v)| v),
+                fiber: fiber.map(|(_, v)| v),
+            }),
+            _ => println!(
+                "Skipping {} ({}), missing energy or protein",
+                food.description, fdc_id
+            ),
+        }
+    }
+    rows
+}
+
+fn main() {
+    let manager = r2d2::ConnectionManager::<SqliteConnection>::new("diesel_demo.sqlite");
+    let pool = r2d2::Pool::builder()
+        .build(manager)
+        .expect("Database url should be a valid path to a SQLite DB file");
+
+    let mut conn = pool.get().expect("couldnt get db conn from pool");
+
+    let cli = Cli::parse();
+
+    let mut inserts = vec![];
+    let mut source_ids = HashMap::new();
+    use schema::foods::dsl;
+
+    for path in &cli.paths {
+        for row in read_bundle(path) {
+            let source_name = format!("USDA FoodData Central ({})", row.dataset);
+            let source_id = *source_ids.entry(row.dataset).or_insert_with(|| {
+                sources::find_or_create(&mut conn, SourceKind::Usda, &source_name).unwrap()
+            });
+            inserts.push((
+                dsl::name.eq(row.description),
+                dsl::calories.eq(row.calories),
+                dsl::protein.eq(row.protein),
+                dsl::source.eq(Some(format!("{}, FDC ID {}", source_name, row.fdc_id))),
+                dsl::data_source_id.eq(source_id),
+                dsl::fat.eq(row.fat),
+                dsl::carbohydrates.eq(row.carbohydrates),
+                dsl::fiber.eq(row.fiber),
+            ));
+        }
+    }
+
+    // Foods sharing a name with an existing row are left untouched.
+    let inserted = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let mut inserted = 0;
+            for chunk in inserts.chunks(CHUNK_SIZE) {
+                inserted += insert_or_ignore_into(dsl::foods)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            Ok(inserted)
+        })
+        .unwrap();
+
+    println!("Imported {} of {} USDA foods", inserted, inserts.len());
+}
@@ -1,10 +1,26 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::PathBuf,
+};
 
 use clap::Parser;
-use diesel::{r2d2, ExpressionMethods, SqliteConnection, Insertable, insert_into, RunQueryDsl};
-use track_notes::schema;
+use diesel::{
+    insert_into, insert_or_ignore_into, r2d2, ExpressionMethods, QueryDsl, RunQueryDsl,
+    SqliteConnection,
+};
+use track_notes::sources::{self, SourceKind};
+use track_notes::{fixed, schema};
 
-type DbPool = r2d2::Pool<r2d2::ConnectionManager<SqliteConnection>>;
+/// Column of the first nutrient not kept on foods, the one after fiber.
+const FIRST_NUTRIENT_COLUMN: usize = 9;
+
+/// Rows of nutrient amounts inserted at once, to stay below the number of
+/// variables SQLite allows in a statement.
+const NUTRIENT_BATCH: usize = 1000;
+
+/// Line of the file the first food is on, after the version and header.
+const FIRST_ROW_LINE: usize = 4;
 
 #[derive(Parser)]
 #[command(author, version, about)]
@@ -12,6 +28,12 @@ struct Cli {
     path: PathBuf,
 }
 
+/// Parses the amount in a cell on `line` of the file, `None` for blank cells,
+/// which are unknown amounts. Anything else that isn't a number is an error.
+fn amount(line: usize, cell: &str) -> Option<i32> {
+    fixed::parse_optional(Some(cell)).unwrap_or_else(|e| panic!("Line {}: {}", line, e.as_str()))
+}
+
 fn main() {
     let manager = r2d2::ConnectionManager::<SqliteConnection>::new("diesel_demo.sqlite");
     let pool = r2d2::Pool::builder()
@@ -23,25 +45,144 @@ fn main() {
     let cli = Cli::parse();
 
     let contents = fs::read_to_string(cli.path).unwrap();
+
+    // The first line reads "Livsmedelsverkets livsmedelsdatabas version 2023-06-13"
+    let header = contents.lines().next().unwrap_or("");
+    let version = header
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim_matches('"')
+        .split_once("version ")
+        .map(|(_, v)| v.trim());
+    let source_name = match version {
+        Some(v) => format!("Livsmedelsverket {}", v),
+        None => "Livsmedelsverket".to_owned(),
+    };
+    let source_id =
+        sources::find_or_create(&mut conn, SourceKind::Livsmedelsverket, &source_name).unwrap();
+
     let rows = contents.lines().skip(3);
 
+    // The "Gruppering" column holds the food group
+    let group_names: BTreeSet<&str> = rows
+        .clone()
+        .map(|r| r.split(';').nth(2).unwrap().trim().trim_matches('"'))
+        .collect();
+    insert_or_ignore_into(schema::food_groups::table)
+        .values(
+            group_names
+                .iter()
+                .map(|name| schema::food_groups::name.eq(*name))
+                .collect::<Vec<_>>(),
+        )
+        .execute(&mut conn)
+        .unwrap();
+    let group_ids: HashMap<String, i32> = schema::food_groups::table
+        .select((schema::food_groups::name, schema::food_groups::id))
+        .load(&mut conn)
+        .unwrap()
+        .into_iter()
+        .collect();
+
     let mut inserts = vec![];
     use schema::foods::dsl;
 
-    for r in rows {
+    for (line, r) in (FIRST_ROW_LINE..).zip(rows.clone()) {
         let cells: Vec<&str> = r.split(";").map(|x| x.trim()).collect();
-        println!("{} {}", cells[3], cells[6]);
         let name = cells[0][1..cells[0].len() - 1].to_owned();
-        let cals: f32 = cells[3].parse().unwrap();
-        let protein: f32 = cells[6].parse().unwrap();
+        let group_id = group_ids.get(cells[2].trim_matches('"')).copied();
+        let cals = amount(line, cells[3]).unwrap_or_else(|| panic!("Line {}: no energy", line));
+        let protein = amount(line, cells[6]).unwrap_or_else(|| panic!("Line {}: no protein", line));
+        let fat = amount(line, cells[5]);
+        let carbohydrates = amount(line, cells[7]);
+        let fiber = amount(line, cells[8]);
         inserts.push((
             dsl::name.eq(name),
-            dsl::calories.eq((cals * 100.0) as i32),
-            dsl::protein.eq((protein * 100.0) as i32),
+            dsl::calories.eq(cals),
+            dsl::protein.eq(protein),
+            dsl::source.eq(format!("Livsmedelsnummer {}", cells[1])),
+            dsl::data_source_id.eq(source_id),
+            dsl::food_group_id.eq(group_id),
+            dsl::fat.eq(fat),
+            dsl::carbohydrates.eq(carbohydrates),
+            dsl::fiber.eq(fiber),
         ));
     }
 
-    insert_into(dsl::foods).values(inserts).execute(&mut conn).unwrap();
+    insert_into(dsl::foods)
+        .values(inserts)
+        .execute(&mut conn)
+        .unwrap();
+
+    // The other columns are nutrients, like "Vitamin D (µg)" or
+    // "Vitamin A (RE/µg)", except for the share of waste
+    let header: Vec<&str> = contents
+        .lines()
+        .nth(2)
+        .unwrap()
+        .split(';')
+        .map(|c| c.trim().trim_matches('"'))
+        .collect();
+    let nutrient_columns: Vec<(usize, &str, &str)> = header
+        .iter()
+        .enumerate()
+        .skip(FIRST_NUTRIENT_COLUMN)
+        .filter_map(|(i, column)| {
+            let (name, unit) = column.rsplit_once(" (")?;
+            let unit = unit.trim_end_matches(')').rsplit('/').next()?;
+            (unit != "%").then_some((i, name, unit))
+        })
+        .collect();
+    insert_or_ignore_into(schema::nutrients::table)
+        .values(
+            nutrient_columns
+                .iter()
+                .map(|(_, name, unit)| {
+                    (
+                        schema::nutrients::name.eq(*name),
+                        schema::nutrients::unit.eq(*unit),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .execute(&mut conn)
+        .unwrap();
+    let nutrient_ids: HashMap<String, i32> = schema::nutrients::table
+        .select((schema::nutrients::name, schema::nutrients::id))
+        .load(&mut conn)
+        .unwrap()
+        .into_iter()
+        .collect();
+    let food_ids: HashMap<String, i32> = dsl::foods
+        .filter(dsl::data_source_id.eq(source_id))
+        .select((dsl::source, dsl::id))
+        .load::<(Option<String>, i32)>(&mut conn)
+        .unwrap()
+        .into_iter()
+        .filter_map(|(source, id)| Some((source?, id)))
+        .collect();
+
+    let mut amounts = vec![];
+    for (line, r) in (FIRST_ROW_LINE..).zip(rows) {
+        let cells: Vec<&str> = r.split(";").map(|x| x.trim()).collect();
+        let food_id = food_ids[&format!("Livsmedelsnummer {}", cells[1])];
+        for (i, name, _) in &nutrient_columns {
+            if let Some(amount) = cells.get(*i).and_then(|c| amount(line, c)) {
+                amounts.push((
+                    schema::food_nutrients::food_id.eq(food_id),
+                    schema::food_nutrients::nutrient_id.eq(nutrient_ids[*name]),
+                    schema::food_nutrients::amount.eq(amount),
+                ));
+            }
+        }
+    }
+    for batch in amounts.chunks(NUTRIENT_BATCH) {
+        insert_or_ignore_into(schema::food_nutrients::table)
+            .values(batch)
+            .execute(&mut conn)
+            .unwrap();
+    }
 
-    println!("Populating db");
+    println!("Populating db from {}", source_name);
 }
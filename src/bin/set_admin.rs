@@ -0,0 +1,41 @@
+use clap::Parser;
+use diesel::{r2d2, ExpressionMethods, RunQueryDsl, SqliteConnection};
+use track_notes::schema::users;
+
+/// Grants a user the right to edit and delete every food, including imported
+/// reference foods.
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Cli {
+    username: String,
+    /// Take the admin rights away instead
+    #[arg(long)]
+    revoke: bool,
+}
+
+fn main() {
+    let manager = r2d2::ConnectionManager::<SqliteConnection>::new("diesel_demo.sqlite");
+    let pool = r2d2::Pool::builder()
+        .build(manager)
+        .expect("Database url should be a valid path to a SQLite DB file");
+
+    let mut conn = pool.get().expect("couldnt get db conn from pool");
+
+    let cli = Cli::parse();
+
+    let updated = diesel::update(users::table)
+        .filter(users::username.eq(&cli.username))
+        .set(users::admin.eq(!cli.revoke))
+        .execute(&mut conn)
+        .unwrap();
+
+    if updated == 0 {
+        eprintln!("No user named {}", cli.username);
+        std::process::exit(1);
+    }
+    println!(
+        "{} is {} an admin",
+        cli.username,
+        if cli.revoke { "no longer" } else { "now" }
+    );
+}
@@ -0,0 +1,53 @@
+use clap::Parser;
+use diesel::{
+    insert_or_ignore_into, r2d2, ExpressionMethods, QueryDsl, RunQueryDsl, SqliteConnection,
+};
+use track_notes::schema::{households, users};
+
+/// Puts a user in a household, whose members can see the foods they share
+/// with it. The household is created if it doesn't exist.
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Cli {
+    username: String,
+    /// Household to join, leaves the current one if not given
+    household: Option<String>,
+}
+
+fn main() {
+    let manager = r2d2::ConnectionManager::<SqliteConnection>::new("diesel_demo.sqlite");
+    let pool = r2d2::Pool::builder()
+        .build(manager)
+        .expect("Database url should be a valid path to a SQLite DB file");
+
+    let mut conn = pool.get().expect("couldnt get db conn from pool");
+
+    let cli = Cli::parse();
+
+    let household_id = cli.household.as_ref().map(|name| {
+        insert_or_ignore_into(households::table)
+            .values(households::name.eq(name))
+            .execute(&mut conn)
+            .unwrap();
+        households::table
+            .filter(households::name.eq(name))
+            .select(households::id)
+            .first::<i32>(&mut conn)
+            .unwrap()
+    });
+
+    let updated = diesel::update(users::table)
+        .filter(users::username.eq(&cli.username))
+        .set(users::household_id.eq(household_id))
+        .execute(&mut conn)
+        .unwrap();
+
+    if updated == 0 {
+        eprintln!("No user named {}", cli.username);
+        std::process::exit(1);
+    }
+    match cli.household {
+        Some(name) => println!("{} is now in household {}", cli.username, name),
+        None => println!("{} is no longer in a household", cli.username),
+    }
+}
@@ -0,0 +1,17 @@
+use diesel::r2d2::{self, CustomizeConnection};
+use diesel::{RunQueryDsl, SqliteConnection};
+
+/// Turns on foreign keys for each pooled connection. SQLite leaves them off
+/// by default, and the `ON DELETE CASCADE` of translations, portions,
+/// recipes and nutrients would never fire.
+#[derive(Debug)]
+pub struct ForeignKeys;
+
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for ForeignKeys {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+        diesel::sql_query("PRAGMA foreign_keys = ON")
+            .execute(conn)
+            .map(|_| ())
+            .map_err(r2d2::Error::QueryError)
+    }
+}
@@ -0,0 +1,504 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use simple_error::{SimpleError, SimpleResult};
+use time::macros::format_description;
+use time::{Date, Duration, OffsetDateTime};
+
+use crate::fixed;
+use crate::food_edit;
+use crate::language::Language;
+use crate::models::{DiaryEntry, Food, Meal, MealFood};
+use crate::nutrition::Totals;
+use crate::schema::{diary_entries, food_groups, foods, meal_food_relations, meals};
+use crate::search;
+use crate::units;
+
+/// Parts of the day food is logged in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Slot {
+    Breakfast,
+    Lunch,
+    Dinner,
+    Snack,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 4] = [Slot::Breakfast, Slot::Lunch, Slot::Dinner, Slot::Snack];
+
+    /// Value stored in `diary_entries.slot`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Slot::Breakfast => "breakfast",
+            Slot::Lunch => "lunch",
+            Slot::Dinner => "dinner",
+            Slot::Snack => "snack",
+        }
+    }
+
+    pub fn from_value(value: &str) -> Option<Slot> {
+        Slot::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Slot::Breakfast => "Breakfast",
+            Slot::Lunch => "Lunch",
+            Slot::Dinner => "Dinner",
+            Slot::Snack => "Snacks",
+        }
+    }
+}
+
+/// Days back that items are suggested for logging again.
+const RECENT_DAYS: i64 = 14;
+
+/// Most items suggested for logging again.
+const RECENT_ITEMS: usize = 10;
+
+/// Most of a food that can be logged at once, 100 kg in hundredths of a gram.
+/// Larger amounts are mistakes, and would overflow the totals.
+const MAX_GRAMS: i32 = 10_000_000;
+
+/// Most servings of a meal that can be logged at once, in hundredths.
+const MAX_SERVINGS: i32 = 10_000;
+
+/// Name of the group of foods that don't belong to one, like recipes.
+const NO_GROUP: &str = "Other";
+
+/// The current date where the server is, or in UTC if the local time zone
+/// can't be found out.
+pub fn today() -> Date {
+    OffsetDateTime::now_local()
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+        .date()
+}
+
+/// Parses a date like "2024-01-15".
+pub fn parse_date(input: &str) -> SimpleResult<Date> {
+    Date::parse(input.trim(), format_description!("[year]-[month]-[day]"))
+        .map_err(|_| SimpleError::new(format!("\"{}\" is not a date", input.trim())))
+}
+
+/// A diary entry with what was eaten.
+pub struct Logged {
+    pub entry: DiaryEntry,
+    /// Name of the food or meal.
+    pub name: String,
+    /// The foods eaten with their weights in hundredths of a gram. A meal
+    /// has its foods scaled to the number of servings.
+    pub foods: Vec<(Food, i32)>,
+}
+
+impl Logged {
+    pub fn totals(&self) -> Totals {
+        self.foods
+            .iter()
+            .fold(Totals::default(), |totals, (food, grams)| {
+                totals.add(food, *grams)
+            })
+    }
+
+    pub fn slot(&self) -> Option<Slot> {
+        Slot::from_value(&self.entry.slot)
+    }
+}
+
+/// Everything logged on a day.
+pub struct Day {
+    pub date: Date,
+    /// Entries in the order they were logged.
+    pub entries: Vec<Logged>,
+    pub totals: Totals,
+    /// Energy in hundredths of a kcal per food group, the largest first.
+    pub energy_by_group: Vec<(String, i32)>,
+}
+
+impl Day {
+    pub fn in_slot(&self, slot: Slot) -> impl Iterator<Item = &Logged> {
+        self.entries.iter().filter(move |e| e.slot() == Some(slot))
+    }
+
+    pub fn slot_totals(&self, slot: Slot) -> Totals {
+        self.in_slot(slot)
+            .fold(Totals::default(), |totals, logged| {
+                totals.plus(&logged.totals())
+            })
+    }
+}
+
+/// Names and contents of the meals looked up so far, by id.
+pub(crate) type MealCache = HashMap<i32, (String, Vec<(MealFood, Food)>)>;
+
+/// Name of a food or meal and the foods eaten with their weights in
+/// hundredths of a gram.
+pub(crate) type EntryFoods = (String, Vec<(Food, i32)>);
+
+/// What `quantity` of a food or of a meal, as stored in an entry, is made of.
+/// A meal has its foods scaled to the number of servings. `None` if the entry
+/// has neither.
+pub(crate) fn entry_foods(
+    conn: &mut SqliteConnection,
+    food_id: Option<i32>,
+    meal_id: Option<i32>,
+    quantity: i32,
+    language: Language,
+    meal_contents: &mut MealCache,
+) -> QueryResult<Option<EntryFoods>> {
+    Ok(Some(match (food_id, meal_id) {
+        (Some(food_id), _) => {
+            let mut food: Food = foods::table
+                .find(food_id)
+                .select(Food::as_select())
+                .first(conn)?;
+            search::translate(conn, &mut food, language)?;
+            (food.name.clone(), vec![(food, quantity)])
+        }
+        (None, Some(meal_id)) => {
+            let (name, contents) = match meal_contents.entry(meal_id) {
+                Entry::Occupied(loaded) => loaded.into_mut(),
+                Entry::Vacant(missing) => {
+                    let name = meals::table.find(meal_id).select(meals::name).first(conn)?;
+                    let contents = crate::meals::contents(conn, meal_id, language)?;
+                    missing.insert((name, contents))
+                }
+            };
+            let foods = contents
+                .iter()
+                .map(|(item, food)| {
+                    let grams = i64::from(item.grams) * i64::from(quantity) / fixed::SCALE as i64;
+                    (food.clone(), fixed::saturate(grams))
+                })
+                .collect();
+            (name.clone(), foods)
+        }
+        (None, None) => return Ok(None),
+    }))
+}
+
+/// Looks up the foods and meals of diary entries, named in `language`.
+fn load(
+    conn: &mut SqliteConnection,
+    entries: Vec<DiaryEntry>,
+    language: Language,
+) -> QueryResult<Vec<Logged>> {
+    let mut meal_contents = MealCache::new();
+    let mut logged = vec![];
+    for entry in entries {
+        let Some((name, foods)) = entry_foods(
+            conn,
+            entry.food_id,
+            entry.meal_id,
+            entry.quantity,
+            language,
+            &mut meal_contents,
+        )?
+        else {
+            continue;
+        };
+        logged.push(Logged { entry, name, foods });
+    }
+    Ok(logged)
+}
+
+/// What the user logged on `date`, with foods named in `language`.
+pub fn day(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    date: Date,
+    language: Language,
+) -> QueryResult<Day> {
+    let entries: Vec<DiaryEntry> = diary_entries::table
+        .filter(diary_entries::user_id.eq(user_id))
+        .filter(diary_entries::date.eq(date))
+        .order(diary_entries::id)
+        .select(DiaryEntry::as_select())
+        .load(conn)?;
+    let entries = load(conn, entries, language)?;
+
+    let group_names: HashMap<i32, String> = food_groups::table
+        .select((food_groups::id, food_groups::name))
+        .load(conn)?
+        .into_iter()
+        .collect();
+    let mut totals = Totals::default();
+    let mut energy: HashMap<&str, i32> = HashMap::new();
+    for (food, grams) in entries.iter().flat_map(|e| &e.foods) {
+        totals = totals.add(food, *grams);
+        let group = food
+            .food_group_id
+            .and_then(|id| group_names.get(&id))
+            .map_or(NO_GROUP, String::as_str);
+        *energy.entry(group).or_default() += units::scale(food.calories, *grams);
+    }
+    let mut energy_by_group: Vec<(String, i32)> = energy
+        .into_iter()
+        .filter(|(_, calories)| *calories > 0)
+        .map(|(group, calories)| (group.to_owned(), calories))
+        .collect();
+    energy_by_group.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    Ok(Day {
+        date,
+        entries,
+        totals,
+        energy_by_group,
+    })
+}
+
+/// What the user logged from `from` to `to`, both included, in date order
+/// and the order it was logged each day, with foods named in `language`.
+pub fn between(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    from: Date,
+    to: Date,
+    language: Language,
+) -> QueryResult<Vec<Logged>> {
+    let entries: Vec<DiaryEntry> = diary_entries::table
+        .filter(diary_entries::user_id.eq(user_id))
+        .filter(diary_entries::date.between(from, to))
+        .order((diary_entries::date, diary_entries::id))
+        .select(DiaryEntry::as_select())
+        .load(conn)?;
+    load(conn, entries, language)
+}
+
+/// Nutrients eaten on each day from `from` to `to`, both included, in date
+/// order. Days with nothing logged are left out.
+pub fn daily_totals(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    from: Date,
+    to: Date,
+) -> QueryResult<Vec<(Date, Totals)>> {
+    Ok(totals_by_day(&between(
+        conn,
+        user_id,
+        from,
+        to,
+        Language::default(),
+    )?))
+}
+
+/// Adds up what was logged by day. `logged` has to be in date order.
+pub fn totals_by_day(logged: &[Logged]) -> Vec<(Date, Totals)> {
+    let mut days: Vec<(Date, Totals)> = vec![];
+    for logged in logged {
+        match days.last_mut() {
+            Some((date, totals)) if *date == logged.entry.date => {
+                *totals = totals.clone().plus(&logged.totals());
+            }
+            _ => days.push((logged.entry.date, logged.totals())),
+        }
+    }
+    days
+}
+
+/// Foods and meals the user logged in the days before `date`, the latest
+/// first, each with the same amount only once.
+pub fn recent(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    date: Date,
+    language: Language,
+) -> QueryResult<Vec<Logged>> {
+    let entries: Vec<DiaryEntry> = diary_entries::table
+        .filter(diary_entries::user_id.eq(user_id))
+        .filter(diary_entries::date.lt(date))
+        .filter(diary_entries::date.ge(date - Duration::days(RECENT_DAYS)))
+        .order((diary_entries::date.desc(), diary_entries::id.desc()))
+        .select(DiaryEntry::as_select())
+        .load(conn)?;
+
+    let mut distinct: Vec<DiaryEntry> = vec![];
+    for entry in entries {
+        let same = |e: &DiaryEntry| {
+            (e.food_id, e.meal_id, e.quantity) == (entry.food_id, entry.meal_id, entry.quantity)
+        };
+        if !distinct.iter().any(same) {
+            distinct.push(entry);
+        }
+        if distinct.len() == RECENT_ITEMS {
+            break;
+        }
+    }
+    load(conn, distinct, language)
+}
+
+/// An entry of the user, `None` if there is no such entry or it belongs to
+/// someone else.
+pub fn find(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    entry_id: i32,
+) -> QueryResult<Option<DiaryEntry>> {
+    diary_entries::table
+        .find(entry_id)
+        .filter(diary_entries::user_id.eq(user_id))
+        .select(DiaryEntry::as_select())
+        .first(conn)
+        .optional()
+}
+
+/// Hundredths of a gram in `amount` of a food, e.g. "2 dl".
+pub(crate) fn food_quantity(
+    conn: &mut SqliteConnection,
+    food: &Food,
+    amount: &str,
+) -> SimpleResult<i32> {
+    let parsed = units::parse(amount)?;
+    let portions = food_edit::portions(conn, food.id).map_err(SimpleError::from)?;
+    let grams = units::to_grams(&parsed, food, &portions)?;
+    if grams <= 0 {
+        return Err(SimpleError::new("The amount has to be more than 0"));
+    }
+    if grams > MAX_GRAMS {
+        return Err(SimpleError::new("The amount can be at most 100 kg"));
+    }
+    Ok(grams)
+}
+
+/// Hundredths of a serving in a number of servings, e.g. "0.5".
+pub(crate) fn meal_quantity(servings: &str) -> SimpleResult<i32> {
+    let quantity = fixed::parse(servings)?;
+    if quantity <= 0 {
+        return Err(SimpleError::new("The servings have to be more than 0"));
+    }
+    if quantity > MAX_SERVINGS {
+        return Err(SimpleError::new("There can be at most 100 servings"));
+    }
+    Ok(quantity)
+}
+
+/// Logs `amount` of a food, e.g. "2 dl".
+pub fn log_food(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    date: Date,
+    slot: Slot,
+    food: &Food,
+    amount: &str,
+) -> SimpleResult<()> {
+    let grams = food_quantity(conn, food, amount)?;
+    diesel::insert_into(diary_entries::table)
+        .values((
+            diary_entries::user_id.eq(user_id),
+            diary_entries::date.eq(date),
+            diary_entries::slot.eq(slot.as_str()),
+            diary_entries::food_id.eq(food.id),
+            diary_entries::amount.eq(amount.trim()),
+            diary_entries::quantity.eq(grams),
+        ))
+        .execute(conn)
+        .map_err(SimpleError::from)?;
+    Ok(())
+}
+
+/// Logs a number of servings, e.g. "0.5", of a saved meal.
+pub fn log_meal(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    date: Date,
+    slot: Slot,
+    meal: &Meal,
+    servings: &str,
+) -> SimpleResult<()> {
+    let quantity = meal_quantity(servings)?;
+    diesel::insert_into(diary_entries::table)
+        .values((
+            diary_entries::user_id.eq(user_id),
+            diary_entries::date.eq(date),
+            diary_entries::slot.eq(slot.as_str()),
+            diary_entries::meal_id.eq(meal.id),
+            diary_entries::amount.eq(servings.trim()),
+            diary_entries::quantity.eq(quantity),
+        ))
+        .execute(conn)
+        .map_err(SimpleError::from)?;
+    Ok(())
+}
+
+/// Logs an earlier entry again on `date`, in the same part of the day.
+pub fn relog(conn: &mut SqliteConnection, entry: &DiaryEntry, date: Date) -> QueryResult<()> {
+    diesel::insert_into(diary_entries::table)
+        .values((
+            diary_entries::user_id.eq(entry.user_id),
+            diary_entries::date.eq(date),
+            diary_entries::slot.eq(&entry.slot),
+            diary_entries::food_id.eq(entry.food_id),
+            diary_entries::meal_id.eq(entry.meal_id),
+            diary_entries::amount.eq(&entry.amount),
+            diary_entries::quantity.eq(entry.quantity),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Logs everything from one part of the day `from` again on `to`, and
+/// returns the number of entries copied.
+pub fn copy_slot(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    slot: Slot,
+    from: Date,
+    to: Date,
+) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let entries: Vec<DiaryEntry> = diary_entries::table
+            .filter(diary_entries::user_id.eq(user_id))
+            .filter(diary_entries::date.eq(from))
+            .filter(diary_entries::slot.eq(slot.as_str()))
+            .order(diary_entries::id)
+            .select(DiaryEntry::as_select())
+            .load(conn)?;
+        for entry in &entries {
+            relog(conn, entry, to)?;
+        }
+        Ok(entries.len())
+    })
+}
+
+pub fn remove(conn: &mut SqliteConnection, user_id: i32, entry_id: i32) -> QueryResult<()> {
+    diesel::delete(diary_entries::table.find(entry_id))
+        .filter(diary_entries::user_id.eq(user_id))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Replaces the entries of a meal with entries of its foods, so that the
+/// diary still says what was eaten once the meal is gone.
+pub fn expand_meal(conn: &mut SqliteConnection, meal_id: i32) -> QueryResult<()> {
+    let entries: Vec<DiaryEntry> = diary_entries::table
+        .filter(diary_entries::meal_id.eq(meal_id))
+        .order(diary_entries::id)
+        .select(DiaryEntry::as_select())
+        .load(conn)?;
+    let items: Vec<MealFood> = meal_food_relations::table
+        .filter(meal_food_relations::meal_id.eq(meal_id))
+        .order(crate::meals::added_order())
+        .select(MealFood::as_select())
+        .load(conn)?;
+
+    for entry in &entries {
+        for item in &items {
+            let grams = i64::from(item.grams) * i64::from(entry.quantity) / fixed::SCALE as i64;
+            let grams = fixed::saturate(grams);
+            diesel::insert_into(diary_entries::table)
+                .values((
+                    diary_entries::user_id.eq(entry.user_id),
+                    diary_entries::date.eq(entry.date),
+                    diary_entries::slot.eq(&entry.slot),
+                    diary_entries::food_id.eq(item.food_id),
+                    diary_entries::amount.eq(fixed::format_short(grams)),
+                    diary_entries::quantity.eq(grams),
+                ))
+                .execute(conn)?;
+        }
+    }
+    diesel::delete(diary_entries::table)
+        .filter(diary_entries::meal_id.eq(meal_id))
+        .execute(conn)?;
+    Ok(())
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use simple_error::{SimpleError, SimpleResult};
+
+use crate::fixed;
+use crate::food_edit;
+use crate::models::Food;
+use crate::recipes;
+use crate::schema::{
+    diary_entries, food_names, food_nutrients, food_portions, foods, meal_food_relations,
+    planned_entries, recipe_ingredients,
+};
+use crate::search::{fold, words};
+use crate::visibility::Visibility;
+
+/// Names at least this similar, from 0 to 1, may be the same food.
+pub const NAME_SIMILARITY: f64 = 0.85;
+
+/// Largest mean relative difference of the nutrients of two foods that are
+/// considered the same.
+pub const NUTRIENT_DISTANCE: f64 = 0.15;
+
+/// Words shared by more foods than this, like "rå" or "kokt", don't say
+/// anything about two foods being the same and aren't used to pair them up.
+const COMMON_WORD: usize = 200;
+
+/// Two foods that are probably the same.
+pub struct Candidate {
+    pub first: Food,
+    pub second: Food,
+    /// Similarity of the normalized names, 1 for the same words.
+    pub name_similarity: f64,
+    /// Mean relative difference of the nutrients both foods have.
+    pub nutrient_distance: f64,
+}
+
+impl Candidate {
+    /// Higher for pairs more likely to be duplicates.
+    pub fn score(&self) -> f64 {
+        self.name_similarity * (1.0 - self.nutrient_distance)
+    }
+}
+
+/// Folded words of a name in alphabetical order, so that "Kyckling, bröst"
+/// and "bröst kyckling" are the same.
+fn normalize(name: &str) -> String {
+    let mut words: Vec<String> = words(name).map(fold).collect();
+    words.sort();
+    words.join(" ")
+}
+
+/// Whether anything rules out two foods being the same before comparing
+/// them. An imported database doesn't list a food twice, and names with
+/// different numbers, like "fett 3%" and "fett 0,5%", are different foods.
+fn distinct(a: &Food, b: &Food, a_name: &str, b_name: &str) -> bool {
+    let numbers = |name: &str| -> Vec<String> {
+        name.split(' ')
+            .filter(|w| w.chars().any(|c| c.is_ascii_digit()))
+            .map(str::to_owned)
+            .collect()
+    };
+
+    (a.owner_id.is_none() && b.owner_id.is_none() && a.data_source_id == b.data_source_id)
+        || numbers(a_name) != numbers(b_name)
+}
+
+/// Mean of `|a - b| / max(|a|, |b|)` over the nutrients known for both foods.
+/// Differences below one unit count as none, so 0.1 g and 0.3 g of fiber are
+/// the same.
+pub fn nutrient_distance(a: &Food, b: &Food) -> f64 {
+    let pairs = [
+        (Some(a.calories), Some(b.calories)),
+        (Some(a.protein), Some(b.protein)),
+        (a.fat, b.fat),
+        (a.carbohydrates, b.carbohydrates),
+        (a.fiber, b.fiber),
+    ];
+
+    let distances: Vec<f64> = pairs
+        .into_iter()
+        .filter_map(|pair| match pair {
+            (Some(x), Some(y)) => {
+                let diff = (x - y).abs();
+                let largest = x.abs().max(y.abs()).max(crate::fixed::SCALE);
+                Some(if diff < crate::fixed::SCALE {
+                    0.0
+                } else {
+                    f64::from(diff) / f64::from(largest)
+                })
+            }
+            _ => None,
+        })
+        .collect();
+
+    distances.iter().sum::<f64>() / distances.len() as f64
+}
+
+/// Whether a food may be merged, which imported and public foods may.
+/// Private and household foods are left alone since merging them would
+/// change what their owners can see.
+pub fn mergeable(food: &Food) -> bool {
+    food.owner_id.is_none() || food.visibility == Visibility::Public.as_str()
+}
+
+/// Finds up to `limit` pairs of [`mergeable`] foods from different sources
+/// with similar names and nutrients, most likely duplicates first.
+pub fn find(conn: &mut SqliteConnection, limit: usize) -> QueryResult<Vec<Candidate>> {
+    let foods: Vec<Food> = foods::table
+        .filter(
+            foods::owner_id
+                .is_null()
+                .or(foods::visibility.eq(Visibility::Public.as_str())),
+        )
+        .select(Food::as_select())
+        .load(conn)?;
+    let names: Vec<String> = foods.iter().map(|f| normalize(&f.name)).collect();
+
+    // Only foods sharing a word are compared, comparing all pairs is too slow
+    let mut by_word: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, name) in names.iter().enumerate() {
+        for word in name.split(' ').filter(|w| w.chars().count() >= 3) {
+            let foods = by_word.entry(word).or_default();
+            if foods.last() != Some(&i) {
+                foods.push(i);
+            }
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = by_word
+        .values()
+        .filter(|foods| foods.len() <= COMMON_WORD)
+        .flat_map(|foods| {
+            foods
+                .iter()
+                .enumerate()
+                .flat_map(move |(n, &i)| foods[n + 1..].iter().map(move |&j| (i, j)))
+        })
+        .collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+
+    let mut candidates: Vec<(usize, usize, f64, f64)> = pairs
+        .into_iter()
+        .filter_map(|(i, j)| {
+            if distinct(&foods[i], &foods[j], &names[i], &names[j]) {
+                return None;
+            }
+            let name_similarity = strsim::normalized_levenshtein(&names[i], &names[j]);
+            if name_similarity < NAME_SIMILARITY {
+                return None;
+            }
+            let distance = nutrient_distance(&foods[i], &foods[j]);
+            (distance <= NUTRIENT_DISTANCE).then_some((i, j, name_similarity, distance))
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        let score = |c: &(usize, usize, f64, f64)| c.2 * (1.0 - c.3);
+        score(b)
+            .total_cmp(&score(a))
+            .then((a.0, a.1).cmp(&(b.0, b.1)))
+    });
+    candidates.truncate(limit);
+
+    Ok(candidates
+        .into_iter()
+        .map(|(i, j, name_similarity, nutrient_distance)| Candidate {
+            first: foods[i].clone(),
+            second: foods[j].clone(),
+            name_similarity,
+            nutrient_distance,
+        })
+        .collect())
+}
+
+/// Merges `duplicate` into `survivor`. Meals, recipes and diary entries of the
+/// duplicate get the survivor instead, and the survivor takes over nutrients,
+/// translations, portions, density and the barcode it is missing. The
+/// duplicate is then deleted. Both foods have to be [`mergeable`], and the
+/// merge is refused if it would make a recipe contain itself.
+pub fn merge(conn: &mut SqliteConnection, survivor_id: i32, duplicate_id: i32) -> SimpleResult<()> {
+    // The reason the merge is refused, checked before anything is changed
+    let refused: QueryResult<Option<String>> = conn.transaction(|conn| {
+        let survivor: Food = foods::table
+            .find(survivor_id)
+            .select(Food::as_select())
+            .first(conn)?;
+        let duplicate: Food = foods::table
+            .find(duplicate_id)
+            .select(Food::as_select())
+            .first(conn)?;
+        for food in [&survivor, &duplicate] {
+            if !mergeable(food) {
+                return Ok(Some(format!(
+                    "{} is private to its owner and can't be merged",
+                    food.name
+                )));
+            }
+        }
+
+        // Recipes using the duplicate get the survivor, which mustn't contain
+        // them. The survivor itself just loses the duplicate, see below.
+        let using_duplicate: Vec<i32> = recipe_ingredients::table
+            .filter(recipe_ingredients::food_id.eq(duplicate_id))
+            .filter(recipe_ingredients::recipe_id.ne(survivor_id))
+            .select(recipe_ingredients::recipe_id)
+            .distinct()
+            .load(conn)?;
+        for recipe_id in using_duplicate {
+            if recipes::contains(conn, survivor_id, recipe_id)? {
+                return Ok(Some(format!(
+                    "{} contains a recipe using {}, merging them would make it contain itself",
+                    survivor.name, duplicate.name
+                )));
+            }
+        }
+
+        // A meal can only contain a food once, meals with both get the sum
+        let survivor_meals: Vec<(i32, i32)> = meal_food_relations::table
+            .filter(meal_food_relations::food_id.eq(survivor_id))
+            .select((meal_food_relations::meal_id, meal_food_relations::grams))
+            .load(conn)?;
+        for (meal_id, grams) in &survivor_meals {
+            let duplicate_grams: Option<i32> = meal_food_relations::table
+                .find((duplicate_id, meal_id))
+                .select(meal_food_relations::grams)
+                .first(conn)
+                .optional()?;
+            if let Some(duplicate_grams) = duplicate_grams {
+                let total = grams + duplicate_grams;
+                diesel::update(meal_food_relations::table.find((survivor_id, meal_id)))
+                    .set((
+                        meal_food_relations::amount.eq(fixed::format_short(total)),
+                        meal_food_relations::grams.eq(total),
+                    ))
+                    .execute(conn)?;
+                diesel::delete(meal_food_relations::table.find((duplicate_id, meal_id)))
+                    .execute(conn)?;
+            }
+        }
+        diesel::update(meal_food_relations::table)
+            .filter(meal_food_relations::food_id.eq(duplicate_id))
+            .set(meal_food_relations::food_id.eq(survivor_id))
+            .execute(conn)?;
+
+        diesel::update(recipe_ingredients::table)
+            .filter(recipe_ingredients::food_id.eq(duplicate_id))
+            .set(recipe_ingredients::food_id.eq(survivor_id))
+            .execute(conn)?;
+        // A recipe can't contain itself
+        diesel::delete(recipe_ingredients::table)
+            .filter(recipe_ingredients::recipe_id.eq(survivor_id))
+            .filter(recipe_ingredients::food_id.eq(survivor_id))
+            .execute(conn)?;
+
+        diesel::update(diary_entries::table)
+            .filter(diary_entries::food_id.eq(duplicate_id))
+            .set(diary_entries::food_id.eq(survivor_id))
+            .execute(conn)?;
+        diesel::update(planned_entries::table)
+            .filter(planned_entries::food_id.eq(duplicate_id))
+            .set(planned_entries::food_id.eq(survivor_id))
+            .execute(conn)?;
+
+        let survivor_languages: Vec<String> = food_names::table
+            .filter(food_names::food_id.eq(survivor_id))
+            .select(food_names::language)
+            .load(conn)?;
+        diesel::update(food_names::table)
+            .filter(food_names::food_id.eq(duplicate_id))
+            .filter(food_names::language.ne_all(survivor_languages))
+            .set(food_names::food_id.eq(survivor_id))
+            .execute(conn)?;
+
+        let survivor_portions: Vec<String> = food_portions::table
+            .filter(food_portions::food_id.eq(survivor_id))
+            .select(food_portions::name)
+            .load(conn)?;
+        diesel::update(food_portions::table)
+            .filter(food_portions::food_id.eq(duplicate_id))
+            .filter(food_portions::name.ne_all(survivor_portions))
+            .set(food_portions::food_id.eq(survivor_id))
+            .execute(conn)?;
+
+        let survivor_nutrients: Vec<i32> = food_nutrients::table
+            .filter(food_nutrients::food_id.eq(survivor_id))
+            .select(food_nutrients::nutrient_id)
+            .load(conn)?;
+        diesel::update(food_nutrients::table)
+            .filter(food_nutrients::food_id.eq(duplicate_id))
+            .filter(food_nutrients::nutrient_id.ne_all(survivor_nutrients))
+            .set(food_nutrients::food_id.eq(survivor_id))
+            .execute(conn)?;
+
+        // Cleared first since barcodes are unique
+        diesel::update(foods::table.find(duplicate_id))
+            .set(foods::barcode.eq(None::<String>))
+            .execute(conn)?;
+        diesel::update(foods::table.find(survivor_id))
+            .set((
+                foods::fat.eq(survivor.fat.or(duplicate.fat)),
+                foods::carbohydrates.eq(survivor.carbohydrates.or(duplicate.carbohydrates)),
+                foods::fiber.eq(survivor.fiber.or(duplicate.fiber)),
+                foods::barcode.eq(survivor.barcode.or(duplicate.barcode)),
+                foods::density.eq(survivor.density.or(duplicate.density)),
+            ))
+            .execute(conn)?;
+
+        food_edit::delete(conn, duplicate_id)?;
+        recipes::recompute(conn, survivor_id)?;
+        Ok(None)
+    });
+
+    match refused.map_err(SimpleError::from)? {
+        Some(reason) => Err(SimpleError::new(reason)),
+        None => Ok(()),
+    }
+}
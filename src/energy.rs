@@ -0,0 +1,160 @@
+use time::{Date, Duration};
+
+use crate::fixed;
+use crate::models::Profile;
+use crate::profile::{self, Activity, Sex};
+
+/// Energy stored in a kg of body weight, mostly fat.
+pub const KCAL_PER_KG: f64 = 7700.0;
+
+/// Days back that logged intake and weight are used to estimate expenditure.
+pub const WINDOW_DAYS: i64 = 28;
+
+/// Fewest days with logged intake in the window for an adaptive estimate.
+pub const MIN_LOGGED_DAYS: usize = 14;
+
+/// Fewest days between the first and last weight in the window for an
+/// adaptive estimate.
+pub const MIN_WEIGHT_SPAN: i64 = 14;
+
+/// Resting energy expenditure in kcal a day by the Mifflin-St Jeor equation.
+pub fn mifflin_st_jeor(sex: Sex, weight_kg: f64, height_cm: f64, age: i32) -> f64 {
+    let base = 10.0 * weight_kg + 6.25 * height_cm - 5.0 * f64::from(age);
+    match sex {
+        Sex::Male => base + 5.0,
+        Sex::Female => base - 161.0,
+    }
+}
+
+/// Resting energy expenditure in kcal a day by the Katch-McArdle equation,
+/// from the lean body mass.
+pub fn katch_mcardle(weight_kg: f64, body_fat_percent: f64) -> f64 {
+    let lean_mass = weight_kg * (1.0 - body_fat_percent / 100.0);
+    370.0 + 21.6 * lean_mass
+}
+
+/// Slope of the least squares line through `points`, `None` if all `x` are
+/// the same.
+pub(crate) fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// Expenditure worked out from what the user ate and how their weight
+/// changed.
+#[derive(Debug, PartialEq)]
+pub struct Adaptive {
+    /// Energy in hundredths of a kcal a day.
+    pub expenditure: i32,
+    /// Days of the window with logged intake.
+    pub logged_days: usize,
+    /// Trend of the weight in hundredths of a kg a week.
+    pub weekly_change: i32,
+}
+
+/// Estimates expenditure over the [`WINDOW_DAYS`] before `today` as the mean
+/// logged intake less the energy of the weight gained, with the weight change
+/// taken from a line fitted to the logged weights. `intake` is energy in
+/// hundredths of a kcal and `weights` in hundredths of a kg by day. `None`
+/// without enough data, see [`MIN_LOGGED_DAYS`] and [`MIN_WEIGHT_SPAN`].
+pub fn adaptive(intake: &[(Date, i32)], weights: &[(Date, i32)], today: Date) -> Option<Adaptive> {
+    let start = today - Duration::days(WINDOW_DAYS);
+    let logged: Vec<i32> = intake
+        .iter()
+        .filter(|(date, _)| (start..today).contains(date))
+        .map(|(_, calories)| *calories)
+        .collect();
+    if logged.len() < MIN_LOGGED_DAYS {
+        return None;
+    }
+
+    let weights: Vec<&(Date, i32)> = weights
+        .iter()
+        .filter(|(date, _)| (start..=today).contains(date))
+        .collect();
+    let (first, last) = (weights.first()?, weights.last()?);
+    if (last.0 - first.0).whole_days() < MIN_WEIGHT_SPAN {
+        return None;
+    }
+    let points: Vec<(f64, f64)> = weights
+        .iter()
+        .map(|&&(date, weight)| {
+            let day = (date - start).whole_days() as f64;
+            (day, f64::from(weight) / f64::from(fixed::SCALE))
+        })
+        .collect();
+    let kg_per_day = slope(&points)?;
+
+    let mean_intake =
+        logged.iter().map(|&c| f64::from(c)).sum::<f64>() / logged.len() as f64 / 100.0;
+    let expenditure = mean_intake - kg_per_day * KCAL_PER_KG;
+    Some(Adaptive {
+        expenditure: (expenditure * 100.0).round() as i32,
+        logged_days: logged.len(),
+        weekly_change: (kg_per_day * 7.0 * 100.0).round() as i32,
+    })
+}
+
+/// Energy expenditure of a user, all in hundredths of a kcal a day.
+#[derive(Debug, PartialEq)]
+pub struct Estimate {
+    /// Basal metabolic rate by Mifflin-St Jeor.
+    pub mifflin_st_jeor: i32,
+    /// Basal metabolic rate by Katch-McArdle, `None` without the body fat.
+    pub katch_mcardle: Option<i32>,
+    /// Total expenditure from the basal metabolic rate, by Katch-McArdle
+    /// when the body fat is known, and the activity level.
+    pub tdee: i32,
+    pub adaptive: Option<Adaptive>,
+    /// What to eat to change weight as the profile aims for, from the
+    /// adaptive expenditure when there is one, in whole kcal. Never less
+    /// than the basal metabolic rate.
+    pub suggestion: i32,
+}
+
+/// Estimates the expenditure of a user weighing `weight` hundredths of a kg.
+pub fn estimate(
+    profile: &Profile,
+    weight: i32,
+    intake: &[(Date, i32)],
+    weights: &[(Date, i32)],
+    today: Date,
+) -> Estimate {
+    let kg = f64::from(weight) / f64::from(fixed::SCALE);
+    let sex = Sex::from_value(&profile.sex).unwrap_or(Sex::Female);
+    let activity = Activity::from_value(&profile.activity).unwrap_or(Activity::Sedentary);
+    let hundredths = |kcal: f64| (kcal * 100.0).round() as i32;
+
+    let mifflin = mifflin_st_jeor(
+        sex,
+        kg,
+        f64::from(profile.height) / f64::from(fixed::SCALE),
+        profile::age(profile.birth_date, today),
+    );
+    let katch = profile
+        .body_fat
+        .map(|body_fat| katch_mcardle(kg, f64::from(body_fat) / f64::from(fixed::SCALE)));
+    let bmr = katch.unwrap_or(mifflin);
+    let tdee = bmr * activity.factor();
+
+    let adaptive = adaptive(intake, weights, today);
+    let expenditure = adaptive
+        .as_ref()
+        .map_or(tdee, |a| f64::from(a.expenditure) / 100.0);
+    let change = f64::from(profile.weekly_change) / f64::from(fixed::SCALE) * KCAL_PER_KG / 7.0;
+
+    Estimate {
+        mifflin_st_jeor: hundredths(mifflin),
+        katch_mcardle: katch.map(hundredths),
+        tdee: hundredths(tdee),
+        adaptive,
+        suggestion: hundredths((expenditure + change).max(bmr).round()),
+    }
+}
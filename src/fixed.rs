@@ -0,0 +1,82 @@
+use std::iter;
+
+use simple_error::{SimpleError, SimpleResult};
+
+/// Nutrient amounts are stored as integers counting hundredths of their unit,
+/// so 12.34 g of protein is stored as 1234.
+pub const SCALE: i32 = 100;
+
+/// Parses a non-negative decimal such as "12", "12.5" or "12,25" (decimal
+/// comma, as Swedish users tend to type it) into hundredths. Further decimals
+/// are rounded half up.
+pub fn parse(input: &str) -> SimpleResult<i32> {
+    let input = input.trim();
+    let (whole, frac) = input.split_once(['.', ',']).unwrap_or((input, ""));
+
+    if (whole.is_empty() && frac.is_empty())
+        || !whole.bytes().all(|b| b.is_ascii_digit())
+        || !frac.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(SimpleError::new(format!("\"{}\" is not a number", input)));
+    }
+
+    let too_large = || SimpleError::new(format!("{} is too large", input));
+    let whole: i32 = match whole {
+        "" => 0,
+        w => w.parse().map_err(|_| too_large())?,
+    };
+    let mut digits = frac
+        .bytes()
+        .map(|b| (b - b'0') as i32)
+        .chain(iter::repeat(0));
+    let hundredths = digits.next().unwrap() * 10 + digits.next().unwrap();
+    let round_up = (digits.next().unwrap() >= 5) as i32;
+
+    whole
+        .checked_mul(SCALE)
+        .and_then(|v| v.checked_add(hundredths + round_up))
+        .ok_or_else(too_large)
+}
+
+/// Formats hundredths with `decimals` (at most 2) decimals, rounding half away
+/// from zero.
+pub fn format(value: i32, decimals: u32) -> String {
+    let decimals = decimals.min(2);
+    let step = 10i64.pow(2 - decimals);
+    let rounded = (i64::from(value).abs() + step / 2) / step;
+    let sign = if value < 0 && rounded != 0 { "-" } else { "" };
+
+    if decimals == 0 {
+        format!("{}{}", sign, rounded)
+    } else {
+        let per_unit = 10i64.pow(decimals);
+        format!(
+            "{}{}.{:0width$}",
+            sign,
+            rounded / per_unit,
+            rounded % per_unit,
+            width = decimals as usize
+        )
+    }
+}
+
+/// Formats hundredths with as few decimals as needed, e.g. "12" or "12.5".
+pub fn format_short(value: i32) -> String {
+    format(value, 2)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_owned()
+}
+
+/// Narrows a value computed in `i64`, clamping it to the range of `i32`.
+pub fn saturate(value: i64) -> i32 {
+    i32::try_from(value).unwrap_or(if value < 0 { i32::MIN } else { i32::MAX })
+}
+
+/// Like [`parse`], but blank or missing input means the value is unknown.
+pub fn parse_optional(input: Option<&str>) -> SimpleResult<Option<i32>> {
+    match input.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(input) => parse(input).map(Some),
+    }
+}
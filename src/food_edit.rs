@@ -0,0 +1,169 @@
+use diesel::prelude::*;
+
+use crate::models::{Food, FoodPortion, User};
+use crate::recipes;
+use crate::schema::{
+    diary_entries, food_names, food_nutrients, food_portions, foods, meal_food_relations, meals,
+    planned_entries, recipe_ingredients, users,
+};
+
+/// Whether `user` may edit or delete `food`. Foods belong to the user who
+/// created them, imported foods have no owner and only admins may change them.
+pub fn can_edit(user: &User, food: &Food) -> bool {
+    user.admin || food.owner_id == Some(user.id)
+}
+
+/// Loads a food that the user may edit, `None` if it doesn't exist or the user
+/// isn't allowed to change it.
+pub fn find_editable(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    food_id: i32,
+) -> QueryResult<Option<Food>> {
+    let user = users::table
+        .find(user_id)
+        .select(User::as_select())
+        .first(conn)?;
+    let food = foods::table
+        .find(food_id)
+        .select(Food::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(food.filter(|food| can_edit(&user, food)))
+}
+
+/// Portions of a food, by name.
+pub fn portions(conn: &mut SqliteConnection, food_id: i32) -> QueryResult<Vec<FoodPortion>> {
+    food_portions::table
+        .filter(food_portions::food_id.eq(food_id))
+        .order(food_portions::name)
+        .select(FoodPortion::as_select())
+        .load(conn)
+}
+
+/// Where a food is used, which changes when it is deleted.
+#[derive(Debug, Default, PartialEq)]
+pub struct Usage {
+    pub meals: i64,
+    pub recipes: i64,
+    pub diary_entries: i64,
+    pub planned_entries: i64,
+    /// Users besides the one deleting the food whose things use it. Their
+    /// things are never changed, so the food can't be deleted then.
+    pub other_users: usize,
+}
+
+impl Usage {
+    pub fn is_unused(&self) -> bool {
+        *self == Usage::default()
+    }
+}
+
+/// Users other than `user_id` whose meals, recipes, diaries or plans use a
+/// food.
+/// Meals without an owner count as someone else's.
+fn other_users(conn: &mut SqliteConnection, food_id: i32, user_id: i32) -> QueryResult<usize> {
+    let mut users: Vec<Option<i32>> = meal_food_relations::table
+        .inner_join(meals::table)
+        .filter(meal_food_relations::food_id.eq(food_id))
+        .select(meals::owner_id)
+        .load(conn)?;
+    let recipe_ids: Vec<i32> = recipe_ingredients::table
+        .filter(recipe_ingredients::food_id.eq(food_id))
+        .select(recipe_ingredients::recipe_id)
+        .load(conn)?;
+    users.extend(
+        foods::table
+            .filter(foods::id.eq_any(recipe_ids))
+            .select(foods::owner_id)
+            .load::<Option<i32>>(conn)?,
+    );
+    users.extend(
+        diary_entries::table
+            .filter(diary_entries::food_id.eq(food_id))
+            .select(diary_entries::user_id)
+            .load::<i32>(conn)?
+            .into_iter()
+            .map(Some),
+    );
+    users.extend(
+        planned_entries::table
+            .filter(planned_entries::food_id.eq(food_id))
+            .select(planned_entries::user_id)
+            .load::<i32>(conn)?
+            .into_iter()
+            .map(Some),
+    );
+
+    users.retain(|&owner| owner != Some(user_id));
+    users.sort();
+    users.dedup();
+    Ok(users.len())
+}
+
+/// Where a food is used, as seen by the user about to delete it.
+pub fn usage(conn: &mut SqliteConnection, food_id: i32, user_id: i32) -> QueryResult<Usage> {
+    Ok(Usage {
+        meals: meal_food_relations::table
+            .filter(meal_food_relations::food_id.eq(food_id))
+            .count()
+            .get_result(conn)?,
+        recipes: recipes::recipe_count(conn, food_id)?,
+        diary_entries: diary_entries::table
+            .filter(diary_entries::food_id.eq(food_id))
+            .count()
+            .get_result(conn)?,
+        planned_entries: planned_entries::table
+            .filter(planned_entries::food_id.eq(food_id))
+            .count()
+            .get_result(conn)?,
+        other_users: other_users(conn, food_id, user_id)?,
+    })
+}
+
+/// Deletes a food together with its translations, portions, other nutrients
+/// and ingredients if it is a recipe, and removes it from every meal, recipe,
+/// diary and plan containing it. Check its [`usage`] first, the food mustn't
+/// be deleted while other users use it.
+pub fn delete(conn: &mut SqliteConnection, food_id: i32) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let used_in: Vec<i32> = recipe_ingredients::table
+            .filter(recipe_ingredients::food_id.eq(food_id))
+            .select(recipe_ingredients::recipe_id)
+            .distinct()
+            .load(conn)?;
+        diesel::delete(recipe_ingredients::table)
+            .filter(
+                recipe_ingredients::food_id
+                    .eq(food_id)
+                    .or(recipe_ingredients::recipe_id.eq(food_id)),
+            )
+            .execute(conn)?;
+        diesel::delete(crate::schema::recipes::table.find(food_id)).execute(conn)?;
+        diesel::delete(meal_food_relations::table)
+            .filter(meal_food_relations::food_id.eq(food_id))
+            .execute(conn)?;
+        diesel::delete(diary_entries::table)
+            .filter(diary_entries::food_id.eq(food_id))
+            .execute(conn)?;
+        diesel::delete(planned_entries::table)
+            .filter(planned_entries::food_id.eq(food_id))
+            .execute(conn)?;
+        diesel::delete(food_names::table)
+            .filter(food_names::food_id.eq(food_id))
+            .execute(conn)?;
+        diesel::delete(food_portions::table)
+            .filter(food_portions::food_id.eq(food_id))
+            .execute(conn)?;
+        diesel::delete(food_nutrients::table)
+            .filter(food_nutrients::food_id.eq(food_id))
+            .execute(conn)?;
+        diesel::delete(foods::table.find(food_id)).execute(conn)?;
+
+        for recipe_id in used_in {
+            recipes::recompute(conn, recipe_id)?;
+        }
+        Ok(())
+    })
+}
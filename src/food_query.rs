@@ -0,0 +1,178 @@
+use std::fmt;
+
+use simple_error::{SimpleError, SimpleResult};
+
+use crate::fixed;
+
+/// Nutrient columns of `foods` that can be filtered on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Nutrient {
+    Calories,
+    Protein,
+    Fat,
+    Carbohydrates,
+    Fiber,
+}
+
+impl Nutrient {
+    fn from_name(name: &str) -> Option<Nutrient> {
+        match name.to_lowercase().as_str() {
+            "kcal" | "calories" | "energy" | "energi" => Some(Nutrient::Calories),
+            "protein" => Some(Nutrient::Protein),
+            "fat" | "fett" => Some(Nutrient::Fat),
+            "carbs" | "carbohydrates" | "kolhydrater" => Some(Nutrient::Carbohydrates),
+            "fiber" | "fibre" | "fibrer" => Some(Nutrient::Fiber),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Nutrient::Calories => "kcal",
+            Nutrient::Protein => "protein",
+            Nutrient::Fat => "fat",
+            Nutrient::Carbohydrates => "carbs",
+            Nutrient::Fiber => "fiber",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Equal => "=",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    /// Compares a nutrient per 100 g, `value` is in hundredths like the columns.
+    Nutrient {
+        nutrient: Nutrient,
+        comparison: Comparison,
+        value: i32,
+    },
+    /// Foods in a group whose name starts with this, ignoring case and accents.
+    Group(String),
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Nutrient {
+                nutrient,
+                comparison,
+                value,
+            } => {
+                write!(
+                    f,
+                    "{}{}{}",
+                    nutrient.name(),
+                    comparison.symbol(),
+                    fixed::format_short(*value)
+                )
+            }
+            Filter::Group(name) => write!(f, "group:{}", name),
+        }
+    }
+}
+
+/// A parsed search box, e.g. `kyckling protein>20 kcal<150 group:kött`.
+#[derive(Debug, Default, PartialEq)]
+pub struct FoodQuery {
+    /// The words that aren't filters, matched against food names.
+    pub text: String,
+    pub filters: Vec<Filter>,
+}
+
+impl FoodQuery {
+    /// The query with its text replaced, used to suggest typo corrections
+    /// without dropping the filters.
+    pub fn with_text(&self, text: &str) -> String {
+        let mut parts = vec![text.to_owned()];
+        parts.extend(self.filters.iter().map(|f| f.to_string()));
+        parts.join(" ")
+    }
+}
+
+fn parse_comparison(token: &str, at: usize) -> SimpleResult<Filter> {
+    let (name, rest) = token.split_at(at);
+    let nutrient = Nutrient::from_name(name).ok_or_else(|| {
+        SimpleError::new(format!(
+            "Unknown nutrient \"{}\" in \"{}\", use kcal, protein, fat, carbs or fiber",
+            name, token
+        ))
+    })?;
+
+    let (comparison, value) = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ]
+    .into_iter()
+    .find_map(|(symbol, comparison)| rest.strip_prefix(symbol).map(|v| (comparison, v)))
+    .unwrap();
+
+    if value.is_empty() {
+        return Err(SimpleError::new(format!(
+            "Missing value after \"{}\"",
+            token
+        )));
+    }
+    let value = fixed::parse(value)
+        .map_err(|_| SimpleError::new(format!("\"{}\" in \"{}\" is not a number", value, token)))?;
+
+    Ok(Filter::Nutrient {
+        nutrient,
+        comparison,
+        value,
+    })
+}
+
+/// Splits the search box into free text and filters. Filters are either a
+/// nutrient comparison such as `protein>20` or `fiber>=3`, or `group:<name>`.
+/// Anything else is free text.
+pub fn parse(input: &str) -> SimpleResult<FoodQuery> {
+    let mut text = vec![];
+    let mut filters = vec![];
+
+    for token in input.split_whitespace() {
+        if let Some(at) = token.find(['<', '>', '=']) {
+            filters.push(parse_comparison(token, at)?);
+        } else if let Some((key, value)) = token.split_once(':') {
+            if !key.eq_ignore_ascii_case("group") {
+                return Err(SimpleError::new(format!(
+                    "Unknown filter \"{}:\", use group:<name>",
+                    key
+                )));
+            }
+            if value.is_empty() {
+                return Err(SimpleError::new("Missing group name after \"group:\""));
+            }
+            filters.push(Filter::Group(value.to_owned()));
+        } else {
+            text.push(token);
+        }
+    }
+
+    Ok(FoodQuery {
+        text: text.join(" "),
+        filters,
+    })
+}
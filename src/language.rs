@@ -0,0 +1,46 @@
+use diesel::prelude::*;
+
+use crate::schema::users;
+
+/// Languages food names can be shown in. `foods.name` is in Swedish, the
+/// language of Livsmedelsverket, other languages are stored in `food_names`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Language {
+    #[default]
+    Swedish,
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::Swedish, Language::English];
+
+    /// ISO 639-1 code, as stored in `food_names.language` and `users.language`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::Swedish => "sv",
+            Language::English => "en",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Language> {
+        Language::ALL.into_iter().find(|l| l.code() == code)
+    }
+
+    /// Name of the language in itself.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::Swedish => "Svenska",
+            Language::English => "English",
+        }
+    }
+}
+
+pub fn set_for_user(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    language: Language,
+) -> QueryResult<usize> {
+    diesel::update(users::table.find(user_id))
+        .set(users::language.eq(language.code()))
+        .execute(conn)
+}
@@ -1,2 +1,26 @@
+pub mod barcode;
+pub mod db;
+pub mod diary;
+pub mod duplicates;
+pub mod energy;
+pub mod fixed;
+pub mod food_edit;
+pub mod food_query;
+pub mod language;
+pub mod meals;
+pub mod measurements;
+pub mod micronutrients;
 pub mod models;
+pub mod nutrition;
+pub mod planner;
+pub mod profile;
+pub mod recipes;
+pub mod reports;
 pub mod schema;
+pub mod search;
+pub mod simplex;
+pub mod sources;
+pub mod targets;
+pub mod units;
+pub mod visibility;
+pub mod week_plan;
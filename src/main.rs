@@ -9,24 +9,61 @@ use actix_web::{
 };
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use diesel::sql_types::{Bool, Nullable};
 use diesel::{
-    r2d2, ExpressionMethods, QueryDsl, RunQueryDsl, SqliteConnection,
-    TextExpressionMethods,
+    r2d2, ExpressionMethods, OptionalExtension, QueryDsl, QueryResult, RunQueryDsl,
+    SelectableHelper, SqliteConnection,
 };
 use maud::{html, Markup};
 use rand::distributions::Alphanumeric;
 use rand::Rng;
-use serde::Deserialize;
-use track_notes::models::{Food, User};
-use track_notes::schema::{foods, users};
+use serde::{Deserialize, Serialize};
+use simple_error::{SimpleError, SimpleResult};
+use time::Date;
+use track_notes::barcode;
+use track_notes::db;
+use track_notes::diary::{self, Day, Logged, Slot};
+use track_notes::duplicates;
+use track_notes::energy::{self, Estimate};
+use track_notes::fixed;
+use track_notes::food_edit;
+use track_notes::language::{self, Language};
+use track_notes::meals::{self, MealItem};
+use track_notes::measurements::{self, WeightUnit};
+use track_notes::micronutrients;
+use track_notes::models::{
+    DataSource, Food, FoodGroup, Meal, Measurement, Profile, Recipe, RecipeIngredient, Target, User,
+};
+use track_notes::nutrition::Totals;
+use track_notes::planner::{self, PoolItem};
+use track_notes::profile::{self, Activity, Sex};
+use track_notes::recipes;
+use track_notes::reports;
+use track_notes::schema::{data_sources, food_groups, food_portions, foods, users};
+use track_notes::search::{self, SearchPage, SearchParams, SearchResult, SortBy};
+use track_notes::sources::{self, SourceKind};
+use track_notes::targets::{self, Macro, WeekDay};
+use track_notes::units;
+use track_notes::visibility::{visible_to, Viewer, Visibility};
+use track_notes::week_plan::{self, PlanDay};
 use ui::layout;
 
-use crate::ui::{food_creator, food_searcher, sign_in_page, sign_up_page};
+use crate::ui::{
+    amount_result, barcode_lookup, barcode_match, barcode_not_found, body_details, body_page,
+    diary_day, diary_matches, diary_page, duplicates_page, food_creator, food_deleted, food_editor,
+    food_row, food_searcher, food_table, ingredient_matches, load_more_row, meal_header, meal_page,
+    meal_panel, meals_page, merge_result, micronutrients_page, plan_form, plan_result,
+    planner_matches, planner_page, portion_list, profile_details, profile_page, recipe_details,
+    recipe_page, recipes_page, report_page, search_error_row, sign_in_page, sign_up_page,
+    suggestion_row, targets_form, targets_page, toolbar, week_grid, week_page,
+};
 
 mod ui;
 
 type DbPool = r2d2::Pool<r2d2::ConnectionManager<SqliteConnection>>;
 
+diesel::sql_function!(fn coalesce(x: Nullable<Bool>, y: Bool) -> Bool);
+
 struct AppState<'a> {
     tracks: Vec<(&'a str, &'a str)>,
     db: DbPool,
@@ -51,17 +88,156 @@ struct LoginData {
     password: String,
 }
 
+/// Nutrient values are decimals as typed per 100 g, kcal for `calories` and
+/// grams for the rest. They are parsed with [`fixed::parse`], the optional
+/// ones may be left blank.
 #[derive(Deserialize)]
 struct CreateFoodData {
     name: String,
+    calories: String,
+    protein: String,
+    fat: Option<String>,
+    carbohydrates: Option<String>,
+    fiber: Option<String>,
+    /// Grams per ml, optional.
+    density: Option<String>,
+    barcode: Option<String>,
+    /// Left out when editing an imported food, which is always public.
+    visibility: Option<String>,
+}
+
+/// Nutrient values of a submitted food form, in hundredths per 100 g, and
+/// its density in hundredths of a gram per ml.
+struct FoodNutrients {
     calories: i32,
     protein: i32,
+    fat: Option<i32>,
+    carbohydrates: Option<i32>,
+    fiber: Option<i32>,
+    density: Option<i32>,
+}
+
+impl CreateFoodData {
+    fn nutrients(&self) -> SimpleResult<FoodNutrients> {
+        Ok(FoodNutrients {
+            calories: fixed::parse(&self.calories)?,
+            protein: fixed::parse(&self.protein)?,
+            fat: fixed::parse_optional(self.fat.as_deref())?,
+            carbohydrates: fixed::parse_optional(self.carbohydrates.as_deref())?,
+            fiber: fixed::parse_optional(self.fiber.as_deref())?,
+            density: fixed::parse_optional(self.density.as_deref())?,
+        })
+    }
+
+    fn visibility(&self) -> SimpleResult<Option<Visibility>> {
+        match self.visibility.as_deref() {
+            None => Ok(None),
+            Some(v) => Visibility::from_value(v)
+                .map(Some)
+                .ok_or_else(|| SimpleError::new(format!("Unknown visibility \"{}\"", v))),
+        }
+    }
+
+    /// Checks the form, the food has to have a name, which is saved trimmed.
+    fn validate(&self) -> SimpleResult<(FoodNutrients, Option<String>, Option<Visibility>)> {
+        if self.name.trim().is_empty() {
+            return Err(SimpleError::new("The food needs a name"));
+        }
+        Ok((self.nutrients()?, self.barcode()?, self.visibility()?))
+    }
+
+    /// The normalized barcode, `None` if left blank.
+    fn barcode(&self) -> SimpleResult<Option<String>> {
+        match self.barcode.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(code) => barcode::normalize(code).map(Some),
+        }
+    }
 }
 
 #[derive(Deserialize)]
+struct DeleteFoodData {
+    confirm: Option<bool>,
+}
+
+/// Status line for a food that couldn't be saved.
+fn food_save_error(e: diesel::result::Error) -> HttpResponse {
+    match e {
+        // Names and barcodes are unique per owner
+        diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            info,
+        ) if info.message().contains("barcode") => {
+            food_status(false, "There already is a food with that barcode")
+        }
+        diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            _,
+        ) => food_status(false, "There already is a food with that name"),
+        _ => food_status(false, "Failed"),
+    }
+}
+
+/// Status line of the food form.
+fn food_status(ok: bool, message: &str) -> HttpResponse {
+    let class = if ok {
+        "text-green-400 font-bold"
+    } else {
+        "text-red-400 font-bold"
+    };
+    let mut resp = HttpResponse::Ok();
+    resp.content_type(ContentType::html());
+    if ok {
+        // Lets the search results pick up the change
+        resp.insert_header(("HX-Trigger", "foods-changed"));
+    }
+    resp.body(html! { p id="status" class=(class) { (message) } }.into_string())
+}
+
+/// State of the food search form. It doubles as the query string of
+/// `/meal_builder` so that searches can be bookmarked.
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
 struct SearchData {
-    #[serde(alias = "search-name")]
+    #[serde(rename = "search-name")]
     search_name: String,
+    #[serde(rename = "trusted-only", skip_serializing_if = "Option::is_none")]
+    trusted_only: Option<String>,
+    #[serde(rename = "food-group", skip_serializing_if = "is_unset")]
+    food_group: Option<String>,
+    #[serde(skip_serializing_if = "is_unset")]
+    sort: Option<String>,
+    #[serde(skip_serializing_if = "is_unset")]
+    dir: Option<String>,
+    #[serde(skip_serializing)]
+    page: Option<i64>,
+}
+
+/// Empty form fields are left out of the search URL.
+fn is_unset(value: &Option<String>) -> bool {
+    value.as_deref().unwrap_or("").is_empty()
+}
+
+impl SearchData {
+    fn sort(&self) -> SortBy {
+        SortBy::from_param(self.sort.as_deref().unwrap_or(""))
+    }
+
+    fn descending(&self) -> bool {
+        match self.dir.as_deref() {
+            Some("desc") => true,
+            Some("asc") => false,
+            _ => self.sort().descending_by_default(),
+        }
+    }
+
+    /// Whether anything has been searched for or filtered on.
+    fn is_blank(&self) -> bool {
+        self.search_name.trim().is_empty()
+            && self.trusted_only.is_none()
+            && is_unset(&self.food_group)
+            && is_unset(&self.sort)
+    }
 }
 
 struct SessionData {
@@ -91,6 +267,13 @@ impl SessionData {
     }
 }
 
+fn load_user(conn: &mut SqliteConnection, user_id: i32) -> QueryResult<User> {
+    users::table
+        .find(user_id)
+        .select(User::as_select())
+        .first(conn)
+}
+
 impl AppState<'_> {
     pub fn new(db: DbPool) -> AppState<'static> {
         AppState {
@@ -194,44 +377,225 @@ async fn change_track(
 
 #[get("/meal_builder")]
 async fn meal_builder(
+    search: web::Query<SearchData>,
     data: web::Data<AppState<'static>>,
     session: Session,
 ) -> AwResult<HttpResponse> {
     match SessionData::from_session(&session) {
-        Some(session_data) => Ok(markup_to_resp(layout(html! {
-            (food_creator())
-            (food_searcher())
-        }))),
-        None => Ok(redirect("/")),
+        Some(session_data) if session_data.authenticated => {
+            let search = search.into_inner();
+            let page_data = data.clone();
+            let user_id = session_data.user_id;
+            let (groups, user) = web::block(move || {
+                let mut conn = page_data.db.get().expect("Couldnt get db conn from pool");
+                let groups = food_groups::table
+                    .order(food_groups::name)
+                    .load::<FoodGroup>(&mut conn)?;
+                let user = load_user(&mut conn, user_id)?;
+                QueryResult::Ok((groups, user))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            let table = if search.is_blank() {
+                food_table(search.sort(), search.descending(), html! {})
+            } else {
+                search_results(data, &search, user_id).await?
+            };
+
+            Ok(markup_to_resp(layout(html! {
+                (toolbar(&user))
+                (food_creator(None))
+                (barcode_lookup())
+                (meal_panel("", &[], None))
+                (food_searcher(&groups, &search, table))
+            })))
+        }
+        _ => Ok(redirect("/")),
     }
 }
 
+/// Runs a search and renders the whole results table for the first page, or
+/// just the rows of a following page.
+async fn search_results(
+    data: web::Data<AppState<'static>>,
+    search: &SearchData,
+    user_id: i32,
+) -> AwResult<Markup> {
+    let page = search.page.unwrap_or(0);
+    let sort = search.sort();
+    let descending = search.descending();
+    let text = search.search_name.clone();
+    let trusted_only = search.trusted_only.is_some();
+    let food_group = search.food_group.as_deref().and_then(|g| g.parse().ok());
+
+    let matching_foods = web::block(move || -> QueryResult<(User, SimpleResult<SearchPage>)> {
+        let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+        let user = load_user(&mut conn, user_id)?;
+        let language = Language::from_code(&user.language).unwrap_or_default();
+        let viewer = Viewer::load(&mut conn, &user)?;
+
+        let found = search::search_foods(
+            &mut conn,
+            &SearchParams {
+                text: &text,
+                trusted_only,
+                food_group,
+                sort,
+                descending,
+                page,
+                language,
+                viewer: &viewer,
+            },
+        )?;
+        Ok((user, found))
+    })
+    .await?;
+
+    let rows = match matching_foods {
+        Ok((user, Ok(found))) => html! {
+            @if let Some(suggestion) = &found.suggestion {
+                (suggestion_row(suggestion))
+            }
+            @for (food, group, source) in &found.results {
+                (food_row(food, group.as_ref(), source.as_ref(), food_edit::can_edit(&user, food)))
+            }
+            @if found.more {
+                (load_more_row(page + 1))
+            }
+        },
+        Ok((_, Err(invalid))) => search_error_row(invalid.as_str()),
+        Err(e) => {
+            eprintln!("Search failed: {}", e);
+            search_error_row("Search failed")
+        }
+    };
+
+    Ok(if page == 0 {
+        food_table(sort, descending, rows)
+    } else {
+        rows
+    })
+}
+
 #[post("/search_food")]
 async fn search_food(
     form: web::Form<SearchData>,
     data: web::Data<AppState<'static>>,
     session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let results = search_results(data, &form, session_data.user_id).await?;
+
+            let mut resp = HttpResponse::Ok();
+            resp.content_type(ContentType::html());
+            if form.page.unwrap_or(0) == 0 {
+                // Keep the address bar in sync so the search can be bookmarked
+                let query = serde_urlencoded::to_string(&*form).unwrap_or_default();
+                resp.insert_header(("HX-Replace-Url", format!("/meal_builder?{}", query)));
+            }
+            Ok(resp.body(results.into_string()))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct LanguageData {
+    language: String,
+}
+
+#[post("/language")]
+async fn change_language(
+    form: web::Form<LanguageData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let Some(language) = Language::from_code(&form.language) else {
+                return Ok(HttpResponse::BadRequest().body("Unknown language"));
+            };
+
+            web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                language::set_for_user(&mut conn, session_data.user_id, language)
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            // Food names are everywhere on the page, so reload all of it
+            Ok(HttpResponse::Ok()
+                .insert_header(("HX-Refresh", "true"))
+                .body(""))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/food/barcode/{code}")]
+async fn food_by_barcode(
+    code: web::Path<String>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
 ) -> AwResult<Markup> {
-    let matching_foods = web::block(move || {
+    let user_id = match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => session_data.user_id,
+        _ => {
+            return Ok(html! { p class="text-red-400 font-bold" { "Log in to look up barcodes" } })
+        }
+    };
+
+    let code = match barcode::normalize(&code) {
+        Ok(code) => code,
+        Err(e) => return Ok(html! { p class="text-red-400 font-bold" { (e) } }),
+    };
+
+    let lookup = code.clone();
+    let matching_food = web::block(move || -> QueryResult<Option<(SearchResult, bool)>> {
         let mut conn = data.db.get().expect("Couldnt get db conn from pool");
         use track_notes::schema::foods::dsl;
 
-        dsl::foods
-            .filter(foods::name.like(format!("{}%", form.search_name)))
-            .load::<Food>(&mut conn)
+        let user = load_user(&mut conn, user_id)?;
+        let viewer = Viewer::load(&mut conn, &user)?;
+
+        // Barcodes are unique per owner, prefer the user's own food, then the
+        // imported one over those of other users. Comparing NULL owners gives
+        // NULL, which would sort last.
+        let found = dsl::foods
+            .left_join(food_groups::table)
+            .left_join(data_sources::table)
+            .filter(foods::barcode.eq(lookup))
+            .filter(visible_to(&viewer))
+            .order((
+                coalesce(foods::owner_id.eq(user_id), false).desc(),
+                foods::owner_id.is_null().desc(),
+            ))
+            .select((
+                Food::as_select(),
+                Option::<FoodGroup>::as_select(),
+                Option::<DataSource>::as_select(),
+            ))
+            .first::<(Food, Option<FoodGroup>, Option<DataSource>)>(&mut conn)
+            .optional()?;
+
+        let Some((mut food, group, source)) = found else {
+            return Ok(None);
+        };
+        let editable = food_edit::can_edit(&user, &food);
+        let language = Language::from_code(&user.language).unwrap_or_default();
+        search::translate(&mut conn, &mut food, language)?;
+        Ok(Some(((food, group, source), editable)))
     })
     .await?;
 
-    let foods: Vec<Food> = matching_foods.unwrap_or(vec![]);
-
-    Ok(html! {
-        @for food in &foods {
-            tr class="" {
-                td class="py-2" {(food.name)}
-                td class="text-right py-2" {(format!("{}", food.calories / 100))}
-                td class="text-right py-2" {(format!("{}", food.protein / 100))}
-            }
+    Ok(match matching_food {
+        Ok(Some(((food, group, source), editable))) => {
+            barcode_match(&food, group.as_ref(), source.as_ref(), editable)
         }
+        Ok(None) => barcode_not_found(&code),
+        Err(_) => html! { p class="text-red-400 font-bold" { "Lookup failed" } },
     })
 }
 
@@ -241,8 +605,6 @@ async fn login(
     data: web::Data<AppState<'static>>,
     session: Session,
 ) -> AwResult<HttpResponse> {
-    println!("Username: {} Password: {}", form.username, form.password);
-
     let pw_bytes = form.password.clone().into_bytes();
     let user = web::block(move || {
         let mut conn = data.db.get().expect("Couldnt get db conn from pool");
@@ -250,15 +612,11 @@ async fn login(
         let x = dsl::users
             .filter(users::username.eq(&form.username))
             .load::<User>(&mut conn);
-        match x {
-            Ok(list) => list,
-            Err(_) => vec![],
-        }
+        x.unwrap_or_default()
     })
     .await?;
 
-    if user.len() > 0 {
-        println!("{:?}", user);
+    if !user.is_empty() {
         let u: &User = &user[0];
         let parsed_hash = PasswordHash::new(&u.password).unwrap();
 
@@ -298,7 +656,6 @@ async fn sign_up_post(
         let rand_str: String = rand::thread_rng()
             .sample_iter(&Alphanumeric)
             .take(16)
-            .map(char::from)
             .collect();
         let salt = SaltString::from_b64(&rand_str).unwrap();
         let argon2 = Argon2::default();
@@ -337,31 +694,2292 @@ async fn create_food(
     data: web::Data<AppState<'static>>,
     session: Session,
 ) -> AwResult<HttpResponse> {
-    if let session_data = SessionData::from_session(&session) {
-        let food_id = web::block(move || {
-            let mut conn = data.db.get().expect("Couldnt get db conn from pool");
-            use track_notes::schema::foods::dsl;
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (nutrients, barcode, visibility) = match form.validate() {
+                Ok(valid) => valid,
+                Err(e) => return Ok(food_status(false, &e.to_string())),
+            };
+
+            let food_id = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                use track_notes::schema::foods::dsl;
+
+                let source_id =
+                    sources::find_or_create(&mut conn, SourceKind::User, sources::USER_CREATED)?;
+
+                diesel::insert_into(dsl::foods)
+                    .values((
+                        foods::name.eq(form.name.trim()),
+                        foods::calories.eq(nutrients.calories),
+                        foods::protein.eq(nutrients.protein),
+                        foods::fat.eq(nutrients.fat),
+                        foods::carbohydrates.eq(nutrients.carbohydrates),
+                        foods::fiber.eq(nutrients.fiber),
+                        foods::density.eq(nutrients.density),
+                        foods::barcode.eq(barcode),
+                        foods::data_source_id.eq(source_id),
+                        foods::owner_id.eq(session_data.user_id),
+                        foods::visibility.eq(visibility.unwrap_or_default().as_str()),
+                    ))
+                    .execute(&mut conn)
+            })
+            .await?;
+
+            Ok(match food_id {
+                Ok(_) => food_status(true, "Success"),
+                Err(e) => food_save_error(e),
+            })
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/food/{id}/edit")]
+async fn edit_food(
+    food_id: web::Path<i32>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let food = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let Some(food) =
+                    food_edit::find_editable(&mut conn, session_data.user_id, *food_id)?
+                else {
+                    return Ok(None);
+                };
+                let portions = food_edit::portions(&mut conn, food.id)?;
+                let recipe = recipes::find(&mut conn, food.id)?.is_some();
+                QueryResult::Ok(Some((food, portions, recipe)))
+            })
+            .await?;
+
+            Ok(markup_to_resp(match food {
+                Ok(Some((food, portions, recipe))) => food_editor(&food, &portions, recipe, None),
+                Ok(None) => {
+                    html! { p class="text-red-400 font-bold" { "You can't edit that food" } }
+                }
+                Err(_) => html! { p class="text-red-400 font-bold" { "Failed to load the food" } },
+            }))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/food/{id}")]
+async fn update_food(
+    food_id: web::Path<i32>,
+    form: web::Form<CreateFoodData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (nutrients, barcode, visibility) = match form.validate() {
+                Ok(valid) => valid,
+                Err(e) => return Ok(food_status(false, &e.to_string())),
+            };
+
+            let updated = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let Some(food) =
+                    food_edit::find_editable(&mut conn, session_data.user_id, *food_id)?
+                else {
+                    return Ok(0);
+                };
+
+                let updated = diesel::update(foods::table.find(food.id))
+                    .set((
+                        foods::name.eq(form.name.trim()),
+                        foods::calories.eq(nutrients.calories),
+                        foods::protein.eq(nutrients.protein),
+                        foods::fat.eq(nutrients.fat),
+                        foods::carbohydrates.eq(nutrients.carbohydrates),
+                        foods::fiber.eq(nutrients.fiber),
+                        foods::density.eq(nutrients.density),
+                        foods::barcode.eq(barcode),
+                        // Imported foods stay public
+                        visibility
+                            .filter(|_| food.owner_id.is_some())
+                            .map(|v| foods::visibility.eq(v.as_str())),
+                    ))
+                    .execute(&mut conn)?;
+                // Recipes keep the nutrients of their ingredients
+                recipes::recompute(&mut conn, food.id)?;
+                QueryResult::Ok(updated)
+            })
+            .await?;
+
+            Ok(match updated {
+                Ok(0) => food_status(false, "You can't edit that food"),
+                Ok(_) => food_status(true, "Saved"),
+                Err(e) => food_save_error(e),
+            })
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/food/{id}/delete")]
+async fn delete_food(
+    food_id: web::Path<i32>,
+    form: web::Form<DeleteFoodData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let confirmed = form.confirm.unwrap_or(false);
+            let deleted = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let Some(food) =
+                    food_edit::find_editable(&mut conn, session_data.user_id, *food_id)?
+                else {
+                    return Ok(None);
+                };
+
+                // Meals and recipes containing the food change too, so ask
+                // first. Those of other users are never changed.
+                let usage = food_edit::usage(&mut conn, food.id, session_data.user_id)?;
+                if usage.other_users > 0 || (!usage.is_unused() && !confirmed) {
+                    let portions = food_edit::portions(&mut conn, food.id)?;
+                    let recipe = recipes::find(&mut conn, food.id)?.is_some();
+                    return Ok(Some((food, portions, recipe, Some(usage))));
+                }
+                food_edit::delete(&mut conn, food.id)?;
+                diesel::QueryResult::Ok(Some((food, vec![], false, None)))
+            })
+            .await?;
+
+            Ok(match deleted {
+                Ok(Some((food, portions, recipe, Some(usage)))) => {
+                    markup_to_resp(food_editor(&food, &portions, recipe, Some(usage)))
+                }
+                Ok(Some((food, _, _, None))) => HttpResponse::Ok()
+                    .content_type(ContentType::html())
+                    .insert_header(("HX-Trigger", "foods-changed"))
+                    .body(food_deleted(&food.name).into_string()),
+                Ok(None) => markup_to_resp(
+                    html! { p class="text-red-400 font-bold" { "You can't delete that food" } },
+                ),
+                Err(_) => markup_to_resp(
+                    html! { p class="text-red-400 font-bold" { "Failed to delete the food" } },
+                ),
+            })
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct PortionData {
+    name: String,
+    grams: String,
+}
+
+/// Adds a portion to a food the user may edit, or removes `remove` from it,
+/// and renders the new list of portions.
+async fn change_portions(
+    data: web::Data<AppState<'static>>,
+    user_id: i32,
+    food_id: i32,
+    add: Option<(String, i32)>,
+    remove: Option<i32>,
+) -> AwResult<Markup> {
+    let changed = web::block(move || {
+        let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+        let Some(food) = food_edit::find_editable(&mut conn, user_id, food_id)? else {
+            return Ok(None);
+        };
 
-            diesel::insert_into(dsl::foods)
+        let mut error = None;
+        if let Some((name, grams)) = add {
+            let added = diesel::insert_into(food_portions::table)
                 .values((
-                    foods::name.eq(&form.name),
-                    foods::calories.eq(form.calories * 100),
-                    foods::protein.eq(form.protein * 100),
+                    food_portions::food_id.eq(food.id),
+                    food_portions::name.eq(&name),
+                    food_portions::grams.eq(grams),
                 ))
-                .execute(&mut conn)
-        })
-        .await?;
-
-        return Ok(match food_id {
-            Ok(_) => markup_to_resp(
-                html! { p id="status" class="text-green-400 font-bold" { "Success" } },
-            ),
-            Err(_) => {
-                markup_to_resp(html! { p id="status" class="text-red-400 font-bold" { "Failed" } })
+                .execute(&mut conn);
+            match added {
+                Err(diesel::result::Error::DatabaseError(
+                    diesel::result::DatabaseErrorKind::UniqueViolation,
+                    _,
+                )) => error = Some(format!("There already is a portion named {}", name)),
+                other => {
+                    other?;
+                }
             }
-        });
-    } else {
-        return Ok(redirect("/"));
+        }
+        if let Some(portion_id) = remove {
+            diesel::delete(food_portions::table.find(portion_id))
+                .filter(food_portions::food_id.eq(food.id))
+                .execute(&mut conn)?;
+        }
+        // Recipes measuring the food in portions change weight
+        recipes::recompute(&mut conn, food.id)?;
+
+        let portions = food_edit::portions(&mut conn, food.id)?;
+        QueryResult::Ok(Some((food, portions, error)))
+    })
+    .await?;
+
+    Ok(match changed {
+        Ok(Some((food, portions, error))) => portion_list(&food, &portions, error.as_deref()),
+        Ok(None) => html! { p class="text-red-400 font-bold" { "You can't edit that food" } },
+        Err(_) => html! { p class="text-red-400 font-bold" { "Failed to change the portions" } },
+    })
+}
+
+#[post("/food/{id}/portions")]
+async fn add_portion(
+    food_id: web::Path<i32>,
+    form: web::Form<PortionData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let name = form.name.trim().to_lowercase();
+            let error = if name.is_empty() {
+                Some("The portion needs a name".to_owned())
+            } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+                Some("Portion names can't start with a number".to_owned())
+            } else if units::Unit::from_name(&name).is_some() {
+                Some(format!("{} is already a unit", name))
+            } else {
+                None
+            };
+            let grams = fixed::parse(&form.grams).map_err(|e| e.to_string());
+
+            let add = match (error, grams) {
+                (None, Ok(grams)) if grams > 0 => Some((name, grams)),
+                (None, Ok(_)) => {
+                    return Ok(markup_to_resp(html! {
+                        p class="text-red-400 font-bold" { "A portion has to weigh something" }
+                    }))
+                }
+                (Some(e), _) | (None, Err(e)) => {
+                    return Ok(markup_to_resp(
+                        html! { p class="text-red-400 font-bold" { (e) } },
+                    ))
+                }
+            };
+
+            Ok(markup_to_resp(
+                change_portions(data, session_data.user_id, *food_id, add, None).await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/food/{food_id}/portions/{portion_id}/delete")]
+async fn delete_portion(
+    path: web::Path<(i32, i32)>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (food_id, portion_id) = path.into_inner();
+            Ok(markup_to_resp(
+                change_portions(data, session_data.user_id, food_id, None, Some(portion_id))
+                    .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct AmountData {
+    amount: String,
+}
+
+/// Converts an amount of a food, in any unit, to grams.
+#[get("/food/{id}/amount")]
+async fn food_amount(
+    food_id: web::Path<i32>,
+    query: web::Query<AmountData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            if query.amount.trim().is_empty() {
+                return Ok(markup_to_resp(html! {}));
+            }
+            let amount = match units::parse(&query.amount) {
+                Ok(amount) => amount,
+                Err(e) => return Ok(markup_to_resp(html! { span class="text-red-400" { (e) } })),
+            };
+
+            let found = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let viewer = Viewer::load(&mut conn, &user)?;
+                let food = foods::table
+                    .find(*food_id)
+                    .select(Food::as_select())
+                    .first(&mut conn)
+                    .optional()?
+                    .filter(|food| viewer.can_view(food));
+                let Some(food) = food else {
+                    return Ok(None);
+                };
+                let portions = food_edit::portions(&mut conn, food.id)?;
+                QueryResult::Ok(Some((food, portions)))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(match found {
+                Some((food, portions)) => match units::to_grams(&amount, &food, &portions) {
+                    Ok(grams) => amount_result(&food, grams),
+                    Err(e) => html! { span class="text-red-400" { (e) } },
+                },
+                None => html! { span class="text-red-400" { "No such food" } },
+            }))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Amount a food gets when it's added to a meal.
+const DEFAULT_AMOUNT: &str = "100";
+
+/// The meal being built, posted as a name and repeated `food` and `amount`
+/// fields. An `add` field appends a food and `remove` drops the item at
+/// that index.
+struct MealForm {
+    name: String,
+    entries: Vec<(i32, String)>,
+}
+
+impl MealForm {
+    fn from_fields(fields: Vec<(String, String)>) -> MealForm {
+        let mut name = String::new();
+        let mut food_ids = vec![];
+        let mut amounts = vec![];
+        let mut add = None;
+        let mut remove = None;
+        for (key, value) in fields {
+            match key.as_str() {
+                "name" => name = value,
+                "food" => food_ids.push(value),
+                "amount" => amounts.push(value),
+                "add" => add = value.parse().ok(),
+                "remove" => remove = value.parse::<usize>().ok(),
+                _ => {}
+            }
+        }
+
+        let mut entries: Vec<(i32, String)> = food_ids
+            .into_iter()
+            .zip(amounts)
+            .filter_map(|(id, amount)| Some((id.parse().ok()?, amount)))
+            .collect();
+        if let Some(i) = remove.filter(|&i| i < entries.len()) {
+            entries.remove(i);
+        }
+        if let Some(food_id) = add {
+            entries.push((food_id, DEFAULT_AMOUNT.to_owned()));
+        }
+        MealForm { name, entries }
+    }
+}
+
+/// Loads the foods of the meal being built as the user sees them.
+async fn load_meal_items(
+    data: web::Data<AppState<'static>>,
+    user_id: i32,
+    entries: Vec<(i32, String)>,
+) -> AwResult<Vec<MealItem>> {
+    web::block(move || {
+        let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+        let user = load_user(&mut conn, user_id)?;
+        let language = Language::from_code(&user.language).unwrap_or_default();
+        let viewer = Viewer::load(&mut conn, &user)?;
+        meals::load_items(&mut conn, &viewer, language, &entries)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)
+}
+
+#[post("/meal/items")]
+async fn change_meal(
+    fields: web::Form<Vec<(String, String)>>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let form = MealForm::from_fields(fields.into_inner());
+            let items = load_meal_items(data, session_data.user_id, form.entries).await?;
+            Ok(markup_to_resp(meal_panel(&form.name, &items, None)))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/meal/save")]
+async fn save_meal(
+    fields: web::Form<Vec<(String, String)>>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let form = MealForm::from_fields(fields.into_inner());
+            let items = load_meal_items(data.clone(), session_data.user_id, form.entries).await?;
+
+            let name = form.name.clone();
+            let (items, saved) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let saved = meals::save(&mut conn, session_data.user_id, &name, &items);
+                (items, saved)
+            })
+            .await?;
+
+            Ok(markup_to_resp(match saved {
+                Ok(_) => meal_panel("", &[], Some(Ok(&format!("Saved {}", form.name.trim())))),
+                Err(e) => meal_panel(&form.name, &items, Some(Err(&e.to_string()))),
+            }))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/meals")]
+async fn list_meals(
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (user, meals) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let meals = meals::owned_by(&mut conn, user.id)?;
+                QueryResult::Ok((user, meals))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(meals_page(&user, &meals))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/meal/{id}")]
+async fn show_meal(
+    meal_id: web::Path<i32>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (user, meal) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let Some(meal) = meals::find_owned(&mut conn, user.id, *meal_id)? else {
+                    return Ok((user, None));
+                };
+                let language = Language::from_code(&user.language).unwrap_or_default();
+                let contents = meals::contents(&mut conn, meal.id, language)?;
+                QueryResult::Ok((user, Some((meal, contents))))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(match meal {
+                Some((meal, contents)) => meal_page(&user, &meal, &contents),
+                None => html! {
+                    (toolbar(&user))
+                    p class="text-red-400 font-bold" { "No such meal" }
+                },
+            })))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct RenameMealData {
+    name: String,
+}
+
+#[post("/meal/{id}/rename")]
+async fn rename_meal(
+    meal_id: web::Path<i32>,
+    form: web::Form<RenameMealData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let renamed = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let Some(meal) = meals::find_owned(&mut conn, session_data.user_id, *meal_id)?
+                else {
+                    return Ok(None);
+                };
+                let error = meals::rename(&mut conn, &meal, &form.name).err();
+                let meal = meals::find_owned(&mut conn, session_data.user_id, meal.id)?;
+                QueryResult::Ok(meal.map(|meal| (meal, error)))
+            })
+            .await?;
+
+            Ok(markup_to_resp(match renamed {
+                Ok(Some((meal, error))) => {
+                    meal_header(&meal, error.map(|e| e.to_string()).as_deref())
+                }
+                Ok(None) => html! { p class="text-red-400 font-bold" { "No such meal" } },
+                Err(_) => {
+                    html! { p class="text-red-400 font-bold" { "Failed to rename the meal" } }
+                }
+            }))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/meal/{id}/duplicate")]
+async fn duplicate_meal(
+    meal_id: web::Path<i32>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let copy = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                match meals::find_owned(&mut conn, session_data.user_id, *meal_id)? {
+                    Some(meal) => meals::duplicate(&mut conn, &meal).map(Some),
+                    None => Ok(None),
+                }
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(match copy {
+                Some(copy_id) => HttpResponse::Ok()
+                    .insert_header(("HX-Redirect", format!("/meal/{}", copy_id)))
+                    .finish(),
+                None => HttpResponse::NotFound().body("No such meal"),
+            })
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/meal/{id}/delete")]
+async fn delete_meal(
+    meal_id: web::Path<i32>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let deleted = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                match meals::find_owned(&mut conn, session_data.user_id, *meal_id)? {
+                    Some(meal) => meals::delete(&mut conn, meal.id).map(|_| true),
+                    None => Ok(false),
+                }
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(if deleted {
+                HttpResponse::Ok()
+                    .insert_header(("HX-Redirect", "/meals"))
+                    .finish()
+            } else {
+                HttpResponse::NotFound().body("No such meal")
+            })
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/recipes")]
+async fn list_recipes(
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (user, recipes) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let language = Language::from_code(&user.language).unwrap_or_default();
+                let mut recipes = recipes::owned_by(&mut conn, user.id)?;
+                for recipe in &mut recipes {
+                    search::translate(&mut conn, recipe, language)?;
+                }
+                QueryResult::Ok((user, recipes))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(recipes_page(&user, &recipes))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateRecipeData {
+    name: String,
+    visibility: String,
+}
+
+#[post("/recipes")]
+async fn create_recipe(
+    form: web::Form<CreateRecipeData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let error = |message: &str| {
+                markup_to_resp(html! { span class="text-red-400 font-bold" { (message) } })
+            };
+            let name = form.name.trim().to_owned();
+            if name.is_empty() {
+                return Ok(error("The recipe needs a name"));
+            }
+            let Some(visibility) = Visibility::from_value(&form.visibility) else {
+                return Ok(error("Unknown visibility"));
+            };
+
+            let created = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                recipes::create(&mut conn, session_data.user_id, &name, visibility)
+            })
+            .await?;
+
+            Ok(match created {
+                Ok(food_id) => HttpResponse::Ok()
+                    .insert_header(("HX-Redirect", format!("/recipe/{}", food_id)))
+                    .finish(),
+                Err(diesel::result::Error::DatabaseError(
+                    diesel::result::DatabaseErrorKind::UniqueViolation,
+                    _,
+                )) => error("There already is a food with that name"),
+                Err(_) => error("Failed to create the recipe"),
+            })
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+type LoadedRecipe = (Food, Recipe, Vec<(RecipeIngredient, Food)>);
+
+/// Loads a recipe the user may edit with its ingredients named in their
+/// language, `None` if the food isn't an editable recipe.
+fn load_recipe(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    food_id: i32,
+) -> QueryResult<Option<LoadedRecipe>> {
+    let Some(food) = food_edit::find_editable(conn, user_id, food_id)? else {
+        return Ok(None);
+    };
+    let Some(recipe) = recipes::find(conn, food.id)? else {
+        return Ok(None);
+    };
+    let language = Language::from_code(&load_user(conn, user_id)?.language).unwrap_or_default();
+    let mut ingredients = recipes::ingredients(conn, recipe.food_id)?;
+    for (_, ingredient) in &mut ingredients {
+        search::translate(conn, ingredient, language)?;
+    }
+    Ok(Some((food, recipe, ingredients)))
+}
+
+#[get("/recipe/{id}")]
+async fn show_recipe(
+    food_id: web::Path<i32>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let user_id = session_data.user_id;
+            let (user, recipe) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, user_id)?;
+                let recipe = load_recipe(&mut conn, user_id, *food_id)?;
+                QueryResult::Ok((user, recipe))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(match recipe {
+                Some((food, recipe, ingredients)) => {
+                    recipe_page(&user, &food, &recipe, &ingredients)
+                }
+                None => html! {
+                    (toolbar(&user))
+                    p class="text-red-400 font-bold" { "You can't edit that recipe" }
+                },
+            })))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Applies `change` to a recipe the user may edit and renders its details,
+/// with the error of the change if it failed.
+async fn change_recipe<F>(
+    data: web::Data<AppState<'static>>,
+    user_id: i32,
+    food_id: i32,
+    change: F,
+) -> AwResult<Markup>
+where
+    F: FnOnce(&mut SqliteConnection, &Recipe) -> SimpleResult<()> + Send + 'static,
+{
+    let changed = web::block(move || {
+        let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+        let Some((_, recipe, _)) = load_recipe(&mut conn, user_id, food_id)? else {
+            return Ok(None);
+        };
+        let error = change(&mut conn, &recipe).err().map(|e| e.to_string());
+        let loaded = load_recipe(&mut conn, user_id, food_id)?;
+        QueryResult::Ok(loaded.map(|loaded| (loaded, error)))
+    })
+    .await?;
+
+    Ok(match changed {
+        Ok(Some(((food, recipe, ingredients), error))) => {
+            recipe_details(&food, &recipe, &ingredients, error.as_deref())
+        }
+        Ok(None) => html! { p class="text-red-400 font-bold" { "You can't edit that recipe" } },
+        Err(_) => html! { p class="text-red-400 font-bold" { "Failed to change the recipe" } },
+    })
+}
+
+#[derive(Deserialize)]
+struct IngredientData {
+    food_id: i32,
+    amount: String,
+}
+
+#[post("/recipe/{id}/ingredients")]
+async fn add_ingredient(
+    recipe_id: web::Path<i32>,
+    form: web::Form<IngredientData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let user_id = session_data.user_id;
+            let IngredientData { food_id, amount } = form.into_inner();
+            Ok(markup_to_resp(
+                change_recipe(data, user_id, *recipe_id, move |conn, recipe| {
+                    let user = load_user(conn, user_id).map_err(SimpleError::from)?;
+                    let viewer = Viewer::load(conn, &user).map_err(SimpleError::from)?;
+                    let food = foods::table
+                        .find(food_id)
+                        .select(Food::as_select())
+                        .first(conn)
+                        .optional()
+                        .map_err(SimpleError::from)?
+                        .filter(|food| viewer.can_view(food))
+                        .ok_or_else(|| SimpleError::new("No such food"))?;
+                    recipes::add_ingredient(conn, recipe.food_id, &food, &amount)
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/recipe/{recipe_id}/ingredients/{ingredient_id}/delete")]
+async fn delete_ingredient(
+    path: web::Path<(i32, i32)>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (recipe_id, ingredient_id) = path.into_inner();
+            Ok(markup_to_resp(
+                change_recipe(
+                    data,
+                    session_data.user_id,
+                    recipe_id,
+                    move |conn, recipe| {
+                        recipes::remove_ingredient(conn, recipe.food_id, ingredient_id)
+                            .map_err(SimpleError::from)
+                    },
+                )
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct CookedWeightData {
+    cooked_weight: String,
+}
+
+#[post("/recipe/{id}/cooked-weight")]
+async fn set_cooked_weight(
+    recipe_id: web::Path<i32>,
+    form: web::Form<CookedWeightData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let cooked_weight = form.into_inner().cooked_weight;
+            Ok(markup_to_resp(
+                change_recipe(
+                    data,
+                    session_data.user_id,
+                    *recipe_id,
+                    move |conn, recipe| {
+                        let cooked_weight = fixed::parse_optional(Some(&cooked_weight))?;
+                        if cooked_weight == Some(0) {
+                            return Err(SimpleError::new(
+                                "The cooked weight has to be more than 0",
+                            ));
+                        }
+                        recipes::set_cooked_weight(conn, recipe.food_id, cooked_weight)
+                            .map_err(SimpleError::from)
+                    },
+                )
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Foods suggested at once while searching for a food to add.
+const FOOD_MATCHES: usize = 10;
+
+#[derive(Deserialize)]
+struct QuickSearchData {
+    q: String,
+}
+
+/// The foods best matching `text` that the user can see, for adding to a
+/// recipe or the diary.
+fn food_matches(conn: &mut SqliteConnection, user: &User, text: &str) -> SimpleResult<Vec<Food>> {
+    let viewer = Viewer::load(conn, user).map_err(SimpleError::from)?;
+    let found = search::search_foods(
+        conn,
+        &SearchParams {
+            text,
+            trusted_only: false,
+            food_group: None,
+            sort: SortBy::Relevance,
+            descending: SortBy::Relevance.descending_by_default(),
+            page: 0,
+            language: Language::from_code(&user.language).unwrap_or_default(),
+            viewer: &viewer,
+        },
+    )
+    .map_err(|e| {
+        eprintln!("Search failed: {}", e);
+        SimpleError::new("Search failed")
+    })??;
+    Ok(found
+        .results
+        .into_iter()
+        .map(|(food, _, _)| food)
+        .take(FOOD_MATCHES)
+        .collect())
+}
+
+#[get("/recipe/{id}/ingredient-search")]
+async fn search_ingredients(
+    recipe_id: web::Path<i32>,
+    query: web::Query<QuickSearchData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            if query.q.trim().is_empty() {
+                return Ok(markup_to_resp(html! {}));
+            }
+            let text = query.into_inner().q;
+            let found = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id).map_err(SimpleError::from)?;
+                food_matches(&mut conn, &user, &text)
+            })
+            .await?;
+
+            Ok(markup_to_resp(match found {
+                Ok(foods) => ingredient_matches(*recipe_id, &foods),
+                Err(e) => html! { p class="text-red-400" { (e) } },
+            }))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/diary")]
+async fn diary_today() -> impl Responder {
+    HttpResponse::SeeOther()
+        .insert_header(("Location", format!("/diary/{}", diary::today())))
+        .finish()
+}
+
+/// A day of the diary with what is shown around it.
+pub struct DiaryView {
+    pub day: Day,
+    /// Targets of the day, `None` if the user has none.
+    pub target: Option<Target>,
+    /// The week of the day.
+    pub week: Vec<WeekDay>,
+    /// Items logged before the day, to log again.
+    pub recent: Vec<Logged>,
+}
+
+impl DiaryView {
+    fn load(conn: &mut SqliteConnection, user: &User, date: Date) -> QueryResult<DiaryView> {
+        let language = Language::from_code(&user.language).unwrap_or_default();
+        Ok(DiaryView {
+            day: diary::day(conn, user.id, date, language)?,
+            target: targets::on(&targets::for_user(conn, user.id)?, date),
+            week: targets::week(conn, user.id, date)?,
+            recent: diary::recent(conn, user.id, date, language)?,
+        })
+    }
+}
+
+/// The day a diary path is about.
+fn diary_date(path: &str) -> AwResult<Date> {
+    diary::parse_date(path).map_err(actix_web::error::ErrorNotFound)
+}
+
+#[get("/diary/{date}")]
+async fn show_diary(
+    date: web::Path<String>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let date = diary_date(&date)?;
+            let (user, view) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let view = DiaryView::load(&mut conn, &user, date)?;
+                QueryResult::Ok((user, view))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(diary_page(&user, &view))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Applies `change` to the user's diary and renders the day, with the error
+/// of the change if it failed.
+async fn change_diary<F>(
+    data: web::Data<AppState<'static>>,
+    user_id: i32,
+    date: Date,
+    change: F,
+) -> AwResult<Markup>
+where
+    F: FnOnce(&mut SqliteConnection, &User) -> SimpleResult<()> + Send + 'static,
+{
+    let changed = web::block(move || {
+        let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+        let user = load_user(&mut conn, user_id)?;
+        let error = change(&mut conn, &user).err().map(|e| e.to_string());
+        let view = DiaryView::load(&mut conn, &user, date)?;
+        QueryResult::Ok((view, error))
+    })
+    .await?;
+
+    Ok(match changed {
+        Ok((view, error)) => diary_day(&view, error.as_deref()),
+        Err(_) => html! { p class="text-red-400 font-bold" { "Failed to change the diary" } },
+    })
+}
+
+/// Servings a meal is logged with when no amount is given.
+const DEFAULT_SERVINGS: &str = "1";
+
+#[derive(Deserialize)]
+struct DiaryEntryData {
+    slot: String,
+    food_id: Option<i32>,
+    meal_id: Option<i32>,
+    amount: String,
+}
+
+#[post("/diary/{date}/entries")]
+async fn log_diary_entry(
+    date: web::Path<String>,
+    form: web::Form<DiaryEntryData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let date = diary_date(&date)?;
+            let form = form.into_inner();
+            Ok(markup_to_resp(
+                change_diary(data, session_data.user_id, date, move |conn, user| {
+                    let slot = Slot::from_value(&form.slot)
+                        .ok_or_else(|| SimpleError::new("Unknown part of the day"))?;
+                    let amount = match form.amount.trim() {
+                        "" if form.meal_id.is_some() => DEFAULT_SERVINGS,
+                        "" => DEFAULT_AMOUNT,
+                        amount => amount,
+                    };
+                    match (form.food_id, form.meal_id) {
+                        (Some(food_id), _) => {
+                            let viewer = Viewer::load(conn, user).map_err(SimpleError::from)?;
+                            let food = foods::table
+                                .find(food_id)
+                                .select(Food::as_select())
+                                .first(conn)
+                                .optional()
+                                .map_err(SimpleError::from)?
+                                .filter(|food| viewer.can_view(food))
+                                .ok_or_else(|| SimpleError::new("No such food"))?;
+                            diary::log_food(conn, user.id, date, slot, &food, amount)
+                        }
+                        (None, Some(meal_id)) => {
+                            let meal = meals::find_owned(conn, user.id, meal_id)
+                                .map_err(SimpleError::from)?
+                                .ok_or_else(|| SimpleError::new("No such meal"))?;
+                            diary::log_meal(conn, user.id, date, slot, &meal, amount)
+                        }
+                        (None, None) => Err(SimpleError::new("Choose a food or meal to log")),
+                    }
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/diary/{date}/entries/{id}/delete")]
+async fn delete_diary_entry(
+    path: web::Path<(String, i32)>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (date, entry_id) = path.into_inner();
+            let date = diary_date(&date)?;
+            Ok(markup_to_resp(
+                change_diary(data, session_data.user_id, date, move |conn, user| {
+                    diary::remove(conn, user.id, entry_id).map_err(SimpleError::from)
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/diary/{date}/entries/{id}/relog")]
+async fn relog_diary_entry(
+    path: web::Path<(String, i32)>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (date, entry_id) = path.into_inner();
+            let date = diary_date(&date)?;
+            Ok(markup_to_resp(
+                change_diary(data, session_data.user_id, date, move |conn, user| {
+                    let entry = diary::find(conn, user.id, entry_id)
+                        .map_err(SimpleError::from)?
+                        .ok_or_else(|| SimpleError::new("No such diary entry"))?;
+                    diary::relog(conn, &entry, date).map_err(SimpleError::from)
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct CopySlotData {
+    slot: String,
+}
+
+#[post("/diary/{date}/copy")]
+async fn copy_diary_slot(
+    date: web::Path<String>,
+    form: web::Form<CopySlotData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let date = diary_date(&date)?;
+            Ok(markup_to_resp(
+                change_diary(data, session_data.user_id, date, move |conn, user| {
+                    let slot = Slot::from_value(&form.slot)
+                        .ok_or_else(|| SimpleError::new("Unknown part of the day"))?;
+                    let day_before = date
+                        .previous_day()
+                        .ok_or_else(|| SimpleError::new("There is no day before"))?;
+                    match diary::copy_slot(conn, user.id, slot, day_before, date)
+                        .map_err(SimpleError::from)?
+                    {
+                        0 => Err(SimpleError::new(format!(
+                            "Nothing was logged for {} the day before",
+                            slot.label().to_lowercase()
+                        ))),
+                        _ => Ok(()),
+                    }
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/diary/{date}/search")]
+async fn search_diary(
+    date: web::Path<String>,
+    query: web::Query<QuickSearchData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let date = diary_date(&date)?;
+            if query.q.trim().is_empty() {
+                return Ok(markup_to_resp(html! {}));
+            }
+            let text = query.into_inner().q;
+            let found = web::block(move || -> SimpleResult<_> {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id).map_err(SimpleError::from)?;
+                let folded = search::fold(text.trim());
+                let meals: Vec<_> = meals::owned_by(&mut conn, user.id)
+                    .map_err(SimpleError::from)?
+                    .into_iter()
+                    .filter(|(meal, _)| search::fold(&meal.name).contains(&folded))
+                    .collect();
+                let foods = food_matches(&mut conn, &user, &text)?;
+                Ok((meals, foods))
+            })
+            .await?;
+
+            Ok(markup_to_resp(match found {
+                Ok((meals, foods)) => diary_matches(date, &meals, &foods),
+                Err(e) => html! { p class="text-red-400" { (e) } },
+            }))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/week")]
+async fn week_today() -> impl Responder {
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/week/{}", week_plan::monday(diary::today())),
+        ))
+        .finish()
+}
+
+/// A week of the plan with what can be planned.
+pub struct WeekView {
+    pub monday: Date,
+    pub days: Vec<PlanDay>,
+    /// The user's saved meals.
+    pub meals: Vec<(Meal, Totals)>,
+    /// The user's recipes.
+    pub recipes: Vec<Food>,
+}
+
+impl WeekView {
+    fn load(conn: &mut SqliteConnection, user: &User, date: Date) -> QueryResult<WeekView> {
+        let language = Language::from_code(&user.language).unwrap_or_default();
+        let mut recipes = recipes::owned_by(conn, user.id)?;
+        for recipe in &mut recipes {
+            search::translate(conn, recipe, language)?;
+        }
+        Ok(WeekView {
+            monday: week_plan::monday(date),
+            days: week_plan::week(conn, user.id, date, language)?,
+            meals: meals::owned_by(conn, user.id)?,
+            recipes,
+        })
+    }
+}
+
+#[get("/week/{date}")]
+async fn show_week(
+    date: web::Path<String>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let date = diary_date(&date)?;
+            let (user, view) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let view = WeekView::load(&mut conn, &user, date)?;
+                QueryResult::Ok((user, view))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(week_page(&user, &view))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Applies `change` to the user's plan and renders the week, with the
+/// message the change returns or its error.
+async fn change_week<F>(
+    data: web::Data<AppState<'static>>,
+    user_id: i32,
+    date: Date,
+    change: F,
+) -> AwResult<Markup>
+where
+    F: FnOnce(&mut SqliteConnection, &User) -> SimpleResult<Option<String>> + Send + 'static,
+{
+    let changed = web::block(move || {
+        let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+        let user = load_user(&mut conn, user_id)?;
+        let status = change(&mut conn, &user).map_err(|e| e.to_string());
+        let view = WeekView::load(&mut conn, &user, date)?;
+        QueryResult::Ok((view, status))
+    })
+    .await?;
+
+    Ok(match changed {
+        Ok((view, Ok(message))) => week_grid(&view, message.as_deref().map(Ok)),
+        Ok((view, Err(error))) => week_grid(&view, Some(Err(&error))),
+        Err(_) => html! { p class="text-red-400 font-bold" { "Failed to change the plan" } },
+    })
+}
+
+/// A meal or recipe to plan, as `meal-3` or `food-5`, on a day of the week.
+#[derive(Deserialize)]
+struct PlannedEntryData {
+    day: String,
+    slot: String,
+    item: String,
+    amount: String,
+}
+
+#[post("/week/{date}/entries")]
+async fn plan_entry(
+    date: web::Path<String>,
+    form: web::Form<PlannedEntryData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let date = diary_date(&date)?;
+            let form = form.into_inner();
+            Ok(markup_to_resp(
+                change_week(data, session_data.user_id, date, move |conn, user| {
+                    let day = diary::parse_date(&form.day)?;
+                    let slot = Slot::from_value(&form.slot)
+                        .ok_or_else(|| SimpleError::new("Unknown part of the day"))?;
+                    let item = form
+                        .item
+                        .split_once('-')
+                        .and_then(|(kind, id)| Some((kind, id.parse::<i32>().ok()?)));
+                    match item {
+                        Some(("food", food_id)) => {
+                            let viewer = Viewer::load(conn, user).map_err(SimpleError::from)?;
+                            let food = foods::table
+                                .find(food_id)
+                                .select(Food::as_select())
+                                .first(conn)
+                                .optional()
+                                .map_err(SimpleError::from)?
+                                .filter(|food| viewer.can_view(food))
+                                .ok_or_else(|| SimpleError::new("No such recipe"))?;
+                            let amount = match form.amount.trim() {
+                                "" => DEFAULT_AMOUNT,
+                                amount => amount,
+                            };
+                            week_plan::plan_food(conn, user.id, day, slot, &food, amount)?;
+                        }
+                        Some(("meal", meal_id)) => {
+                            let meal = meals::find_owned(conn, user.id, meal_id)
+                                .map_err(SimpleError::from)?
+                                .ok_or_else(|| SimpleError::new("No such meal"))?;
+                            let servings = match form.amount.trim() {
+                                "" => DEFAULT_SERVINGS,
+                                servings => servings,
+                            };
+                            week_plan::plan_meal(conn, user.id, day, slot, &meal, servings)?;
+                        }
+                        _ => return Err(SimpleError::new("Choose a meal or recipe to plan")),
+                    }
+                    Ok(None)
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/week/{date}/entries/{id}/delete")]
+async fn delete_planned_entry(
+    path: web::Path<(String, i32)>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (date, entry_id) = path.into_inner();
+            let date = diary_date(&date)?;
+            Ok(markup_to_resp(
+                change_week(data, session_data.user_id, date, move |conn, user| {
+                    week_plan::remove(conn, user.id, entry_id).map_err(SimpleError::from)?;
+                    Ok(None)
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct LogPlanData {
+    day: String,
+}
+
+#[post("/week/{date}/log")]
+async fn log_planned_day(
+    date: web::Path<String>,
+    form: web::Form<LogPlanData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let date = diary_date(&date)?;
+            Ok(markup_to_resp(
+                change_week(data, session_data.user_id, date, move |conn, user| {
+                    let day = diary::parse_date(&form.day)?;
+                    match week_plan::log_day(conn, user.id, day).map_err(SimpleError::from)? {
+                        0 => Err(SimpleError::new("Everything planned that day is logged")),
+                        1 => Ok(Some("Logged 1 item in the diary".to_owned())),
+                        n => Ok(Some(format!("Logged {} items in the diary", n))),
+                    }
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/targets")]
+async fn show_targets(
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (user, targets) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let targets = targets::for_user(&mut conn, user.id)?;
+                QueryResult::Ok((user, targets))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(targets_page(&user, &targets))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Reads the targets posted as fields like `0-calories` for every day and
+/// `6-protein` for Saturday, one target per weekday.
+fn targets_from_fields(user_id: i32, fields: &[(String, String)]) -> SimpleResult<Vec<Target>> {
+    let mut targets: Vec<Target> = (targets::EVERY_DAY..=7)
+        .map(|weekday| Target {
+            user_id,
+            weekday,
+            ..Target::default()
+        })
+        .collect();
+    for (key, value) in fields {
+        let Some((weekday, nutrient)) = key.split_once('-') else {
+            continue;
+        };
+        let (Ok(weekday), Some(nutrient)) = (weekday.parse::<usize>(), Macro::from_value(nutrient))
+        else {
+            continue;
+        };
+        if let Some(target) = targets.get_mut(weekday) {
+            nutrient.set(target, fixed::parse_optional(Some(value))?);
+        }
+    }
+    Ok(targets)
+}
+
+#[post("/targets")]
+async fn save_targets(
+    fields: web::Form<Vec<(String, String)>>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let user_id = session_data.user_id;
+            let fields = fields.into_inner();
+            let saved = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let saved = targets_from_fields(user_id, &fields).and_then(|targets| {
+                    targets::save(&mut conn, &targets).map_err(SimpleError::from)
+                });
+                let targets = targets::for_user(&mut conn, user_id)?;
+                QueryResult::Ok((targets, saved))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(match saved {
+                (targets, Ok(())) => targets_form(&targets, Some(Ok("Saved"))),
+                (targets, Err(e)) => targets_form(&targets, Some(Err(&e.to_string()))),
+            }))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// The plan being made, posted as its targets and repeated `food`, `min` and
+/// `max` fields. An `add` field adds a food to the pool and `remove` drops
+/// the one at that index.
+struct PlanForm {
+    calories: String,
+    protein: String,
+    entries: Vec<(i32, String, String)>,
+}
+
+impl PlanForm {
+    fn from_fields(fields: Vec<(String, String)>) -> PlanForm {
+        let mut calories = String::new();
+        let mut protein = String::new();
+        let mut food_ids = vec![];
+        let mut mins = vec![];
+        let mut maxes = vec![];
+        let mut add = None;
+        let mut remove = None;
+        for (key, value) in fields {
+            match key.as_str() {
+                "calories" => calories = value,
+                "protein" => protein = value,
+                "food" => food_ids.push(value),
+                "min" => mins.push(value),
+                "max" => maxes.push(value),
+                "add" => add = value.parse().ok(),
+                "remove" => remove = value.parse::<usize>().ok(),
+                _ => {}
+            }
+        }
+
+        let mut entries: Vec<(i32, String, String)> = food_ids
+            .into_iter()
+            .zip(mins.into_iter().zip(maxes))
+            .filter_map(|(id, (min, max))| Some((id.parse().ok()?, min, max)))
+            .collect();
+        if let Some(i) = remove.filter(|&i| i < entries.len()) {
+            entries.remove(i);
+        }
+        if let Some(food_id) = add {
+            entries.push((food_id, String::new(), planner::DEFAULT_MAX.to_owned()));
+        }
+        PlanForm {
+            calories,
+            protein,
+            entries,
+        }
+    }
+}
+
+/// Loads the pool of the plan being made as the user sees it.
+async fn load_plan_pool(
+    data: web::Data<AppState<'static>>,
+    user_id: i32,
+    entries: Vec<(i32, String, String)>,
+) -> AwResult<Vec<PoolItem>> {
+    web::block(move || {
+        let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+        let user = load_user(&mut conn, user_id)?;
+        let language = Language::from_code(&user.language).unwrap_or_default();
+        let viewer = Viewer::load(&mut conn, &user)?;
+        planner::load_pool(&mut conn, &viewer, language, &entries)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)
+}
+
+#[get("/planner")]
+async fn show_planner(
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (user, target) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let target = targets::on(&targets::for_user(&mut conn, user.id)?, diary::today());
+                QueryResult::Ok((user, target))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            // Start from today's targets
+            let target_value = |nutrient: Macro| {
+                target
+                    .as_ref()
+                    .and_then(|t| nutrient.of_target(t))
+                    .map(fixed::format_short)
+                    .unwrap_or_default()
+            };
+            Ok(markup_to_resp(layout(planner_page(
+                &user,
+                &target_value(Macro::Calories),
+                &target_value(Macro::Protein),
+            ))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/planner/pool")]
+async fn change_plan_pool(
+    fields: web::Form<Vec<(String, String)>>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let form = PlanForm::from_fields(fields.into_inner());
+            let pool = load_plan_pool(data, session_data.user_id, form.entries).await?;
+            Ok(markup_to_resp(plan_form(
+                &form.calories,
+                &form.protein,
+                &pool,
+            )))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/planner/search")]
+async fn search_plan_foods(
+    query: web::Query<QuickSearchData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            if query.q.trim().is_empty() {
+                return Ok(markup_to_resp(html! {}));
+            }
+            let text = query.into_inner().q;
+            let found = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id).map_err(SimpleError::from)?;
+                food_matches(&mut conn, &user, &text)
+            })
+            .await?;
+
+            Ok(markup_to_resp(match found {
+                Ok(foods) => planner_matches(&foods),
+                Err(e) => html! { p class="text-red-400" { (e) } },
+            }))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Makes a plan for the pool and targets of `form`, as meal items with the
+/// foods the plan doesn't use left out.
+fn make_plan(form: &PlanForm, pool: Vec<PoolItem>) -> SimpleResult<(Vec<MealItem>, Target)> {
+    let target = |value: &str| {
+        fixed::parse_optional(Some(value))?
+            .ok_or_else(|| SimpleError::new("Set targets for both energy and protein"))
+    };
+    let calories = target(&form.calories)?;
+    let protein = target(&form.protein)?;
+    let grams = planner::solve(&pool, calories, protein)?;
+
+    let items = pool
+        .into_iter()
+        .zip(grams)
+        .filter(|(_, grams)| *grams > 0)
+        .map(|(item, grams)| MealItem {
+            food: item.food,
+            amount: format!("{} g", fixed::format(grams, 0)),
+            grams: Ok(grams),
+        })
+        .collect();
+    let target = Target {
+        calories: Some(calories),
+        protein: Some(protein),
+        ..Target::default()
+    };
+    Ok((items, target))
+}
+
+#[post("/planner/solve")]
+async fn solve_plan(
+    fields: web::Form<Vec<(String, String)>>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let form = PlanForm::from_fields(fields.into_inner());
+            let pool = load_plan_pool(data, session_data.user_id, form.entries.clone()).await?;
+            Ok(markup_to_resp(match make_plan(&form, pool) {
+                Ok((items, target)) => plan_result(Ok((&items, &target))),
+                Err(e) => plan_result(Err(&e.to_string())),
+            }))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// The profile of a user with what is estimated from it.
+pub struct ProfileView {
+    pub profile: Option<Profile>,
+    pub unit: WeightUnit,
+    /// The latest logged weight in hundredths of a kg.
+    pub weight: Option<i32>,
+    /// `None` without a profile or a weight.
+    pub estimate: Option<Estimate>,
+}
+
+impl ProfileView {
+    fn load(conn: &mut SqliteConnection, user: &User) -> QueryResult<ProfileView> {
+        let today = diary::today();
+        let start = today - time::Duration::days(energy::WINDOW_DAYS);
+        let profile = profile::find(conn, user.id)?;
+        let weight = measurements::latest_weight(conn, user.id)?;
+        let estimate = match (&profile, weight) {
+            (Some(profile), Some(weight)) => {
+                // A logged body fat is likely newer than the one in the profile
+                let profile = Profile {
+                    body_fat: measurements::latest_body_fat(conn, user.id)?.or(profile.body_fat),
+                    ..profile.clone()
+                };
+                let intake: Vec<(Date, i32)> = diary::daily_totals(conn, user.id, start, today)?
+                    .into_iter()
+                    .map(|(date, totals)| (date, totals.calories))
+                    .collect();
+                let weights = measurements::weights(&measurements::since(conn, user.id, start)?);
+                Some(energy::estimate(&profile, weight, &intake, &weights, today))
+            }
+            _ => None,
+        };
+        Ok(ProfileView {
+            profile,
+            unit: WeightUnit::from_code(&user.weight_unit).unwrap_or_default(),
+            weight,
+            estimate,
+        })
+    }
+}
+
+#[get("/profile")]
+async fn show_profile(
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (user, view) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let view = ProfileView::load(&mut conn, &user)?;
+                QueryResult::Ok((user, view))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(profile_page(&user, &view))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Applies `change` to the user's profile and renders it with the message of
+/// the change, or its error if it failed.
+async fn change_profile<F>(
+    data: web::Data<AppState<'static>>,
+    user_id: i32,
+    change: F,
+) -> AwResult<Markup>
+where
+    F: FnOnce(&mut SqliteConnection, &User) -> SimpleResult<String> + Send + 'static,
+{
+    let changed = web::block(move || {
+        let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+        let user = load_user(&mut conn, user_id)?;
+        let status = change(&mut conn, &user).map_err(|e| e.to_string());
+        let view = ProfileView::load(&mut conn, &user)?;
+        QueryResult::Ok((view, status))
+    })
+    .await?;
+
+    Ok(match changed {
+        Ok((view, status)) => {
+            profile_details(&view, Some(status.as_deref().map_err(String::as_str)))
+        }
+        Err(_) => html! { p class="text-red-400 font-bold" { "Failed to change the profile" } },
+    })
+}
+
+#[derive(Deserialize)]
+struct ProfileData {
+    sex: String,
+    birth_date: String,
+    height: String,
+    activity: String,
+    body_fat: String,
+    weekly_change: i32,
+}
+
+impl ProfileData {
+    fn to_profile(&self, user_id: i32) -> SimpleResult<Profile> {
+        let sex = Sex::from_value(&self.sex).ok_or_else(|| SimpleError::new("Unknown sex"))?;
+        let activity = Activity::from_value(&self.activity)
+            .ok_or_else(|| SimpleError::new("Unknown activity level"))?;
+        let birth_date = diary::parse_date(&self.birth_date)
+            .map_err(|_| SimpleError::new("Enter your date of birth"))?;
+        if birth_date >= diary::today() {
+            return Err(SimpleError::new("The date of birth has to be in the past"));
+        }
+        let height = fixed::parse(&self.height)?;
+        if height == 0 {
+            return Err(SimpleError::new("Enter your height"));
+        }
+        let body_fat = fixed::parse_optional(Some(&self.body_fat))?;
+        if body_fat.is_some_and(|b| b >= 100 * fixed::SCALE) {
+            return Err(SimpleError::new("The body fat has to be less than 100 %"));
+        }
+        if !profile::WEEKLY_CHANGES.contains(&self.weekly_change) {
+            return Err(SimpleError::new("Unknown goal"));
+        }
+        Ok(Profile {
+            user_id,
+            sex: sex.as_str().to_owned(),
+            birth_date,
+            height,
+            activity: activity.as_str().to_owned(),
+            body_fat,
+            weekly_change: self.weekly_change,
+        })
+    }
+}
+
+#[post("/profile")]
+async fn save_profile(
+    form: web::Form<ProfileData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let user_id = session_data.user_id;
+            Ok(markup_to_resp(
+                change_profile(data, user_id, move |conn, _| {
+                    let profile = form.to_profile(user_id)?;
+                    profile::save(conn, &profile).map_err(SimpleError::from)?;
+                    Ok("Saved".to_owned())
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Sets the energy target of every day to the suggested intake.
+#[post("/profile/energy-target")]
+async fn use_energy_target(
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let user_id = session_data.user_id;
+            Ok(markup_to_resp(
+                change_profile(data, user_id, move |conn, user| {
+                    let suggestion = ProfileView::load(conn, user)
+                        .map_err(SimpleError::from)?
+                        .estimate
+                        .ok_or_else(|| SimpleError::new("Fill in your profile and weight first"))?
+                        .suggestion;
+                    let mut target = targets::for_user(conn, user_id)
+                        .map_err(SimpleError::from)?
+                        .into_iter()
+                        .find(|t| t.weekday == targets::EVERY_DAY)
+                        .unwrap_or(Target {
+                            user_id,
+                            weekday: targets::EVERY_DAY,
+                            ..Target::default()
+                        });
+                    target.calories = Some(suggestion);
+                    targets::save(conn, &[target]).map_err(SimpleError::from)?;
+                    Ok(format!(
+                        "Your daily energy target is now {} kcal",
+                        fixed::format(suggestion, 0)
+                    ))
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Days back the body chart can show, `None` for everything logged.
+pub const CHART_RANGES: [Option<i64>; 4] = [Some(30), Some(90), Some(365), None];
+
+/// Days back the body chart shows unless another range is picked.
+const DEFAULT_CHART_RANGE: Option<i64> = Some(90);
+
+/// The range of the body chart from its form field, "all" for everything
+/// logged. Anything not in [`CHART_RANGES`] gives the default range.
+fn chart_range(days: Option<&str>) -> Option<i64> {
+    match days {
+        Some("all") => None,
+        Some(days) => days
+            .parse()
+            .ok()
+            .filter(|days| CHART_RANGES.contains(&Some(*days)))
+            .or(DEFAULT_CHART_RANGE),
+        None => DEFAULT_CHART_RANGE,
+    }
+}
+
+/// Logged measurements and the weight trend.
+pub struct BodyView {
+    pub unit: WeightUnit,
+    /// Days back the chart shows, `None` for everything logged.
+    pub days: Option<i64>,
+    /// Measurements in the range of the chart, oldest first.
+    pub measurements: Vec<Measurement>,
+    /// Trend of the weights in the range, oldest first, smoothed from the
+    /// first weight ever logged.
+    pub trend: Vec<(Date, i32)>,
+    /// Change of the trend in hundredths of a kg a week.
+    pub weekly_rate: Option<i32>,
+}
+
+impl BodyView {
+    fn load(conn: &mut SqliteConnection, user_id: i32, days: Option<i64>) -> QueryResult<BodyView> {
+        let user = load_user(conn, user_id)?;
+        let mut measurements = measurements::for_user(conn, user_id)?;
+        let trend = measurements::trend(&measurements::weights(&measurements));
+        let weekly_rate = measurements::weekly_rate(&trend);
+
+        let start = days.map_or(Date::MIN, |days| {
+            diary::today() - time::Duration::days(days)
+        });
+        measurements.retain(|m| m.date >= start);
+        Ok(BodyView {
+            unit: WeightUnit::from_code(&user.weight_unit).unwrap_or_default(),
+            days,
+            measurements,
+            trend: trend
+                .into_iter()
+                .filter(|(date, _)| *date >= start)
+                .collect(),
+            weekly_rate,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ChartRangeData {
+    days: Option<String>,
+}
+
+#[get("/body")]
+async fn show_body(
+    query: web::Query<ChartRangeData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let days = chart_range(query.days.as_deref());
+            let (user, view) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let view = BodyView::load(&mut conn, user.id, days)?;
+                QueryResult::Ok((user, view))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(body_page(&user, &view))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Applies `change` to the user's measurements or weight unit and renders
+/// them with the message of the change, or its error if it failed.
+async fn change_body<F>(
+    data: web::Data<AppState<'static>>,
+    user_id: i32,
+    days: Option<i64>,
+    change: F,
+) -> AwResult<Markup>
+where
+    F: FnOnce(&mut SqliteConnection, &User) -> SimpleResult<String> + Send + 'static,
+{
+    let changed = web::block(move || {
+        let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+        let user = load_user(&mut conn, user_id)?;
+        let status = change(&mut conn, &user).map_err(|e| e.to_string());
+        let view = BodyView::load(&mut conn, user_id, days)?;
+        QueryResult::Ok((view, status))
+    })
+    .await?;
+
+    Ok(match changed {
+        Ok((view, status)) => body_details(&view, Some(status.as_deref().map_err(String::as_str))),
+        Err(_) => {
+            html! { p class="text-red-400 font-bold" { "Failed to change the measurements" } }
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct MeasurementData {
+    date: String,
+    weight: String,
+    waist: String,
+    body_fat: String,
+    days: Option<String>,
+}
+
+#[post("/body/measurements")]
+async fn log_measurements(
+    form: web::Form<MeasurementData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let days = chart_range(form.days.as_deref());
+            Ok(markup_to_resp(
+                change_body(data, session_data.user_id, days, move |conn, user| {
+                    let date = diary::parse_date(&form.date)?;
+                    let unit = WeightUnit::from_code(&user.weight_unit).unwrap_or_default();
+                    let weight = fixed::parse_optional(Some(&form.weight))?.map(|w| unit.to_kg(w));
+                    let waist = fixed::parse_optional(Some(&form.waist))?;
+                    let body_fat = fixed::parse_optional(Some(&form.body_fat))?;
+                    if weight.is_none() && waist.is_none() && body_fat.is_none() {
+                        return Err(SimpleError::new("Enter a weight, waist or body fat"));
+                    }
+                    if weight == Some(0) || waist == Some(0) {
+                        return Err(SimpleError::new("Measurements have to be more than 0"));
+                    }
+                    if body_fat.is_some_and(|b| b >= 100 * fixed::SCALE) {
+                        return Err(SimpleError::new("The body fat has to be less than 100 %"));
+                    }
+                    measurements::log(conn, user.id, date, weight, waist, body_fat)
+                        .map_err(SimpleError::from)?;
+                    Ok(format!("Logged on {}", date))
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[post("/body/measurements/{id}/delete")]
+async fn delete_measurement(
+    measurement_id: web::Path<i32>,
+    form: web::Form<ChartRangeData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let days = chart_range(form.days.as_deref());
+            Ok(markup_to_resp(
+                change_body(data, session_data.user_id, days, move |conn, user| {
+                    measurements::delete(conn, user.id, *measurement_id)
+                        .map_err(SimpleError::from)?;
+                    Ok("Removed".to_owned())
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct WeightUnitData {
+    weight_unit: String,
+    days: Option<String>,
+}
+
+#[post("/body/unit")]
+async fn change_weight_unit(
+    form: web::Form<WeightUnitData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let Some(unit) = WeightUnit::from_code(&form.weight_unit) else {
+                return Ok(HttpResponse::BadRequest().body("Unknown unit"));
+            };
+            let days = chart_range(form.days.as_deref());
+            Ok(markup_to_resp(
+                change_body(data, session_data.user_id, days, move |conn, user| {
+                    measurements::set_unit_for_user(conn, user.id, unit)
+                        .map_err(SimpleError::from)?;
+                    Ok(format!("Weights are now shown in {}", unit.code()))
+                })
+                .await?,
+            ))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Days a report covers unless another range is picked.
+const DEFAULT_REPORT_DAYS: i64 = 7;
+
+#[derive(Deserialize)]
+struct ReportRangeData {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+impl ReportRangeData {
+    /// The first and last day of the report, by default the last
+    /// [`DEFAULT_REPORT_DAYS`] up to today.
+    fn dates(&self) -> SimpleResult<(Date, Date)> {
+        let date = |input: &Option<String>| match input.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(input) => diary::parse_date(input).map(Some),
+        };
+        let to = date(&self.to)?.unwrap_or_else(diary::today);
+        let from = date(&self.from)?.unwrap_or(to - time::Duration::days(DEFAULT_REPORT_DAYS - 1));
+        if from > to {
+            return Err(SimpleError::new("The report has to start before it ends"));
+        }
+        if (to - from).whole_days() >= reports::MAX_DAYS {
+            return Err(SimpleError::new(format!(
+                "A report can cover at most {} days",
+                reports::MAX_DAYS
+            )));
+        }
+        Ok((from, to))
+    }
+}
+
+#[get("/reports")]
+async fn show_report(
+    query: web::Query<ReportRangeData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (from, to, error) = match query.dates() {
+                Ok((from, to)) => (from, to, None),
+                Err(e) => {
+                    let to = diary::today();
+                    let from = to - time::Duration::days(DEFAULT_REPORT_DAYS - 1);
+                    (from, to, Some(e.to_string()))
+                }
+            };
+            let (user, report) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let language = Language::from_code(&user.language).unwrap_or_default();
+                let report = reports::report(&mut conn, user.id, from, to, language)?;
+                QueryResult::Ok((user, report))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(report_page(
+                &user,
+                &report,
+                error.as_deref(),
+            ))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[get("/micronutrients")]
+async fn show_micronutrients(
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let (user, coverage) = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                let coverage = match profile::find(&mut conn, user.id)? {
+                    Some(profile) => {
+                        let viewer = Viewer::load(&mut conn, &user)?;
+                        let language = Language::from_code(&user.language).unwrap_or_default();
+                        Some(micronutrients::coverage(
+                            &mut conn,
+                            &viewer,
+                            &profile,
+                            diary::today(),
+                            language,
+                        )?)
+                    }
+                    None => None,
+                };
+                QueryResult::Ok((user, coverage))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(markup_to_resp(layout(micronutrients_page(
+                &user,
+                coverage.as_ref(),
+            ))))
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+/// Pairs of foods shown at once on the duplicates page.
+const DUPLICATES_SHOWN: usize = 50;
+
+#[get("/admin/duplicates")]
+async fn duplicate_foods(
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            let page = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                if !user.admin {
+                    return Ok(None);
+                }
+                let candidates = duplicates::find(&mut conn, DUPLICATES_SHOWN)?;
+                QueryResult::Ok(Some(duplicates_page(&user, &candidates)))
+            })
+            .await?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+
+            Ok(match page {
+                Some(page) => markup_to_resp(layout(page)),
+                None => HttpResponse::Forbidden().body("Admins only"),
+            })
+        }
+        _ => Ok(redirect("/")),
+    }
+}
+
+#[derive(Deserialize)]
+struct MergeData {
+    survivor: i32,
+    duplicate: i32,
+}
+
+#[post("/admin/merge")]
+async fn merge_foods(
+    form: web::Form<MergeData>,
+    data: web::Data<AppState<'static>>,
+    session: Session,
+) -> AwResult<HttpResponse> {
+    match SessionData::from_session(&session) {
+        Some(session_data) if session_data.authenticated => {
+            if form.survivor == form.duplicate {
+                return Ok(markup_to_resp(merge_result(
+                    "Can't merge a food with itself",
+                    false,
+                )));
+            }
+
+            let merged = web::block(move || {
+                let mut conn = data.db.get().expect("Couldnt get db conn from pool");
+                let user = load_user(&mut conn, session_data.user_id)?;
+                if !user.admin {
+                    return Ok(None);
+                }
+                let name = |conn: &mut SqliteConnection, id| {
+                    foods::table
+                        .find(id)
+                        .select(foods::name)
+                        .first::<String>(conn)
+                };
+                let names = (
+                    name(&mut conn, form.survivor)?,
+                    name(&mut conn, form.duplicate)?,
+                );
+                let merged = duplicates::merge(&mut conn, form.survivor, form.duplicate);
+                QueryResult::Ok(Some((names, merged)))
+            })
+            .await?;
+
+            Ok(markup_to_resp(match merged {
+                Ok(Some(((survivor, duplicate), Ok(())))) => {
+                    merge_result(&format!("Merged {} into {}", duplicate, survivor), true)
+                }
+                Ok(Some((_, Err(e)))) => merge_result(&e.to_string(), false),
+                Ok(None) => merge_result("Admins only", false),
+                Err(diesel::result::Error::NotFound) => {
+                    merge_result("One of the foods no longer exists", false)
+                }
+                Err(_) => merge_result("Failed to merge the foods", false),
+            }))
+        }
+        _ => Ok(redirect("/")),
     }
 }
 
@@ -372,6 +2990,7 @@ async fn main() -> std::io::Result<()> {
 
     let manager = r2d2::ConnectionManager::<SqliteConnection>::new("diesel_demo.sqlite");
     let pool = r2d2::Pool::builder()
+        .connection_customizer(Box::new(db::ForeignKeys))
         .build(manager)
         .expect("Database url should be a valid path to a SQLite DB file");
 
@@ -391,6 +3010,57 @@ async fn main() -> std::io::Result<()> {
             .service(meal_builder)
             .service(create_food)
             .service(search_food)
+            .service(food_by_barcode)
+            .service(change_language)
+            .service(edit_food)
+            .service(update_food)
+            .service(delete_food)
+            .service(add_portion)
+            .service(delete_portion)
+            .service(food_amount)
+            .service(change_meal)
+            .service(save_meal)
+            .service(list_meals)
+            .service(show_meal)
+            .service(rename_meal)
+            .service(duplicate_meal)
+            .service(delete_meal)
+            .service(list_recipes)
+            .service(create_recipe)
+            .service(show_recipe)
+            .service(add_ingredient)
+            .service(delete_ingredient)
+            .service(set_cooked_weight)
+            .service(search_ingredients)
+            .service(diary_today)
+            .service(show_diary)
+            .service(log_diary_entry)
+            .service(delete_diary_entry)
+            .service(relog_diary_entry)
+            .service(copy_diary_slot)
+            .service(search_diary)
+            .service(week_today)
+            .service(show_week)
+            .service(plan_entry)
+            .service(delete_planned_entry)
+            .service(log_planned_day)
+            .service(show_targets)
+            .service(save_targets)
+            .service(show_planner)
+            .service(change_plan_pool)
+            .service(search_plan_foods)
+            .service(solve_plan)
+            .service(show_profile)
+            .service(save_profile)
+            .service(use_energy_target)
+            .service(show_body)
+            .service(log_measurements)
+            .service(delete_measurement)
+            .service(change_weight_unit)
+            .service(show_report)
+            .service(show_micronutrients)
+            .service(duplicate_foods)
+            .service(merge_foods)
             .service(Files::new("/", "./assets").prefer_utf8(true))
     })
     .bind(("127.0.0.1", 8080))?
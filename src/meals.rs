@@ -0,0 +1,270 @@
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
+use diesel::prelude::*;
+use diesel::sql_types::Integer;
+use simple_error::{SimpleError, SimpleResult};
+
+use crate::diary;
+use crate::food_edit;
+use crate::language::Language;
+use crate::models::{Food, Meal, MealFood};
+use crate::nutrition::Totals;
+use crate::schema::{foods, meal_food_relations, meals, planned_entries};
+use crate::search;
+use crate::units;
+use crate::visibility::Viewer;
+
+/// A food in a meal with its amount as typed.
+pub struct MealItem {
+    pub food: Food,
+    pub amount: String,
+    /// Hundredths of a gram, or why the amount couldn't be converted.
+    pub grams: Result<i32, String>,
+}
+
+/// Looks up the foods of a meal being built from their ids and typed
+/// amounts, with names in `language`. Foods the viewer can't see are left
+/// out, and so is a food listed a second time.
+pub fn load_items(
+    conn: &mut SqliteConnection,
+    viewer: &Viewer,
+    language: Language,
+    entries: &[(i32, String)],
+) -> QueryResult<Vec<MealItem>> {
+    let mut items: Vec<MealItem> = vec![];
+    for (food_id, amount) in entries {
+        if items.iter().any(|item| item.food.id == *food_id) {
+            continue;
+        }
+        let food = foods::table
+            .find(food_id)
+            .select(Food::as_select())
+            .first(conn)
+            .optional()?
+            .filter(|food| viewer.can_view(food));
+        let Some(mut food) = food else {
+            continue;
+        };
+        search::translate(conn, &mut food, language)?;
+
+        let portions = food_edit::portions(conn, food.id)?;
+        let grams = units::parse(amount)
+            .and_then(|parsed| units::to_grams(&parsed, &food, &portions))
+            .map_err(|e| e.to_string());
+        items.push(MealItem {
+            food,
+            amount: amount.trim().to_owned(),
+            grams,
+        });
+    }
+    Ok(items)
+}
+
+/// Nutrients of the items whose amounts could be converted.
+pub fn totals(items: &[MealItem]) -> Totals {
+    items
+        .iter()
+        .fold(Totals::default(), |totals, item| match item.grams {
+            Ok(grams) => totals.add(&item.food, grams),
+            Err(_) => totals,
+        })
+}
+
+/// Orders the foods of a meal as they were added. The table has no id column,
+/// but SQLite numbers its rows.
+pub(crate) fn added_order() -> SqlLiteral<Integer> {
+    sql::<Integer>("meal_food_relations.rowid")
+}
+
+/// Explains why a meal couldn't be named `name`.
+fn name_error(e: diesel::result::Error, name: &str) -> SimpleError {
+    match e {
+        diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            _,
+        ) => SimpleError::new(format!("You already have a meal named {}", name)),
+        e => SimpleError::from(e),
+    }
+}
+
+fn valid_name(name: &str) -> SimpleResult<&str> {
+    match name.trim() {
+        "" => Err(SimpleError::new("The meal needs a name")),
+        name => Ok(name),
+    }
+}
+
+/// Saves a meal owned by the user and returns its id.
+pub fn save(
+    conn: &mut SqliteConnection,
+    owner_id: i32,
+    name: &str,
+    items: &[MealItem],
+) -> SimpleResult<i32> {
+    let name = valid_name(name)?;
+    if items.is_empty() {
+        return Err(SimpleError::new("Add some foods to the meal first"));
+    }
+    let mut rows = vec![];
+    for item in items {
+        let grams = item.grams.clone().map_err(SimpleError::new)?;
+        rows.push((
+            meal_food_relations::food_id.eq(item.food.id),
+            meal_food_relations::amount.eq(&item.amount),
+            meal_food_relations::grams.eq(grams),
+        ));
+    }
+
+    let saved = conn.transaction(|conn| {
+        diesel::insert_into(meals::table)
+            .values((meals::name.eq(name), meals::owner_id.eq(owner_id)))
+            .execute(conn)?;
+        // Names are unique per owner
+        let meal_id: i32 = meals::table
+            .filter(meals::owner_id.eq(owner_id))
+            .filter(meals::name.eq(name))
+            .select(meals::id)
+            .first(conn)?;
+        for (food_id, amount, grams) in rows {
+            diesel::insert_into(meal_food_relations::table)
+                .values((
+                    food_id,
+                    meal_food_relations::meal_id.eq(meal_id),
+                    amount,
+                    grams,
+                ))
+                .execute(conn)?;
+        }
+        QueryResult::Ok(meal_id)
+    });
+
+    saved.map_err(|e| name_error(e, name))
+}
+
+/// The user's meals by name, each with the nutrients of all its foods.
+pub fn owned_by(conn: &mut SqliteConnection, user_id: i32) -> QueryResult<Vec<(Meal, Totals)>> {
+    let meals: Vec<Meal> = meals::table
+        .filter(meals::owner_id.eq(user_id))
+        .order(meals::name)
+        .select(Meal::as_select())
+        .load(conn)?;
+    let contents: Vec<(MealFood, Food)> = meal_food_relations::table
+        .inner_join(foods::table)
+        .filter(meal_food_relations::meal_id.eq_any(meals.iter().map(|m| m.id)))
+        .select((MealFood::as_select(), Food::as_select()))
+        .load(conn)?;
+
+    Ok(meals
+        .into_iter()
+        .map(|meal| {
+            let totals = contents
+                .iter()
+                .filter(|(item, _)| item.meal_id == meal.id)
+                .fold(Totals::default(), |totals, (item, food)| {
+                    totals.add(food, item.grams)
+                });
+            (meal, totals)
+        })
+        .collect())
+}
+
+/// Loads a meal owned by the user, `None` if there is no such meal or it
+/// belongs to someone else.
+pub fn find_owned(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    meal_id: i32,
+) -> QueryResult<Option<Meal>> {
+    meals::table
+        .find(meal_id)
+        .filter(meals::owner_id.eq(user_id))
+        .select(Meal::as_select())
+        .first(conn)
+        .optional()
+}
+
+/// Foods of a meal in the order they were added, named in `language`.
+pub fn contents(
+    conn: &mut SqliteConnection,
+    meal_id: i32,
+    language: Language,
+) -> QueryResult<Vec<(MealFood, Food)>> {
+    let mut contents: Vec<(MealFood, Food)> = meal_food_relations::table
+        .inner_join(foods::table)
+        .filter(meal_food_relations::meal_id.eq(meal_id))
+        .order(added_order())
+        .select((MealFood::as_select(), Food::as_select()))
+        .load(conn)?;
+    for (_, food) in &mut contents {
+        search::translate(conn, food, language)?;
+    }
+    Ok(contents)
+}
+
+pub fn rename(conn: &mut SqliteConnection, meal: &Meal, name: &str) -> SimpleResult<()> {
+    let name = valid_name(name)?;
+    diesel::update(meals::table.find(meal.id))
+        .set(meals::name.eq(name))
+        .execute(conn)
+        .map_err(|e| name_error(e, name))?;
+    Ok(())
+}
+
+/// Copies a meal, named like "Lunch (copy)", and returns the id of the copy.
+pub fn duplicate(conn: &mut SqliteConnection, meal: &Meal) -> QueryResult<i32> {
+    conn.transaction(|conn| {
+        let taken: Vec<String> = meals::table
+            .filter(meals::owner_id.eq(meal.owner_id))
+            .select(meals::name)
+            .load(conn)?;
+        let name = (1..)
+            .map(|n| match n {
+                1 => format!("{} (copy)", meal.name),
+                n => format!("{} (copy {})", meal.name, n),
+            })
+            .find(|name| !taken.contains(name))
+            .unwrap();
+
+        diesel::insert_into(meals::table)
+            .values((meals::name.eq(&name), meals::owner_id.eq(meal.owner_id)))
+            .execute(conn)?;
+        let copy_id: i32 = meals::table
+            .filter(meals::owner_id.eq(meal.owner_id))
+            .filter(meals::name.eq(&name))
+            .select(meals::id)
+            .first(conn)?;
+
+        let items: Vec<MealFood> = meal_food_relations::table
+            .filter(meal_food_relations::meal_id.eq(meal.id))
+            .order(added_order())
+            .select(MealFood::as_select())
+            .load(conn)?;
+        for item in items {
+            diesel::insert_into(meal_food_relations::table)
+                .values((
+                    meal_food_relations::food_id.eq(item.food_id),
+                    meal_food_relations::meal_id.eq(copy_id),
+                    meal_food_relations::amount.eq(item.amount),
+                    meal_food_relations::grams.eq(item.grams),
+                ))
+                .execute(conn)?;
+        }
+        Ok(copy_id)
+    })
+}
+
+/// Deletes a meal. Diary entries of it get its foods instead, and plans of
+/// it are dropped.
+pub fn delete(conn: &mut SqliteConnection, meal_id: i32) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        diary::expand_meal(conn, meal_id)?;
+        diesel::delete(planned_entries::table)
+            .filter(planned_entries::meal_id.eq(meal_id))
+            .execute(conn)?;
+        diesel::delete(meal_food_relations::table)
+            .filter(meal_food_relations::meal_id.eq(meal_id))
+            .execute(conn)?;
+        diesel::delete(meals::table.find(meal_id)).execute(conn)?;
+        Ok(())
+    })
+}
@@ -0,0 +1,208 @@
+use diesel::prelude::*;
+use time::{Date, Duration};
+
+use crate::energy;
+use crate::models::Measurement;
+use crate::schema::{measurements, users};
+
+/// Share of the gap between a weight and the trend the trend closes each day,
+/// as in The Hacker's Diet.
+pub const SMOOTHING: f64 = 0.1;
+
+/// Days back from the last weight the weekly rate of change is measured over.
+pub const RATE_DAYS: i64 = 14;
+
+/// Fewest days between the first and last weight for a weekly rate.
+pub const MIN_RATE_SPAN: i64 = 7;
+
+const KG_PER_LB: f64 = 0.45359237;
+
+/// Units weights can be shown and entered in. They're stored in kg.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WeightUnit {
+    #[default]
+    Kilogram,
+    Pound,
+}
+
+impl WeightUnit {
+    pub const ALL: [WeightUnit; 2] = [WeightUnit::Kilogram, WeightUnit::Pound];
+
+    /// Symbol of the unit, as stored in `users.weight_unit`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WeightUnit::Kilogram => "kg",
+            WeightUnit::Pound => "lb",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<WeightUnit> {
+        WeightUnit::ALL.into_iter().find(|u| u.code() == code)
+    }
+
+    /// Converts hundredths of a kg to hundredths of this unit.
+    pub fn from_kg(&self, weight: i32) -> i32 {
+        match self {
+            WeightUnit::Kilogram => weight,
+            WeightUnit::Pound => (f64::from(weight) / KG_PER_LB).round() as i32,
+        }
+    }
+
+    /// Converts hundredths of this unit to hundredths of a kg.
+    pub fn to_kg(&self, weight: i32) -> i32 {
+        match self {
+            WeightUnit::Kilogram => weight,
+            WeightUnit::Pound => (f64::from(weight) * KG_PER_LB).round() as i32,
+        }
+    }
+}
+
+pub fn set_unit_for_user(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    unit: WeightUnit,
+) -> QueryResult<usize> {
+    diesel::update(users::table.find(user_id))
+        .set(users::weight_unit.eq(unit.code()))
+        .execute(conn)
+}
+
+/// Logs measurements, weight in hundredths of a kg, waist in hundredths of a
+/// cm and body fat in hundredths of a percent, on a day. Values already logged
+/// that day are replaced and those left out kept.
+pub fn log(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    date: Date,
+    weight: Option<i32>,
+    waist: Option<i32>,
+    body_fat: Option<i32>,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let logged = measurements::table
+            .filter(measurements::user_id.eq(user_id))
+            .filter(measurements::date.eq(date))
+            .select(Measurement::as_select())
+            .first(conn)
+            .optional()?;
+        match logged {
+            Some(logged) => diesel::update(measurements::table.find(logged.id))
+                .set((
+                    measurements::weight.eq(weight.or(logged.weight)),
+                    measurements::waist.eq(waist.or(logged.waist)),
+                    measurements::body_fat.eq(body_fat.or(logged.body_fat)),
+                ))
+                .execute(conn)?,
+            None => diesel::insert_into(measurements::table)
+                .values((
+                    measurements::user_id.eq(user_id),
+                    measurements::date.eq(date),
+                    measurements::weight.eq(weight),
+                    measurements::waist.eq(waist),
+                    measurements::body_fat.eq(body_fat),
+                ))
+                .execute(conn)?,
+        };
+        Ok(())
+    })
+}
+
+/// All of the user's measurements, oldest first.
+pub fn for_user(conn: &mut SqliteConnection, user_id: i32) -> QueryResult<Vec<Measurement>> {
+    measurements::table
+        .filter(measurements::user_id.eq(user_id))
+        .order(measurements::date)
+        .select(Measurement::as_select())
+        .load(conn)
+}
+
+/// The user's measurements from `from` on, oldest first.
+pub fn since(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    from: Date,
+) -> QueryResult<Vec<Measurement>> {
+    measurements::table
+        .filter(measurements::user_id.eq(user_id))
+        .filter(measurements::date.ge(from))
+        .order(measurements::date)
+        .select(Measurement::as_select())
+        .load(conn)
+}
+
+/// The user's most recently logged weight.
+pub fn latest_weight(conn: &mut SqliteConnection, user_id: i32) -> QueryResult<Option<i32>> {
+    measurements::table
+        .filter(measurements::user_id.eq(user_id))
+        .filter(measurements::weight.is_not_null())
+        .order(measurements::date.desc())
+        .select(measurements::weight)
+        .first(conn)
+        .optional()
+        .map(Option::flatten)
+}
+
+/// The user's most recently logged body fat.
+pub fn latest_body_fat(conn: &mut SqliteConnection, user_id: i32) -> QueryResult<Option<i32>> {
+    measurements::table
+        .filter(measurements::user_id.eq(user_id))
+        .filter(measurements::body_fat.is_not_null())
+        .order(measurements::date.desc())
+        .select(measurements::body_fat)
+        .first(conn)
+        .optional()
+        .map(Option::flatten)
+}
+
+pub fn delete(conn: &mut SqliteConnection, user_id: i32, measurement_id: i32) -> QueryResult<()> {
+    diesel::delete(measurements::table.find(measurement_id))
+        .filter(measurements::user_id.eq(user_id))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// The weights among `measurements` by day, in the same order.
+pub fn weights(measurements: &[Measurement]) -> Vec<(Date, i32)> {
+    measurements
+        .iter()
+        .filter_map(|m| Some((m.date, m.weight?)))
+        .collect()
+}
+
+/// Exponentially smoothed trend of `weights`, oldest first, on each day a
+/// weight was logged. The trend moves by [`SMOOTHING`] for every day since the
+/// last weight, so gaps between weighings count.
+pub fn trend(weights: &[(Date, i32)]) -> Vec<(Date, i32)> {
+    let mut last: Option<(Date, f64)> = None;
+    weights
+        .iter()
+        .map(|&(date, weight)| {
+            let weight = f64::from(weight);
+            let trend = match last {
+                Some((last_date, trend)) => {
+                    let days = (date - last_date).whole_days() as i32;
+                    trend + (1.0 - (1.0 - SMOOTHING).powi(days)) * (weight - trend)
+                }
+                None => weight,
+            };
+            last = Some((date, trend));
+            (date, trend.round() as i32)
+        })
+        .collect()
+}
+
+/// Change of the `trend` a week, in hundredths of a kg, over the last
+/// [`RATE_DAYS`]. `None` if those weights span less than [`MIN_RATE_SPAN`].
+pub fn weekly_rate(trend: &[(Date, i32)]) -> Option<i32> {
+    let &(end, _) = trend.last()?;
+    let start = end - Duration::days(RATE_DAYS);
+    let recent: Vec<&(Date, i32)> = trend.iter().filter(|(date, _)| *date >= start).collect();
+    if (end - recent.first()?.0).whole_days() < MIN_RATE_SPAN {
+        return None;
+    }
+    let points: Vec<(f64, f64)> = recent
+        .iter()
+        .map(|&&(date, weight)| ((date - start).whole_days() as f64, f64::from(weight)))
+        .collect();
+    energy::slope(&points).map(|per_day| (per_day * 7.0).round() as i32)
+}
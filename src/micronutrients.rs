@@ -0,0 +1,347 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use diesel::prelude::*;
+use time::{Date, Duration};
+
+use crate::diary;
+use crate::language::Language;
+use crate::models::{Food, Nutrient, Profile};
+use crate::profile::{self, Sex};
+use crate::schema::{food_nutrients, foods, nutrients};
+use crate::search;
+use crate::units;
+use crate::visibility::{visible_to, Viewer};
+
+/// Days back, today included, that intake is averaged over to find nutrients
+/// that are chronically low.
+pub const WINDOW_DAYS: i64 = 28;
+
+/// Days back, today included, of the weekly average.
+pub const WEEK_DAYS: i64 = 7;
+
+/// Share of the recommended intake, in percent, an average intake below which
+/// is low.
+pub const LOW_PERCENT: i64 = 70;
+
+/// Fewest days with logged food in the window to call a nutrient chronically
+/// low.
+pub const MIN_LOGGED_DAYS: usize = 7;
+
+/// Foods listed as sources of each low nutrient.
+pub const BEST_SOURCES: i64 = 5;
+
+/// Oldest age of each age group of the recommendations but the last, which
+/// has everyone older.
+const AGE_GROUPS: [i32; 3] = [30, 60, 74];
+
+/// A vitamin or mineral with its recommended daily intake for adults in the
+/// Nordic Nutrition Recommendations 2012.
+pub struct Reference {
+    /// Name in `nutrients.name`, as in the database of Livsmedelsverket.
+    pub nutrient: &'static str,
+    pub label: &'static str,
+    /// Recommended intakes in hundredths of the unit of the nutrient, for
+    /// ages up to 30, 31 to 60, 61 to 74 and 75 and up.
+    women: [i32; 4],
+    men: [i32; 4],
+}
+
+impl Reference {
+    /// Recommended daily intake for someone of `sex` and `age`, in hundredths
+    /// of the unit of the nutrient. There are only recommendations for adults,
+    /// so younger ages get those of the youngest adults.
+    pub fn intake(&self, sex: Sex, age: i32) -> i32 {
+        let group = AGE_GROUPS.iter().filter(|&&oldest| age > oldest).count();
+        match sex {
+            Sex::Female => self.women[group],
+            Sex::Male => self.men[group],
+        }
+    }
+}
+
+pub const REFERENCES: [Reference; 18] = [
+    Reference {
+        nutrient: "Vitamin A",
+        label: "Vitamin A",
+        women: [70000; 4],
+        men: [90000; 4],
+    },
+    Reference {
+        nutrient: "Vitamin D",
+        label: "Vitamin D",
+        women: [1000, 1000, 1000, 2000],
+        men: [1000, 1000, 1000, 2000],
+    },
+    Reference {
+        nutrient: "Vitamin E",
+        label: "Vitamin E",
+        women: [800; 4],
+        men: [1000; 4],
+    },
+    Reference {
+        nutrient: "Tiamin",
+        label: "Thiamin",
+        women: [110, 110, 100, 100],
+        men: [140, 130, 120, 120],
+    },
+    Reference {
+        nutrient: "Riboflavin",
+        label: "Riboflavin",
+        women: [130, 120, 120, 120],
+        men: [160, 150, 140, 130],
+    },
+    Reference {
+        nutrient: "Niacinekvivalenter",
+        label: "Niacin",
+        women: [1500, 1400, 1300, 1200],
+        men: [1900, 1800, 1600, 1500],
+    },
+    Reference {
+        nutrient: "Vitamin B6",
+        label: "Vitamin B6",
+        women: [120, 120, 130, 130],
+        men: [150; 4],
+    },
+    Reference {
+        nutrient: "Folat",
+        label: "Folate",
+        women: [40000, 40000, 30000, 30000],
+        men: [30000; 4],
+    },
+    Reference {
+        nutrient: "Vitamin B12",
+        label: "Vitamin B12",
+        women: [200; 4],
+        men: [200; 4],
+    },
+    Reference {
+        nutrient: "Vitamin C",
+        label: "Vitamin C",
+        women: [7500; 4],
+        men: [7500; 4],
+    },
+    Reference {
+        nutrient: "Kalcium, Ca",
+        label: "Calcium",
+        women: [80000; 4],
+        men: [80000; 4],
+    },
+    Reference {
+        nutrient: "Fosfor, P",
+        label: "Phosphorus",
+        women: [60000; 4],
+        men: [60000; 4],
+    },
+    Reference {
+        nutrient: "Kalium, K",
+        label: "Potassium",
+        women: [310000; 4],
+        men: [350000; 4],
+    },
+    Reference {
+        nutrient: "Magnesium, Mg",
+        label: "Magnesium",
+        women: [28000; 4],
+        men: [35000; 4],
+    },
+    Reference {
+        nutrient: "Järn, Fe",
+        label: "Iron",
+        women: [1500, 1500, 900, 900],
+        men: [900; 4],
+    },
+    Reference {
+        nutrient: "Zink, Zn",
+        label: "Zinc",
+        women: [700; 4],
+        men: [900; 4],
+    },
+    Reference {
+        nutrient: "Jod, I",
+        label: "Iodine",
+        women: [15000; 4],
+        men: [15000; 4],
+    },
+    Reference {
+        nutrient: "Selen, Se",
+        label: "Selenium",
+        women: [5000; 4],
+        men: [6000; 4],
+    },
+];
+
+/// Intake of a micronutrient compared to its recommended intake. Amounts are
+/// in hundredths of the unit of the nutrient a day.
+pub struct Intake {
+    pub reference: &'static Reference,
+    pub nutrient: Nutrient,
+    pub recommended: i32,
+    /// Eaten today, `None` if nothing was logged.
+    pub today: Option<i32>,
+    /// Average of the days logged in the last [`WEEK_DAYS`].
+    pub week: Option<i32>,
+    /// Average of the days logged in the last [`WINDOW_DAYS`].
+    pub window: Option<i32>,
+    /// Percent of the weight of the food logged in the window with a known
+    /// amount of the nutrient. Foods lacking it count as having none.
+    pub known: i32,
+    /// The visible foods with the most of the nutrient per 100 g, with that
+    /// amount, if it is chronically low.
+    pub sources: Vec<(Food, i32)>,
+}
+
+impl Intake {
+    /// Percent of the recommended intake `amount` is.
+    pub fn percent(&self, amount: i32) -> i64 {
+        i64::from(amount) * 100 / i64::from(self.recommended.max(1))
+    }
+}
+
+/// Micronutrient intake of a user over the last [`WINDOW_DAYS`].
+pub struct Coverage {
+    pub sex: Sex,
+    pub age: i32,
+    /// Days in the window with logged food.
+    pub logged_days: usize,
+    /// Each nutrient of [`REFERENCES`] that has been imported, in that order.
+    pub intakes: Vec<Intake>,
+}
+
+impl Coverage {
+    /// Whether the window has enough logged days to tell what is chronically
+    /// low, see [`MIN_LOGGED_DAYS`].
+    pub fn enough_days(&self) -> bool {
+        self.logged_days >= MIN_LOGGED_DAYS
+    }
+
+    /// Nutrients averaging below [`LOW_PERCENT`] of the recommended intake
+    /// over the window, empty without enough logged days.
+    pub fn chronically_low(&self) -> impl Iterator<Item = &Intake> {
+        let enough_days = self.enough_days();
+        self.intakes.iter().filter(move |intake| {
+            enough_days
+                && intake
+                    .window
+                    .is_some_and(|w| intake.percent(w) < LOW_PERCENT)
+        })
+    }
+}
+
+/// The visible foods with the most of a nutrient per 100 g, with that amount,
+/// named in `language`.
+pub fn best_sources(
+    conn: &mut SqliteConnection,
+    viewer: &Viewer,
+    nutrient_id: i32,
+    language: Language,
+) -> QueryResult<Vec<(Food, i32)>> {
+    let mut sources: Vec<(Food, i32)> = food_nutrients::table
+        .inner_join(foods::table)
+        .filter(food_nutrients::nutrient_id.eq(nutrient_id))
+        .filter(visible_to(viewer))
+        .order((food_nutrients::amount.desc(), foods::name))
+        .limit(BEST_SOURCES)
+        .select((Food::as_select(), food_nutrients::amount))
+        .load(conn)?;
+    for (food, _) in &mut sources {
+        search::translate(conn, food, language)?;
+    }
+    Ok(sources)
+}
+
+/// Compares what the user logged in the last [`WINDOW_DAYS`] up to `today` to
+/// the recommended intakes for the sex and age in `profile`.
+pub fn coverage(
+    conn: &mut SqliteConnection,
+    viewer: &Viewer,
+    profile: &Profile,
+    today: Date,
+    language: Language,
+) -> QueryResult<Coverage> {
+    let sex = Sex::from_value(&profile.sex).unwrap_or(Sex::Female);
+    let age = profile::age(profile.birth_date, today);
+    let start = today - Duration::days(WINDOW_DAYS - 1);
+    let week_start = today - Duration::days(WEEK_DAYS - 1);
+
+    let logged = diary::between(conn, viewer.user_id, start, today, language)?;
+    let eaten: Vec<(Date, &Food, i32)> = logged
+        .iter()
+        .flat_map(|l| {
+            l.foods
+                .iter()
+                .map(|(food, grams)| (l.entry.date, food, *grams))
+        })
+        .collect();
+    let logged_days: HashSet<Date> = logged.iter().map(|l| l.entry.date).collect();
+    let total_grams: i64 = eaten.iter().map(|(_, _, grams)| i64::from(*grams)).sum();
+
+    let imported: HashMap<String, Nutrient> = nutrients::table
+        .filter(nutrients::name.eq_any(REFERENCES.iter().map(|r| r.nutrient)))
+        .select(Nutrient::as_select())
+        .load(conn)?
+        .into_iter()
+        .map(|n| (n.name.clone(), n))
+        .collect();
+    let food_ids: HashSet<i32> = eaten.iter().map(|(_, food, _)| food.id).collect();
+    let amounts: HashMap<(i32, i32), i32> = food_nutrients::table
+        .filter(food_nutrients::food_id.eq_any(food_ids))
+        .filter(food_nutrients::nutrient_id.eq_any(imported.values().map(|n| n.id)))
+        .select((
+            food_nutrients::food_id,
+            food_nutrients::nutrient_id,
+            food_nutrients::amount,
+        ))
+        .load::<(i32, i32, i32)>(conn)?
+        .into_iter()
+        .map(|(food_id, nutrient_id, amount)| ((food_id, nutrient_id), amount))
+        .collect();
+
+    let average = |days: &BTreeMap<Date, i32>, from: Date| {
+        let logged: Vec<Date> = logged_days
+            .iter()
+            .filter(|d| **d >= from)
+            .copied()
+            .collect();
+        let sum: i64 = logged.iter().map(|d| i64::from(days[d])).sum();
+        (!logged.is_empty()).then(|| (sum / logged.len() as i64) as i32)
+    };
+
+    let mut intakes = vec![];
+    for reference in &REFERENCES {
+        let Some(nutrient) = imported.get(reference.nutrient) else {
+            continue;
+        };
+        let mut days: BTreeMap<Date, i32> = logged_days.iter().map(|d| (*d, 0)).collect();
+        let mut known_grams: i64 = 0;
+        for (date, food, grams) in &eaten {
+            if let Some(amount) = amounts.get(&(food.id, nutrient.id)) {
+                *days.entry(*date).or_default() += units::scale(*amount, *grams);
+                known_grams += i64::from(*grams);
+            }
+        }
+        intakes.push(Intake {
+            reference,
+            nutrient: nutrient.clone(),
+            recommended: reference.intake(sex, age),
+            today: days.get(&today).copied(),
+            week: average(&days, week_start),
+            window: average(&days, start),
+            known: (known_grams * 100 / total_grams.max(1)) as i32,
+            sources: vec![],
+        });
+    }
+
+    let mut coverage = Coverage {
+        sex,
+        age,
+        logged_days: logged_days.len(),
+        intakes,
+    };
+    let low: Vec<i32> = coverage.chronically_low().map(|i| i.nutrient.id).collect();
+    for intake in &mut coverage.intakes {
+        if low.contains(&intake.nutrient.id) {
+            intake.sources = best_sources(conn, viewer, intake.nutrient.id, language)?;
+        }
+    }
+    Ok(coverage)
+}
@@ -7,16 +7,115 @@ pub struct User {
     pub id: i32,
     pub username: String,
     pub password: String,
+    /// Language code food names are shown in, see [`crate::language`].
+    pub language: String,
+    /// Admins may edit and delete every food, including imported ones.
+    pub admin: bool,
+    /// Household the user shares foods with.
+    pub household_id: Option<i32>,
+    /// Unit weights are shown in, see [`crate::measurements::WeightUnit`].
+    pub weight_unit: String,
 }
 
-#[derive(Queryable, Selectable, Insertable, Debug)]
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
 #[diesel(table_name = crate::schema::foods)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
 pub struct Food {
     pub id: i32,
     pub name: String,
+    /// Energy per 100 g in hundredths of a kcal, see [`crate::fixed`].
     pub calories: i32,
+    /// Protein per 100 g in hundredths of a gram, see [`crate::fixed`].
     pub protein: i32,
+    pub source: Option<String>,
+    pub barcode: Option<String>,
+    pub data_source_id: Option<i32>,
+    pub food_group_id: Option<i32>,
+    /// Fat per 100 g in hundredths of a gram, `None` if unknown.
+    pub fat: Option<i32>,
+    /// Available carbohydrates per 100 g in hundredths of a gram, `None` if unknown.
+    pub carbohydrates: Option<i32>,
+    /// Fiber per 100 g in hundredths of a gram, `None` if unknown.
+    pub fiber: Option<i32>,
+    /// The user who created the food, `None` for imported foods.
+    pub owner_id: Option<i32>,
+    /// Who else can see the food, see [`crate::visibility`].
+    pub visibility: String,
+    /// Hundredths of a gram per ml, `None` if unknown. See [`crate::units`].
+    pub density: Option<i32>,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Debug)]
+#[diesel(table_name = crate::schema::data_sources)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct DataSource {
+    pub id: i32,
+    pub kind: String,
+    pub name: String,
+    pub trusted: bool,
+}
+
+/// Something a user ate, see [`crate::diary`].
+#[derive(Queryable, Selectable, Identifiable, Clone, Debug)]
+#[diesel(table_name = crate::schema::diary_entries)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct DiaryEntry {
+    pub id: i32,
+    pub user_id: i32,
+    pub date: time::Date,
+    /// Part of the day, see [`crate::diary::Slot`].
+    pub slot: String,
+    /// Set for an amount of a food, `None` for a meal.
+    pub food_id: Option<i32>,
+    /// Set for servings of a saved meal, `None` for a food.
+    pub meal_id: Option<i32>,
+    /// The amount as entered, see [`crate::units::parse`], or the number of
+    /// servings of a meal.
+    pub amount: String,
+    /// Hundredths of a gram of the food, or hundredths of a serving of the
+    /// meal.
+    pub quantity: i32,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Debug)]
+#[diesel(table_name = crate::schema::food_groups)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct FoodGroup {
+    pub id: i32,
+    pub name: String,
+}
+
+/// Name of a food in another language than `foods.name`.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug)]
+#[diesel(table_name = crate::schema::food_names)]
+#[diesel(belongs_to(Food))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct FoodName {
+    pub id: i32,
+    pub food_id: i32,
+    pub language: String,
+    pub name: String,
+}
+
+/// A named unit of a food, like "egg" or "slice".
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug)]
+#[diesel(table_name = crate::schema::food_portions)]
+#[diesel(belongs_to(Food))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct FoodPortion {
+    pub id: i32,
+    pub food_id: i32,
+    pub name: String,
+    /// Weight of one unit in hundredths of a gram.
+    pub grams: i32,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Debug)]
+#[diesel(table_name = crate::schema::households)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Household {
+    pub id: i32,
+    pub name: String,
 }
 
 #[derive(Queryable, Selectable, Insertable, Debug)]
@@ -25,6 +124,9 @@ pub struct Food {
 pub struct Meal {
     pub id: i32,
     pub name: String,
+    /// The user who saved the meal, `None` for meals saved before meals had
+    /// owners.
+    pub owner_id: Option<i32>,
 }
 
 #[derive(Identifiable, Selectable, Queryable, Associations, Debug)]
@@ -35,4 +137,123 @@ pub struct Meal {
 pub struct MealFood {
     pub food_id: i32,
     pub meal_id: i32,
+    /// The amount as entered, see [`crate::units::parse`].
+    pub amount: String,
+    /// Weight in hundredths of a gram.
+    pub grams: i32,
+}
+
+/// Weight, waist and body fat logged on a day, see [`crate::measurements`].
+#[derive(Queryable, Selectable, Identifiable, Clone, Debug)]
+#[diesel(table_name = crate::schema::measurements)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Measurement {
+    pub id: i32,
+    pub user_id: i32,
+    pub date: time::Date,
+    /// Hundredths of a kg.
+    pub weight: Option<i32>,
+    /// Waist circumference in hundredths of a cm.
+    pub waist: Option<i32>,
+    /// Body fat in hundredths of a percent.
+    pub body_fat: Option<i32>,
+}
+
+/// A vitamin, mineral or other nutrient foods have amounts of in
+/// `food_nutrients`, see [`crate::micronutrients`].
+#[derive(Queryable, Selectable, Identifiable, Clone, Debug)]
+#[diesel(table_name = crate::schema::nutrients)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Nutrient {
+    pub id: i32,
+    /// Name in the database of Livsmedelsverket, e.g. "Vitamin D".
+    pub name: String,
+    pub unit: String,
+}
+
+/// Something a user plans to eat, see [`crate::week_plan`]. Has the same
+/// fields as a [`DiaryEntry`].
+#[derive(Queryable, Selectable, Identifiable, Clone, Debug)]
+#[diesel(table_name = crate::schema::planned_entries)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct PlannedEntry {
+    pub id: i32,
+    pub user_id: i32,
+    pub date: time::Date,
+    /// Part of the day, see [`crate::diary::Slot`].
+    pub slot: String,
+    /// Set for an amount of a food, `None` for a meal.
+    pub food_id: Option<i32>,
+    /// Set for servings of a saved meal, `None` for a food.
+    pub meal_id: Option<i32>,
+    /// The amount as entered or the number of servings of a meal.
+    pub amount: String,
+    /// Hundredths of a gram of the food, or hundredths of a serving of the
+    /// meal.
+    pub quantity: i32,
+    /// The diary entry it was logged as, `None` until it is logged.
+    pub logged_entry_id: Option<i32>,
+}
+
+/// What energy expenditure is estimated from, see [`crate::energy`].
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::profiles)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Profile {
+    pub user_id: i32,
+    /// See [`crate::profile::Sex`].
+    pub sex: String,
+    pub birth_date: time::Date,
+    /// Hundredths of a cm.
+    pub height: i32,
+    /// See [`crate::profile::Activity`].
+    pub activity: String,
+    /// Hundredths of a percent, `None` if unknown.
+    pub body_fat: Option<i32>,
+    /// Weight change aimed for in hundredths of a kg a week, negative to lose
+    /// weight.
+    pub weekly_change: i32,
+}
+
+/// A food made from other foods, see [`crate::recipes`].
+#[derive(Queryable, Selectable, Identifiable, Debug)]
+#[diesel(table_name = crate::schema::recipes)]
+#[diesel(primary_key(food_id))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Recipe {
+    pub food_id: i32,
+    /// Weight after cooking in hundredths of a gram, `None` if it weighs as
+    /// much as the raw ingredients.
+    pub cooked_weight: Option<i32>,
+}
+
+/// What a user aims to eat in a day, see [`crate::targets`].
+#[derive(Queryable, Selectable, Insertable, Clone, Debug, Default, PartialEq)]
+#[diesel(table_name = crate::schema::targets)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Target {
+    pub user_id: i32,
+    /// 0 for every day, 1 to 7 for Monday to Sunday.
+    pub weekday: i32,
+    /// Energy in hundredths of a kcal, `None` for no target.
+    pub calories: Option<i32>,
+    /// Hundredths of a gram, `None` for no target.
+    pub protein: Option<i32>,
+    pub fat: Option<i32>,
+    pub carbohydrates: Option<i32>,
+    pub fiber: Option<i32>,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug)]
+#[diesel(table_name = crate::schema::recipe_ingredients)]
+#[diesel(belongs_to(Recipe))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RecipeIngredient {
+    pub id: i32,
+    pub recipe_id: i32,
+    pub food_id: i32,
+    /// The amount as entered, see [`crate::units::parse`].
+    pub amount: String,
+    /// Raw weight in hundredths of a gram.
+    pub grams: i32,
 }
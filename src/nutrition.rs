@@ -0,0 +1,89 @@
+use crate::fixed;
+use crate::models::Food;
+use crate::units;
+
+/// Nutrients of some amounts of foods added together, in hundredths. Sums
+/// saturate rather than overflow.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Totals {
+    /// Weight of the foods in hundredths of a gram.
+    pub grams: i32,
+    pub calories: i32,
+    pub protein: i32,
+    /// `None` if any of the foods lacks the value.
+    pub fat: Option<i32>,
+    pub carbohydrates: Option<i32>,
+    pub fiber: Option<i32>,
+}
+
+impl Default for Totals {
+    /// Nothing, which is known to contain nothing.
+    fn default() -> Totals {
+        Totals {
+            grams: 0,
+            calories: 0,
+            protein: 0,
+            fat: Some(0),
+            carbohydrates: Some(0),
+            fiber: Some(0),
+        }
+    }
+}
+
+impl Totals {
+    /// Adds `grams`, in hundredths, of a food.
+    pub fn add(self, food: &Food, grams: i32) -> Totals {
+        let add = |sum: Option<i32>, per_100g: Option<i32>| {
+            Some(sum?.saturating_add(units::scale(per_100g?, grams)))
+        };
+
+        Totals {
+            grams: self.grams.saturating_add(grams),
+            calories: self
+                .calories
+                .saturating_add(units::scale(food.calories, grams)),
+            protein: self
+                .protein
+                .saturating_add(units::scale(food.protein, grams)),
+            fat: add(self.fat, food.fat),
+            carbohydrates: add(self.carbohydrates, food.carbohydrates),
+            fiber: add(self.fiber, food.fiber),
+        }
+    }
+
+    /// Adds up two totals.
+    pub fn plus(self, other: &Totals) -> Totals {
+        let add = |a: Option<i32>, b: Option<i32>| Some(a?.saturating_add(b?));
+
+        Totals {
+            grams: self.grams.saturating_add(other.grams),
+            calories: self.calories.saturating_add(other.calories),
+            protein: self.protein.saturating_add(other.protein),
+            fat: add(self.fat, other.fat),
+            carbohydrates: add(self.carbohydrates, other.carbohydrates),
+            fiber: add(self.fiber, other.fiber),
+        }
+    }
+
+    /// Average of the totals of `days` days.
+    pub fn per_day(&self, days: usize) -> Totals {
+        let days = i32::try_from(days.max(1)).unwrap_or(i32::MAX);
+        Totals {
+            grams: self.grams / days,
+            calories: self.calories / days,
+            protein: self.protein / days,
+            fat: self.fat.map(|v| v / days),
+            carbohydrates: self.carbohydrates.map(|v| v / days),
+            fiber: self.fiber.map(|v| v / days),
+        }
+    }
+
+    /// Amount of a `total` in hundredths per 100 g of food weighing `weight`
+    /// hundredths of a gram.
+    pub fn per_100g(total: i32, weight: i32) -> i32 {
+        if weight <= 0 {
+            return 0;
+        }
+        fixed::saturate(i64::from(total) * 100 * 100 / i64::from(weight))
+    }
+}
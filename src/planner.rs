@@ -0,0 +1,255 @@
+use diesel::prelude::*;
+use simple_error::{SimpleError, SimpleResult};
+
+use crate::fixed;
+use crate::language::Language;
+use crate::models::Food;
+use crate::schema::foods;
+use crate::search;
+use crate::simplex;
+use crate::visibility::Viewer;
+
+/// Grams of a food added to the pool the plan may use at most.
+pub const DEFAULT_MAX: &str = "300";
+
+/// A food the plan may use with the fewest and most grams of it as typed.
+pub struct PoolItem {
+    pub food: Food,
+    pub min: String,
+    pub max: String,
+}
+
+/// Looks up the foods of the pool from their ids and typed limits, with names
+/// in `language`. Foods the viewer can't see are left out, and so is a food
+/// listed a second time.
+pub fn load_pool(
+    conn: &mut SqliteConnection,
+    viewer: &Viewer,
+    language: Language,
+    entries: &[(i32, String, String)],
+) -> QueryResult<Vec<PoolItem>> {
+    let mut pool: Vec<PoolItem> = vec![];
+    for (food_id, min, max) in entries {
+        if pool.iter().any(|item| item.food.id == *food_id) {
+            continue;
+        }
+        let food = foods::table
+            .find(food_id)
+            .select(Food::as_select())
+            .first(conn)
+            .optional()?
+            .filter(|food| viewer.can_view(food));
+        let Some(mut food) = food else {
+            continue;
+        };
+        search::translate(conn, &mut food, language)?;
+        pool.push(PoolItem {
+            food,
+            min: min.trim().to_owned(),
+            max: max.trim().to_owned(),
+        });
+    }
+    Ok(pool)
+}
+
+/// Hundredths of a gram the plan may use of an item, blank meaning none at
+/// least and [`DEFAULT_MAX`] at most.
+fn limits(item: &PoolItem) -> SimpleResult<(i32, i32)> {
+    let min = fixed::parse_optional(Some(&item.min))?.unwrap_or(0);
+    let max = match fixed::parse_optional(Some(&item.max))? {
+        Some(max) => max,
+        None => fixed::parse(DEFAULT_MAX)?,
+    };
+    if max < min {
+        return Err(SimpleError::new(format!(
+            "The most grams of {} are fewer than the least",
+            item.food.name
+        )));
+    }
+    Ok((min, max))
+}
+
+/// Grams, in hundredths and rounded to whole grams, of each item of the pool
+/// that get as close as the limits allow to `calories` and `protein`, both in
+/// hundredths. Being off by some percent of either target counts the same.
+///
+/// The plan is a linear program solved with [`simplex::minimize`]. Each food
+/// has a variable for the grams above its least and one for those left below
+/// its most, and each target one for how far under and one for how far over
+/// it the plan ends up. The same pool and targets always give the same plan.
+pub fn solve(pool: &[PoolItem], calories: i32, protein: i32) -> SimpleResult<Vec<i32>> {
+    if pool.is_empty() {
+        return Err(SimpleError::new("Add some foods the plan may use first"));
+    }
+    if calories <= 0 || protein <= 0 {
+        return Err(SimpleError::new("Set targets for both energy and protein"));
+    }
+    let limits = pool.iter().map(limits).collect::<SimpleResult<Vec<_>>>()?;
+
+    let n = pool.len();
+    let variables = 2 * n + 4;
+    let (energy_under, protein_under) = (2 * n, 2 * n + 2);
+    // Nutrients in hundredths per 100 g are the same numbers per gram
+    let per_gram = |per_100g: i32| f64::from(per_100g) / f64::from(fixed::SCALE).powi(2);
+    let grams = |hundredths: i32| f64::from(hundredths) / f64::from(fixed::SCALE);
+
+    let mut constraints = vec![];
+    let mut rhs = vec![];
+    for (i, &(min, max)) in limits.iter().enumerate() {
+        let mut row = vec![0.0; variables];
+        row[i] = 1.0;
+        row[n + i] = 1.0;
+        constraints.push(row);
+        rhs.push(grams(max - min));
+    }
+    let per_100g = |nutrient: fn(&Food) -> i32| -> Vec<i32> {
+        pool.iter().map(|item| nutrient(&item.food)).collect()
+    };
+    let nutrients = [
+        (energy_under, calories, per_100g(|food| food.calories)),
+        (protein_under, protein, per_100g(|food| food.protein)),
+    ];
+    let mut targets = vec![];
+    for (under, target, amounts) in nutrients {
+        let mut row = vec![0.0; variables];
+        let mut least = 0.0;
+        for (i, (&amount, &(min, _))) in amounts.iter().zip(&limits).enumerate() {
+            row[i] = per_gram(amount);
+            least += per_gram(amount) * grams(min);
+        }
+        row[under] = 1.0;
+        row[under + 1] = -1.0;
+        constraints.push(row);
+        rhs.push(grams(target) - least);
+        targets.push((under, grams(target)));
+    }
+
+    let mut cost = vec![0.0; variables];
+    for (under, target) in targets {
+        cost[under] = 1.0 / target;
+        cost[under + 1] = 1.0 / target;
+    }
+
+    let x = simplex::minimize(&cost, &constraints, &rhs)
+        .ok_or_else(|| SimpleError::new("Couldn't find a plan for these foods"))?;
+    Ok(limits
+        .iter()
+        .zip(&x)
+        .map(|(&(min, max), above)| {
+            let whole_grams = (grams(min) + above).round() as i32;
+            (whole_grams * fixed::SCALE).clamp(min, max)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units;
+
+    fn item(id: i32, calories: i32, protein: i32, min: &str, max: &str) -> PoolItem {
+        PoolItem {
+            food: Food {
+                id,
+                name: format!("Food {}", id),
+                calories,
+                protein,
+                source: None,
+                barcode: None,
+                data_source_id: None,
+                food_group_id: None,
+                fat: None,
+                carbohydrates: None,
+                fiber: None,
+                owner_id: None,
+                visibility: "public".to_owned(),
+                density: None,
+            },
+            min: min.to_owned(),
+            max: max.to_owned(),
+        }
+    }
+
+    /// Oats, milk, chicken and rice, per 100 g.
+    fn pool() -> Vec<PoolItem> {
+        vec![
+            item(1, 37000, 1300, "40", "100"),
+            item(2, 4600, 340, "", "500"),
+            item(3, 11000, 2300, "", "300"),
+            item(4, 13000, 270, "", "400"),
+        ]
+    }
+
+    fn totals(pool: &[PoolItem], grams: &[i32]) -> (i32, i32) {
+        pool.iter()
+            .zip(grams)
+            .fold((0, 0), |(calories, protein), (item, &grams)| {
+                (
+                    calories + units::scale(item.food.calories, grams),
+                    protein + units::scale(item.food.protein, grams),
+                )
+            })
+    }
+
+    #[test]
+    fn hits_the_targets() {
+        let pool = pool();
+        let grams = solve(&pool, 120000, 8000).unwrap();
+        let (calories, protein) = totals(&pool, &grams);
+        // Whole grams are off by less than a percent
+        assert!((calories - 120000).abs() < 1200, "{} kcal", calories);
+        assert!((protein - 8000).abs() < 80, "{} protein", protein);
+        for (item, &grams) in pool.iter().zip(&grams) {
+            let (min, max) = limits(item).unwrap();
+            assert!(min <= grams && grams <= max);
+            assert_eq!(grams % fixed::SCALE, 0);
+        }
+    }
+
+    #[test]
+    fn same_pool_gives_same_plan() {
+        let pool = pool();
+        let first = solve(&pool, 120000, 8000).unwrap();
+        assert_eq!(first, vec![10000, 13900, 22400, 40000]);
+        for _ in 0..10 {
+            assert_eq!(solve(&pool, 120000, 8000).unwrap(), first);
+        }
+    }
+
+    #[test]
+    fn too_small_portions_give_the_most_of_everything() {
+        let pool = vec![
+            item(1, 37000, 1300, "", "100"),
+            item(2, 4600, 340, "", "200"),
+        ];
+        let grams = solve(&pool, 250000, 15000).unwrap();
+        assert_eq!(grams, vec![10000, 20000]);
+    }
+
+    #[test]
+    fn too_large_least_portions_give_the_least_of_everything() {
+        let pool = vec![
+            item(1, 37000, 1300, "500", "600"),
+            item(2, 4600, 340, "100", "200"),
+        ];
+        let grams = solve(&pool, 100000, 5000).unwrap();
+        assert_eq!(grams, vec![50000, 10000]);
+    }
+
+    #[test]
+    fn empty_pool_is_an_error() {
+        assert!(solve(&[], 180000, 12000).is_err());
+    }
+
+    #[test]
+    fn targets_are_required() {
+        assert!(solve(&pool(), 0, 12000).is_err());
+        assert!(solve(&pool(), 180000, 0).is_err());
+    }
+
+    #[test]
+    fn least_above_most_is_an_error() {
+        let pool = vec![item(1, 37000, 1300, "200", "100")];
+        assert!(solve(&pool, 180000, 12000).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use diesel::prelude::*;
+use time::Date;
+
+use crate::models::Profile;
+use crate::schema::profiles;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sex {
+    Female,
+    Male,
+}
+
+impl Sex {
+    pub const ALL: [Sex; 2] = [Sex::Female, Sex::Male];
+
+    /// Value stored in `profiles.sex`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sex::Female => "female",
+            Sex::Male => "male",
+        }
+    }
+
+    pub fn from_value(value: &str) -> Option<Sex> {
+        Sex::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Sex::Female => "Female",
+            Sex::Male => "Male",
+        }
+    }
+}
+
+/// How active a user is besides resting, which multiplies the basal
+/// metabolic rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Activity {
+    Sedentary,
+    Light,
+    Moderate,
+    Active,
+    VeryActive,
+}
+
+impl Activity {
+    pub const ALL: [Activity; 5] = [
+        Activity::Sedentary,
+        Activity::Light,
+        Activity::Moderate,
+        Activity::Active,
+        Activity::VeryActive,
+    ];
+
+    /// Value stored in `profiles.activity`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Activity::Sedentary => "sedentary",
+            Activity::Light => "light",
+            Activity::Moderate => "moderate",
+            Activity::Active => "active",
+            Activity::VeryActive => "very_active",
+        }
+    }
+
+    pub fn from_value(value: &str) -> Option<Activity> {
+        Activity::ALL.into_iter().find(|a| a.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Activity::Sedentary => "Little or no exercise",
+            Activity::Light => "Exercise 1-3 days a week",
+            Activity::Moderate => "Exercise 3-5 days a week",
+            Activity::Active => "Exercise 6-7 days a week",
+            Activity::VeryActive => "Hard exercise every day or a physical job",
+        }
+    }
+
+    /// Total energy expenditure as a multiple of the basal metabolic rate.
+    pub fn factor(&self) -> f64 {
+        match self {
+            Activity::Sedentary => 1.2,
+            Activity::Light => 1.375,
+            Activity::Moderate => 1.55,
+            Activity::Active => 1.725,
+            Activity::VeryActive => 1.9,
+        }
+    }
+}
+
+/// Weekly weight changes, in hundredths of a kg, a user can aim for.
+pub const WEEKLY_CHANGES: [i32; 7] = [-100, -75, -50, -25, 0, 25, 50];
+
+/// Full years from `birth_date` to `date`.
+pub fn age(birth_date: Date, date: Date) -> i32 {
+    let years = date.year() - birth_date.year();
+    if (date.month(), date.day()) < (birth_date.month(), birth_date.day()) {
+        years - 1
+    } else {
+        years
+    }
+}
+
+pub fn find(conn: &mut SqliteConnection, user_id: i32) -> QueryResult<Option<Profile>> {
+    profiles::table
+        .find(user_id)
+        .select(Profile::as_select())
+        .first(conn)
+        .optional()
+}
+
+pub fn save(conn: &mut SqliteConnection, profile: &Profile) -> QueryResult<()> {
+    diesel::replace_into(profiles::table)
+        .values(profile)
+        .execute(conn)?;
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use simple_error::{SimpleError, SimpleResult};
+
+use crate::food_edit;
+use crate::models::{Food, Recipe, RecipeIngredient};
+use crate::nutrition::Totals;
+use crate::schema::{food_nutrients, foods, recipe_ingredients, recipes};
+use crate::sources::{self, SourceKind};
+use crate::units;
+use crate::visibility::Visibility;
+
+/// Sums up the raw ingredients of a recipe.
+pub fn totals(ingredients: &[(RecipeIngredient, Food)]) -> Totals {
+    ingredients
+        .iter()
+        .fold(Totals::default(), |totals, (ingredient, food)| {
+            totals.add(food, ingredient.grams)
+        })
+}
+
+/// The recipe of a food, `None` if it isn't one.
+pub fn find(conn: &mut SqliteConnection, food_id: i32) -> QueryResult<Option<Recipe>> {
+    recipes::table
+        .find(food_id)
+        .select(Recipe::as_select())
+        .first(conn)
+        .optional()
+}
+
+/// Recipes owned by the user, by name.
+pub fn owned_by(conn: &mut SqliteConnection, user_id: i32) -> QueryResult<Vec<Food>> {
+    recipes::table
+        .inner_join(foods::table)
+        .filter(foods::owner_id.eq(user_id))
+        .order(foods::name)
+        .select(Food::as_select())
+        .load(conn)
+}
+
+/// Ingredients of a recipe in the order they were added.
+pub fn ingredients(
+    conn: &mut SqliteConnection,
+    recipe_id: i32,
+) -> QueryResult<Vec<(RecipeIngredient, Food)>> {
+    recipe_ingredients::table
+        .inner_join(foods::table)
+        .filter(recipe_ingredients::recipe_id.eq(recipe_id))
+        .order(recipe_ingredients::id)
+        .select((RecipeIngredient::as_select(), Food::as_select()))
+        .load(conn)
+}
+
+/// Number of recipes the food is an ingredient of.
+pub fn recipe_count(conn: &mut SqliteConnection, food_id: i32) -> QueryResult<i64> {
+    recipe_ingredients::table
+        .filter(recipe_ingredients::food_id.eq(food_id))
+        .select(recipe_ingredients::recipe_id)
+        .distinct()
+        .count()
+        .get_result(conn)
+}
+
+/// Creates an empty recipe owned by the user and returns its food id.
+pub fn create(
+    conn: &mut SqliteConnection,
+    owner_id: i32,
+    name: &str,
+    visibility: Visibility,
+) -> QueryResult<i32> {
+    conn.transaction(|conn| {
+        let source_id = sources::find_or_create(conn, SourceKind::User, sources::USER_CREATED)?;
+        diesel::insert_into(foods::table)
+            .values((
+                foods::name.eq(name),
+                foods::calories.eq(0),
+                foods::protein.eq(0),
+                foods::data_source_id.eq(source_id),
+                foods::owner_id.eq(owner_id),
+                foods::visibility.eq(visibility.as_str()),
+            ))
+            .execute(conn)?;
+        // Names are unique per owner
+        let food_id = foods::table
+            .filter(foods::owner_id.eq(owner_id))
+            .filter(foods::name.eq(name))
+            .select(foods::id)
+            .first(conn)?;
+        diesel::insert_into(recipes::table)
+            .values(recipes::food_id.eq(food_id))
+            .execute(conn)?;
+        Ok(food_id)
+    })
+}
+
+/// Whether `food_id` is the recipe or one of its ingredients, directly or
+/// through other recipes.
+pub(crate) fn contains(
+    conn: &mut SqliteConnection,
+    recipe_id: i32,
+    food_id: i32,
+) -> QueryResult<bool> {
+    let mut pending = vec![recipe_id];
+    let mut seen = vec![];
+    while let Some(id) = pending.pop() {
+        if id == food_id {
+            return Ok(true);
+        }
+        if seen.contains(&id) {
+            continue;
+        }
+        seen.push(id);
+        pending.extend(
+            recipe_ingredients::table
+                .filter(recipe_ingredients::recipe_id.eq(id))
+                .select(recipe_ingredients::food_id)
+                .load::<i32>(conn)?,
+        );
+    }
+    Ok(false)
+}
+
+/// Adds `amount` of a food, e.g. "2 dl", to a recipe.
+pub fn add_ingredient(
+    conn: &mut SqliteConnection,
+    recipe_id: i32,
+    food: &Food,
+    amount: &str,
+) -> SimpleResult<()> {
+    let parsed = units::parse(amount)?;
+    let portions = food_edit::portions(conn, food.id).map_err(SimpleError::from)?;
+    let grams = units::to_grams(&parsed, food, &portions)?;
+    if grams <= 0 {
+        return Err(SimpleError::new("The amount has to be more than 0"));
+    }
+    if food.id == recipe_id {
+        return Err(SimpleError::new(format!(
+            "{} can't be an ingredient of itself",
+            food.name
+        )));
+    }
+    if contains(conn, food.id, recipe_id).map_err(SimpleError::from)? {
+        return Err(SimpleError::new(format!(
+            "{} already contains this recipe",
+            food.name
+        )));
+    }
+
+    diesel::insert_into(recipe_ingredients::table)
+        .values((
+            recipe_ingredients::recipe_id.eq(recipe_id),
+            recipe_ingredients::food_id.eq(food.id),
+            recipe_ingredients::amount.eq(amount.trim()),
+            recipe_ingredients::grams.eq(grams),
+        ))
+        .execute(conn)
+        .map_err(SimpleError::from)?;
+    recompute(conn, recipe_id).map_err(SimpleError::from)
+}
+
+pub fn remove_ingredient(
+    conn: &mut SqliteConnection,
+    recipe_id: i32,
+    ingredient_id: i32,
+) -> QueryResult<()> {
+    diesel::delete(recipe_ingredients::table.find(ingredient_id))
+        .filter(recipe_ingredients::recipe_id.eq(recipe_id))
+        .execute(conn)?;
+    recompute(conn, recipe_id)
+}
+
+/// Sets the weight after cooking in hundredths of a gram, `None` to use the
+/// raw weight of the ingredients.
+pub fn set_cooked_weight(
+    conn: &mut SqliteConnection,
+    recipe_id: i32,
+    cooked_weight: Option<i32>,
+) -> QueryResult<()> {
+    diesel::update(recipes::table.find(recipe_id))
+        .set(recipes::cooked_weight.eq(cooked_weight))
+        .execute(conn)?;
+    recompute(conn, recipe_id)
+}
+
+/// Derives the nutrients per 100 g of a recipe from its ingredients. The
+/// grams of each ingredient are worked out again from the amount as entered,
+/// in case its density or portions changed, and kept as they were if that no
+/// longer works.
+fn update_nutrients(conn: &mut SqliteConnection, recipe: &Recipe) -> QueryResult<()> {
+    let mut ingredients = ingredients(conn, recipe.food_id)?;
+    for (ingredient, food) in &mut ingredients {
+        let portions = food_edit::portions(conn, food.id)?;
+        let grams = units::parse(&ingredient.amount)
+            .and_then(|amount| units::to_grams(&amount, food, &portions));
+        match grams {
+            Ok(grams) if grams != ingredient.grams => {
+                diesel::update(recipe_ingredients::table.find(ingredient.id))
+                    .set(recipe_ingredients::grams.eq(grams))
+                    .execute(conn)?;
+                ingredient.grams = grams;
+            }
+            _ => {}
+        }
+    }
+
+    let totals = totals(&ingredients);
+    let weight = recipe.cooked_weight.unwrap_or(totals.grams);
+    let per_100g = |total| Totals::per_100g(total, weight);
+    diesel::update(foods::table.find(recipe.food_id))
+        .set((
+            foods::calories.eq(per_100g(totals.calories)),
+            foods::protein.eq(per_100g(totals.protein)),
+            foods::fat.eq(totals.fat.map(per_100g)),
+            foods::carbohydrates.eq(totals.carbohydrates.map(per_100g)),
+            foods::fiber.eq(totals.fiber.map(per_100g)),
+        ))
+        .execute(conn)?;
+
+    // Other nutrients are known like the macros, when every ingredient has them
+    let food_ids: Vec<i32> = ingredients.iter().map(|(_, food)| food.id).collect();
+    let amounts: Vec<(i32, i32, i32)> = food_nutrients::table
+        .filter(food_nutrients::food_id.eq_any(&food_ids))
+        .select((
+            food_nutrients::food_id,
+            food_nutrients::nutrient_id,
+            food_nutrients::amount,
+        ))
+        .load(conn)?;
+    let mut sums: HashMap<i32, (i32, usize)> = HashMap::new();
+    for (ingredient, food) in &ingredients {
+        for (_, nutrient_id, amount) in amounts.iter().filter(|a| a.0 == food.id) {
+            let (sum, count) = sums.entry(*nutrient_id).or_default();
+            *sum = sum.saturating_add(units::scale(*amount, ingredient.grams));
+            *count += 1;
+        }
+    }
+    diesel::delete(food_nutrients::table)
+        .filter(food_nutrients::food_id.eq(recipe.food_id))
+        .execute(conn)?;
+    let known: Vec<_> = sums
+        .into_iter()
+        .filter(|(_, (_, count))| *count == ingredients.len())
+        .map(|(nutrient_id, (sum, _))| {
+            (
+                food_nutrients::food_id.eq(recipe.food_id),
+                food_nutrients::nutrient_id.eq(nutrient_id),
+                food_nutrients::amount.eq(per_100g(sum)),
+            )
+        })
+        .collect();
+    if !known.is_empty() {
+        diesel::insert_into(food_nutrients::table)
+            .values(known)
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Recomputes the food if it is a recipe, and then every recipe using it,
+/// directly or through other recipes. Call it whenever the nutrients, density
+/// or portions of a food change.
+pub fn recompute(conn: &mut SqliteConnection, food_id: i32) -> QueryResult<()> {
+    // Recipes each affected food is an ingredient of
+    let mut used_in: HashMap<i32, Vec<i32>> = HashMap::new();
+    let mut pending = vec![food_id];
+    while let Some(id) = pending.pop() {
+        if used_in.contains_key(&id) {
+            continue;
+        }
+        let recipe_ids: Vec<i32> = recipe_ingredients::table
+            .filter(recipe_ingredients::food_id.eq(id))
+            .select(recipe_ingredients::recipe_id)
+            .distinct()
+            .load(conn)?;
+        pending.extend(&recipe_ids);
+        used_in.insert(id, recipe_ids);
+    }
+
+    // A recipe is recomputed once all of its affected ingredients are
+    let mut waiting: HashMap<i32, usize> = used_in.keys().map(|&id| (id, 0)).collect();
+    for recipe_ids in used_in.values() {
+        for recipe_id in recipe_ids {
+            *waiting.get_mut(recipe_id).unwrap() += 1;
+        }
+    }
+    let mut ready: Vec<i32> = waiting
+        .iter()
+        .filter(|(_, &count)| count == 0)
+        .map(|(&id, _)| id)
+        .collect();
+
+    while let Some(id) = ready.pop() {
+        if let Some(recipe) = find(conn, id)? {
+            update_nutrients(conn, &recipe)?;
+        }
+        for recipe_id in &used_in[&id] {
+            let count = waiting.get_mut(recipe_id).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                ready.push(*recipe_id);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use time::{Date, Weekday};
+
+use crate::diary;
+use crate::language::Language;
+use crate::models::{Food, Target};
+use crate::nutrition::Totals;
+use crate::targets::{self, Macro, Status};
+use crate::units;
+
+/// Longest range a report can cover, in days.
+pub const MAX_DAYS: i64 = 366;
+
+/// Foods in each top list of a report.
+pub const TOP_FOODS: usize = 10;
+
+/// How much of a food was eaten over a report.
+#[derive(Clone)]
+pub struct FoodTotal {
+    pub food: Food,
+    /// Weight in hundredths of a gram.
+    pub grams: i32,
+    /// Energy in hundredths of a kcal.
+    pub calories: i32,
+    /// Protein in hundredths of a gram.
+    pub protein: i32,
+}
+
+/// How the days with a target for a nutrient went.
+pub struct Adherence {
+    pub nutrient: Macro,
+    pub under: usize,
+    pub on_target: usize,
+    pub over: usize,
+}
+
+impl Adherence {
+    /// Days with both a target and a known amount eaten.
+    pub fn days(&self) -> usize {
+        self.under + self.on_target + self.over
+    }
+}
+
+/// What was eaten on average on one day of the week.
+pub struct WeekdayAverage {
+    pub weekday: Weekday,
+    /// Days logged on that weekday.
+    pub days: usize,
+    /// `None` if none were logged.
+    pub average: Option<Totals>,
+}
+
+/// What the user ate from `from` to `to`, both included. Averages are over
+/// the days something was logged, as days left blank say nothing of intake.
+pub struct Report {
+    pub from: Date,
+    pub to: Date,
+    /// Totals of each day something was logged, in date order.
+    pub daily: Vec<(Date, Totals)>,
+    /// Targets of each logged day, `None` where there are none.
+    pub targets: Vec<Option<Target>>,
+    /// `None` if nothing was logged.
+    pub average: Option<Totals>,
+    /// Nutrients with a target on any logged day.
+    pub adherence: Vec<Adherence>,
+    /// The foods with the most energy, meals split into their foods.
+    pub top_by_calories: Vec<FoodTotal>,
+    /// The foods with the most protein.
+    pub top_by_protein: Vec<FoodTotal>,
+    /// Monday to Sunday.
+    pub weekdays: Vec<WeekdayAverage>,
+}
+
+impl Report {
+    /// Days in the range of the report.
+    pub fn days(&self) -> i64 {
+        (self.to - self.from).whole_days() + 1
+    }
+}
+
+/// Adds up the days of `daily` and averages them, `None` if there are none.
+fn average<'a>(daily: impl Iterator<Item = &'a Totals>) -> Option<Totals> {
+    let (sum, days) = daily.fold((None::<Totals>, 0), |(sum, days), totals| {
+        let sum = match sum {
+            Some(sum) => sum.plus(totals),
+            None => totals.clone(),
+        };
+        (Some(sum), days + 1)
+    });
+    sum.map(|sum| sum.per_day(days))
+}
+
+/// Foods of `eaten` with the most of `amount`, the largest first.
+fn top(eaten: &HashMap<i32, FoodTotal>, amount: fn(&FoodTotal) -> i32) -> Vec<FoodTotal> {
+    let mut foods: Vec<&FoodTotal> = eaten.values().filter(|f| amount(f) > 0).collect();
+    foods.sort_by(|a, b| {
+        amount(b)
+            .cmp(&amount(a))
+            .then_with(|| a.food.name.cmp(&b.food.name))
+    });
+    foods.into_iter().take(TOP_FOODS).cloned().collect()
+}
+
+/// Reports on what the user logged from `from` to `to`, with foods named in
+/// `language`.
+pub fn report(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    from: Date,
+    to: Date,
+    language: Language,
+) -> QueryResult<Report> {
+    let logged = diary::between(conn, user_id, from, to, language)?;
+    let daily = diary::totals_by_day(&logged);
+    let all_targets = targets::for_user(conn, user_id)?;
+    let day_targets: Vec<_> = daily
+        .iter()
+        .map(|(date, _)| targets::on(&all_targets, *date))
+        .collect();
+
+    let adherence = Macro::ALL
+        .into_iter()
+        .filter(|m| {
+            day_targets
+                .iter()
+                .flatten()
+                .any(|t| m.of_target(t).is_some())
+        })
+        .map(|nutrient| {
+            let mut adherence = Adherence {
+                nutrient,
+                under: 0,
+                on_target: 0,
+                over: 0,
+            };
+            for ((_, totals), target) in daily.iter().zip(&day_targets) {
+                let eaten = nutrient.of_totals(totals);
+                let goal = target.as_ref().and_then(|t| nutrient.of_target(t));
+                if let (Some(eaten), Some(goal)) = (eaten, goal) {
+                    match Status::of(eaten, goal) {
+                        Status::Under => adherence.under += 1,
+                        Status::OnTarget => adherence.on_target += 1,
+                        Status::Over => adherence.over += 1,
+                    }
+                }
+            }
+            adherence
+        })
+        .collect();
+
+    let mut eaten: HashMap<i32, FoodTotal> = HashMap::new();
+    for (food, grams) in logged.iter().flat_map(|l| &l.foods) {
+        let total = eaten.entry(food.id).or_insert_with(|| FoodTotal {
+            food: food.clone(),
+            grams: 0,
+            calories: 0,
+            protein: 0,
+        });
+        total.grams = total.grams.saturating_add(*grams);
+        total.calories = total
+            .calories
+            .saturating_add(units::scale(food.calories, *grams));
+        total.protein = total
+            .protein
+            .saturating_add(units::scale(food.protein, *grams));
+    }
+
+    let mut weekday = Weekday::Monday;
+    let mut weekdays = vec![];
+    for _ in 0..7 {
+        let days: Vec<&Totals> = daily
+            .iter()
+            .filter(|(date, _)| date.weekday() == weekday)
+            .map(|(_, totals)| totals)
+            .collect();
+        weekdays.push(WeekdayAverage {
+            weekday,
+            days: days.len(),
+            average: average(days.into_iter()),
+        });
+        weekday = weekday.next();
+    }
+
+    Ok(Report {
+        from,
+        to,
+        average: average(daily.iter().map(|(_, totals)| totals)),
+        daily,
+        targets: day_targets,
+        adherence,
+        top_by_calories: top(&eaten, |f| f.calories),
+        top_by_protein: top(&eaten, |f| f.protein),
+        weekdays,
+    })
+}
@@ -1,11 +1,83 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    data_sources (id) {
+        id -> Integer,
+        kind -> Text,
+        name -> Text,
+        trusted -> Bool,
+    }
+}
+
+diesel::table! {
+    diary_entries (id) {
+        id -> Integer,
+        user_id -> Integer,
+        date -> Date,
+        slot -> Text,
+        food_id -> Nullable<Integer>,
+        meal_id -> Nullable<Integer>,
+        amount -> Text,
+        quantity -> Integer,
+    }
+}
+
+diesel::table! {
+    food_groups (id) {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    food_names (id) {
+        id -> Integer,
+        food_id -> Integer,
+        language -> Text,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    food_nutrients (food_id, nutrient_id) {
+        food_id -> Integer,
+        nutrient_id -> Integer,
+        amount -> Integer,
+    }
+}
+
+diesel::table! {
+    food_portions (id) {
+        id -> Integer,
+        food_id -> Integer,
+        name -> Text,
+        grams -> Integer,
+    }
+}
+
 diesel::table! {
     foods (id) {
         id -> Integer,
         name -> Text,
         calories -> Integer,
         protein -> Integer,
+        source -> Nullable<Text>,
+        barcode -> Nullable<Text>,
+        data_source_id -> Nullable<Integer>,
+        food_group_id -> Nullable<Integer>,
+        fat -> Nullable<Integer>,
+        carbohydrates -> Nullable<Integer>,
+        fiber -> Nullable<Integer>,
+        owner_id -> Nullable<Integer>,
+        visibility -> Text,
+        density -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    households (id) {
+        id -> Integer,
+        name -> Text,
     }
 }
 
@@ -13,6 +85,8 @@ diesel::table! {
     meal_food_relations (food_id, meal_id) {
         food_id -> Integer,
         meal_id -> Integer,
+        amount -> Text,
+        grams -> Integer,
     }
 }
 
@@ -20,6 +94,81 @@ diesel::table! {
     meals (id) {
         id -> Integer,
         name -> Text,
+        owner_id -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    measurements (id) {
+        id -> Integer,
+        user_id -> Integer,
+        date -> Date,
+        weight -> Nullable<Integer>,
+        waist -> Nullable<Integer>,
+        body_fat -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    nutrients (id) {
+        id -> Integer,
+        name -> Text,
+        unit -> Text,
+    }
+}
+
+diesel::table! {
+    planned_entries (id) {
+        id -> Integer,
+        user_id -> Integer,
+        date -> Date,
+        slot -> Text,
+        food_id -> Nullable<Integer>,
+        meal_id -> Nullable<Integer>,
+        amount -> Text,
+        quantity -> Integer,
+        logged_entry_id -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    profiles (user_id) {
+        user_id -> Integer,
+        sex -> Text,
+        birth_date -> Date,
+        height -> Integer,
+        activity -> Text,
+        body_fat -> Nullable<Integer>,
+        weekly_change -> Integer,
+    }
+}
+
+diesel::table! {
+    recipe_ingredients (id) {
+        id -> Integer,
+        recipe_id -> Integer,
+        food_id -> Integer,
+        amount -> Text,
+        grams -> Integer,
+    }
+}
+
+diesel::table! {
+    recipes (food_id) {
+        food_id -> Integer,
+        cooked_weight -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    targets (user_id, weekday) {
+        user_id -> Integer,
+        weekday -> Integer,
+        calories -> Nullable<Integer>,
+        protein -> Nullable<Integer>,
+        fat -> Nullable<Integer>,
+        carbohydrates -> Nullable<Integer>,
+        fiber -> Nullable<Integer>,
     }
 }
 
@@ -28,7 +177,55 @@ diesel::table! {
         id -> Integer,
         username -> Text,
         password -> Text,
+        language -> Text,
+        admin -> Bool,
+        household_id -> Nullable<Integer>,
+        weight_unit -> Text,
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(foods, meal_food_relations, meals, users,);
+diesel::joinable!(diary_entries -> foods (food_id));
+diesel::joinable!(diary_entries -> meals (meal_id));
+diesel::joinable!(diary_entries -> users (user_id));
+diesel::joinable!(food_names -> foods (food_id));
+diesel::joinable!(food_nutrients -> foods (food_id));
+diesel::joinable!(food_nutrients -> nutrients (nutrient_id));
+diesel::joinable!(food_portions -> foods (food_id));
+diesel::joinable!(foods -> data_sources (data_source_id));
+diesel::joinable!(foods -> food_groups (food_group_id));
+diesel::joinable!(foods -> users (owner_id));
+diesel::joinable!(meal_food_relations -> foods (food_id));
+diesel::joinable!(meal_food_relations -> meals (meal_id));
+diesel::joinable!(meals -> users (owner_id));
+diesel::joinable!(measurements -> users (user_id));
+diesel::joinable!(planned_entries -> diary_entries (logged_entry_id));
+diesel::joinable!(planned_entries -> foods (food_id));
+diesel::joinable!(planned_entries -> meals (meal_id));
+diesel::joinable!(planned_entries -> users (user_id));
+diesel::joinable!(profiles -> users (user_id));
+diesel::joinable!(recipe_ingredients -> foods (food_id));
+diesel::joinable!(recipe_ingredients -> recipes (recipe_id));
+diesel::joinable!(recipes -> foods (food_id));
+diesel::joinable!(targets -> users (user_id));
+diesel::joinable!(users -> households (household_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    data_sources,
+    diary_entries,
+    food_groups,
+    food_names,
+    food_nutrients,
+    food_portions,
+    foods,
+    households,
+    meal_food_relations,
+    meals,
+    measurements,
+    nutrients,
+    planned_entries,
+    profiles,
+    recipe_ingredients,
+    recipes,
+    targets,
+    users,
+);
@@ -0,0 +1,450 @@
+use diesel::prelude::*;
+use diesel::sql_types::{Integer, Nullable, Text};
+use diesel::sqlite::Sqlite;
+use diesel::IntoSql;
+use simple_error::{SimpleError, SimpleResult};
+
+use crate::food_query::{self, Comparison, Filter, Nutrient};
+use crate::language::Language;
+use crate::models::{DataSource, Food, FoodGroup};
+use crate::schema::{data_sources, food_groups, food_names, foods};
+use crate::visibility::{visible_to, Viewer};
+
+diesel::table! {
+    /// FTS5 index over `foods.name` and the names in `food_names`, kept in
+    /// sync by triggers on both tables.
+    foods_fts (rowid) {
+        rowid -> Integer,
+        /// The hidden column named after the table, the left hand side of MATCH.
+        #[sql_name = "foods_fts"]
+        document -> Text,
+        rank -> Double,
+    }
+}
+
+diesel::table! {
+    /// `fts5vocab` view of the terms in `foods_fts`.
+    foods_fts_vocab (term) {
+        term -> Text,
+        /// Number of foods containing the term.
+        doc -> Integer,
+        cnt -> Integer,
+    }
+}
+
+diesel::joinable!(foods_fts -> foods (rowid));
+diesel::allow_tables_to_appear_in_same_query!(foods_fts, foods);
+diesel::allow_tables_to_appear_in_same_query!(foods_fts, food_groups);
+diesel::allow_tables_to_appear_in_same_query!(foods_fts, data_sources);
+diesel::allow_tables_to_appear_in_same_query!(foods_fts, food_names);
+
+diesel::sql_function!(fn ifnull(x: Nullable<Text>, y: Text) -> Text);
+diesel::sql_function!(fn length(x: Text) -> Integer);
+
+diesel::infix_operator!(Matches, " MATCH ");
+
+/// Maximum number of foods returned per request.
+pub const PAGE_SIZE: i64 = 50;
+
+/// Below this many exact matches a typo corrected search is tried as well.
+pub const FUZZY_THRESHOLD: usize = 5;
+
+pub type SearchResult = (Food, Option<FoodGroup>, Option<DataSource>);
+
+/// Column the search results are ordered by.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SortBy {
+    /// Best text match first, alphabetical when there is no search text.
+    #[default]
+    Relevance,
+    Name,
+    Calories,
+    Protein,
+    ProteinPerKcal,
+}
+
+impl SortBy {
+    pub fn from_param(param: &str) -> SortBy {
+        match param {
+            "name" => SortBy::Name,
+            "kcal" => SortBy::Calories,
+            "protein" => SortBy::Protein,
+            "protein_per_kcal" => SortBy::ProteinPerKcal,
+            _ => SortBy::Relevance,
+        }
+    }
+
+    pub fn param(&self) -> &'static str {
+        match self {
+            SortBy::Relevance => "relevance",
+            SortBy::Name => "name",
+            SortBy::Calories => "kcal",
+            SortBy::Protein => "protein",
+            SortBy::ProteinPerKcal => "protein_per_kcal",
+        }
+    }
+
+    /// Direction used when a column is first sorted on. Nutrients are more
+    /// interesting largest first.
+    pub fn descending_by_default(&self) -> bool {
+        matches!(self, SortBy::Protein | SortBy::ProteinPerKcal)
+    }
+}
+
+pub struct SearchParams<'a> {
+    pub text: &'a str,
+    pub trusted_only: bool,
+    pub food_group: Option<i32>,
+    pub sort: SortBy,
+    pub descending: bool,
+    pub page: i64,
+    /// Language food names are shown and sorted in. Searches match names in
+    /// every language.
+    pub language: Language,
+    /// Only foods this user can see are returned.
+    pub viewer: &'a Viewer,
+}
+
+#[derive(Default)]
+pub struct SearchPage {
+    pub results: Vec<SearchResult>,
+    /// Whether there are more pages after this one.
+    pub more: bool,
+    /// Typo corrected search text, set when the original text matched few
+    /// foods and the corrected one more. Foods matching it are appended to
+    /// `results`.
+    pub suggestion: Option<String>,
+}
+
+pub(crate) fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+}
+
+/// Turns free text into an FTS5 query in which every word has to match the
+/// beginning of a word in the food name. Words are quoted so that user input
+/// can't inject FTS syntax.
+pub fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = words(text).map(|t| format!("\"{}\"*", t)).collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Lowercases and strips diacritics the same way the `unicode61` tokenizer of
+/// the index does, so words can be compared with indexed terms.
+pub fn fold(word: &str) -> String {
+    word.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ý' | 'ÿ' => 'y',
+            c => c,
+        })
+        .collect()
+}
+
+/// Edits allowed before a term is considered too different from a word.
+fn max_distance(word: &str) -> usize {
+    match word.chars().count() {
+        0..=4 => 1,
+        5..=8 => 2,
+        _ => 3,
+    }
+}
+
+/// The first string after all strings starting with `prefix`, which must not
+/// be empty.
+fn prefix_end(prefix: &str) -> String {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    let last = chars.pop().map_or(0, u32::from);
+    chars.push(char::from_u32(last + 1).unwrap_or(char::MAX));
+    chars.into_iter().collect()
+}
+
+/// Replaces every word that isn't the beginning of any indexed term with the
+/// closest term by edit distance, preferring terms used by many foods. Words
+/// are compared both to whole terms and to terms cut to the word's length, so
+/// half typed words are corrected too. Only terms with the same first letter
+/// and long enough to be close are loaded, so a typo in the first letter isn't
+/// corrected. Returns `None` if nothing changed.
+pub fn suggest(conn: &mut SqliteConnection, text: &str) -> QueryResult<Option<String>> {
+    let mut changed = false;
+    let mut corrected = vec![];
+    for word in words(text) {
+        let folded = fold(word);
+        let known = foods_fts_vocab::table
+            .filter(foods_fts_vocab::term.ge(&folded))
+            .filter(foods_fts_vocab::term.lt(prefix_end(&folded)))
+            .select(foods_fts_vocab::term)
+            .first::<String>(conn)
+            .optional()?
+            .is_some();
+        if known {
+            corrected.push(word.to_owned());
+            continue;
+        }
+
+        let len = folded.chars().count();
+        let first: String = folded.chars().take(1).collect();
+        let shortest = len.saturating_sub(max_distance(&folded)) as i32;
+        let vocab: Vec<(String, i32)> = foods_fts_vocab::table
+            .filter(foods_fts_vocab::term.ge(&first))
+            .filter(foods_fts_vocab::term.lt(prefix_end(&first)))
+            .filter(length(foods_fts_vocab::term).ge(shortest))
+            .select((foods_fts_vocab::term, foods_fts_vocab::doc))
+            .load(conn)?;
+        let best = vocab
+            .iter()
+            .map(|(term, doc)| {
+                let prefix: String = term.chars().take(len).collect();
+                let distance =
+                    strsim::osa_distance(&folded, term).min(strsim::osa_distance(&folded, &prefix));
+                (distance, -doc, term)
+            })
+            .filter(|(distance, _, _)| *distance <= max_distance(&folded))
+            .min();
+
+        match best {
+            Some((_, _, term)) => {
+                changed = true;
+                corrected.push(term.clone());
+            }
+            None => corrected.push(word.to_owned()),
+        }
+    }
+
+    Ok(if changed {
+        Some(corrected.join(" "))
+    } else {
+        None
+    })
+}
+
+/// Ids of the food groups a `group:` filter refers to, none if no group
+/// starts with the name.
+fn resolve_group(conn: &mut SqliteConnection, name: &str) -> QueryResult<Vec<i32>> {
+    let prefix = fold(name);
+    Ok(food_groups::table
+        .load::<FoodGroup>(conn)?
+        .into_iter()
+        .filter(|g| fold(&g.name).starts_with(&prefix))
+        .map(|g| g.id)
+        .collect())
+}
+
+/// Replaces the name of `food` with its translation, if there is one.
+pub fn translate(
+    conn: &mut SqliteConnection,
+    food: &mut Food,
+    language: Language,
+) -> QueryResult<()> {
+    let name: Option<String> = food_names::table
+        .filter(food_names::food_id.eq(food.id))
+        .filter(food_names::language.eq(language.code()))
+        .select(food_names::name)
+        .first(conn)
+        .optional()?;
+
+    if let Some(name) = name {
+        food.name = name;
+    }
+    Ok(())
+}
+
+/// Loads up to `limit` foods matching `text` and the nutrient filters, best
+/// matches first. Without any search text foods are listed alphabetically.
+/// Names are translated to `params.language` where possible.
+fn load_matches(
+    conn: &mut SqliteConnection,
+    text: &str,
+    filters: &[Filter],
+    group_ids: &Option<Vec<i32>>,
+    params: &SearchParams,
+    limit: i64,
+    offset: i64,
+) -> QueryResult<Vec<SearchResult>> {
+    let mut query = foods::table
+        .inner_join(foods_fts::table)
+        .left_join(food_groups::table)
+        .left_join(data_sources::table)
+        .left_join(
+            food_names::table.on(food_names::food_id
+                .eq(foods::id)
+                .and(food_names::language.eq(params.language.code()))),
+        )
+        .select((
+            Food::as_select(),
+            Option::<FoodGroup>::as_select(),
+            Option::<DataSource>::as_select(),
+            food_names::name.nullable(),
+        ))
+        .into_boxed();
+
+    let fts = fts_query(text);
+    let has_text = fts.is_some();
+    if let Some(q) = fts {
+        query = query.filter(Matches::new(foods_fts::document, q.into_sql::<Text>()));
+    }
+
+    // Integer division by zero calories gives NULL, those foods are put last
+    // in both directions.
+    let protein_per_kcal = foods::protein * 10_000 / foods::calories;
+    let no_calories = foods::calories.eq(0);
+    let shown_name = ifnull(food_names::name.nullable(), foods::name);
+    query = match (params.sort, params.descending) {
+        (SortBy::Relevance, _) if has_text => query.order_by(foods_fts::rank),
+        (SortBy::Relevance | SortBy::Name, false) => query.order_by(shown_name),
+        (SortBy::Relevance | SortBy::Name, true) => query.order_by(shown_name.desc()),
+        (SortBy::Calories, false) => query.order_by(foods::calories),
+        (SortBy::Calories, true) => query.order_by(foods::calories.desc()),
+        (SortBy::Protein, false) => query.order_by(foods::protein),
+        (SortBy::Protein, true) => query.order_by(foods::protein.desc()),
+        (SortBy::ProteinPerKcal, false) => query.order_by((no_calories, protein_per_kcal)),
+        (SortBy::ProteinPerKcal, true) => query.order_by((no_calories, protein_per_kcal.desc())),
+    };
+    query = query
+        .then_order_by(ifnull(food_names::name.nullable(), foods::name))
+        .then_order_by(foods::id);
+    query = query.filter(visible_to(params.viewer));
+    if params.trusted_only {
+        query = query.filter(data_sources::trusted.eq(true));
+    }
+    if let Some(group_id) = params.food_group {
+        query = query.filter(foods::food_group_id.eq(group_id));
+    }
+    if let Some(ids) = group_ids {
+        query = query.filter(foods::food_group_id.eq_any(ids.clone()));
+    }
+
+    for filter in filters {
+        if let Filter::Nutrient {
+            nutrient,
+            comparison,
+            value,
+        } = filter
+        {
+            // Unknown values are NULL and never match a comparison
+            let column: Box<dyn BoxableExpression<_, Sqlite, SqlType = Nullable<Integer>>> =
+                match nutrient {
+                    Nutrient::Calories => Box::new(foods::calories.nullable()),
+                    Nutrient::Protein => Box::new(foods::protein.nullable()),
+                    Nutrient::Fat => Box::new(foods::fat),
+                    Nutrient::Carbohydrates => Box::new(foods::carbohydrates),
+                    Nutrient::Fiber => Box::new(foods::fiber),
+                };
+            query = match comparison {
+                Comparison::Less => query.filter(column.lt(*value)),
+                Comparison::LessOrEqual => query.filter(column.le(*value)),
+                Comparison::Greater => query.filter(column.gt(*value)),
+                Comparison::GreaterOrEqual => query.filter(column.ge(*value)),
+                Comparison::Equal => query.filter(column.eq(*value)),
+            };
+        }
+    }
+
+    let found = query.limit(limit).offset(offset).load::<(
+        Food,
+        Option<FoodGroup>,
+        Option<DataSource>,
+        Option<String>,
+    )>(conn)?;
+
+    Ok(found
+        .into_iter()
+        .map(|(mut food, group, source, translated)| {
+            if let Some(name) = translated {
+                food.name = name;
+            }
+            (food, group, source)
+        })
+        .collect())
+}
+
+/// Returns one page of foods matching `params`, whose text may contain
+/// filters as described in [`food_query::parse`]. When the first page has few
+/// exact matches, foods matching a typo corrected version of the text follow
+/// them. Invalid queries give the inner error, which is meant to be shown to
+/// the user, unlike database errors.
+pub fn search_foods(
+    conn: &mut SqliteConnection,
+    params: &SearchParams,
+) -> QueryResult<SimpleResult<SearchPage>> {
+    let query = match food_query::parse(params.text) {
+        Ok(query) => query,
+        Err(e) => return Ok(Err(e)),
+    };
+
+    let mut group_ids: Option<Vec<i32>> = None;
+    for filter in &query.filters {
+        if let Filter::Group(name) = filter {
+            let ids = resolve_group(conn, name)?;
+            if ids.is_empty() {
+                return Ok(Err(SimpleError::new(format!(
+                    "No food group starts with \"{}\"",
+                    name
+                ))));
+            }
+            group_ids = Some(match group_ids {
+                Some(prev) => prev.into_iter().filter(|id| ids.contains(id)).collect(),
+                None => ids,
+            });
+        }
+    }
+
+    let page = params.page.max(0);
+    let mut results = load_matches(
+        conn,
+        &query.text,
+        &query.filters,
+        &group_ids,
+        params,
+        PAGE_SIZE + 1,
+        page * PAGE_SIZE,
+    )?;
+    let more = results.len() as i64 > PAGE_SIZE;
+    results.truncate(PAGE_SIZE as usize);
+
+    let mut suggestion = None;
+    if page == 0 && results.len() < FUZZY_THRESHOLD {
+        if let Some(corrected) = suggest(conn, &query.text)? {
+            let limit = PAGE_SIZE - results.len() as i64;
+            let fuzzy = load_matches(
+                conn,
+                &corrected,
+                &query.filters,
+                &group_ids,
+                params,
+                limit,
+                0,
+            )?;
+            let mut found_more = false;
+            for found in fuzzy {
+                if !results.iter().any(|(food, _, _)| food.id == found.0.id) {
+                    results.push(found);
+                    found_more = true;
+                }
+            }
+            // The index has the words of every food, so only suggest
+            // corrections that lead to foods the user can see
+            if found_more {
+                suggestion = Some(query.with_text(&corrected));
+            }
+        }
+    }
+
+    Ok(Ok(SearchPage {
+        results,
+        more,
+        suggestion,
+    }))
+}
@@ -0,0 +1,199 @@
+//! A small dense two-phase simplex solver for linear programs. Pivots follow
+//! Bland's rule, so the same problem always gives the same solution.
+
+/// Values closer to zero than this count as zero.
+const EPSILON: f64 = 1e-9;
+
+/// Pivots after which the solver gives up. Bland's rule doesn't cycle, so this
+/// only guards against rounding errors.
+const MAX_PIVOTS: usize = 10_000;
+
+struct Tableau {
+    /// A row per constraint, with the right-hand side last.
+    rows: Vec<Vec<f64>>,
+    /// Reduced costs of the columns, with the negated objective value last.
+    objective: Vec<f64>,
+    /// Column basic in each row.
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn rhs(&self, row: usize) -> f64 {
+        self.rows[row][self.objective.len() - 1]
+    }
+
+    fn pivot(&mut self, row: usize, column: usize) {
+        let pivot = self.rows[row][column];
+        for value in &mut self.rows[row] {
+            *value /= pivot;
+        }
+        let pivot_row = self.rows[row].clone();
+        let others = self
+            .rows
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, _)| *i != row)
+            .map(|(_, r)| r)
+            .chain(std::iter::once(&mut self.objective));
+        for other in others {
+            let factor = other[column];
+            if factor.abs() > EPSILON {
+                for (value, p) in other.iter_mut().zip(&pivot_row) {
+                    *value -= factor * p;
+                }
+            }
+        }
+        self.basis[row] = column;
+    }
+
+    /// Makes `cost`, padded with zeros, the objective of the current basis.
+    fn set_objective(&mut self, cost: &[f64]) {
+        self.objective = vec![0.0; self.objective.len()];
+        self.objective[..cost.len()].copy_from_slice(cost);
+        for (row, &basic) in self.rows.iter().zip(&self.basis) {
+            let factor = self.objective[basic];
+            if factor != 0.0 {
+                for (value, r) in self.objective.iter_mut().zip(row) {
+                    *value -= factor * r;
+                }
+            }
+        }
+    }
+
+    /// Pivots until no column before `columns` can lower the objective.
+    /// `None` if it's unbounded or takes too long.
+    fn optimize(&mut self, columns: usize) -> Option<()> {
+        for _ in 0..MAX_PIVOTS {
+            let Some(column) = (0..columns).find(|&j| self.objective[j] < -EPSILON) else {
+                return Some(());
+            };
+            let row = (0..self.rows.len())
+                .filter(|&i| self.rows[i][column] > EPSILON)
+                .min_by(|&a, &b| {
+                    let ratio = |i: usize| self.rhs(i) / self.rows[i][column];
+                    ratio(a)
+                        .total_cmp(&ratio(b))
+                        .then(self.basis[a].cmp(&self.basis[b]))
+                })?;
+            self.pivot(row, column);
+        }
+        None
+    }
+}
+
+/// Finds `x >= 0` minimizing `cost · x` with `constraints[i] · x = rhs[i]`
+/// for every `i`. Each constraint has a coefficient per variable. `None` if
+/// there is no such `x` or the cost has no lower bound.
+pub fn minimize(cost: &[f64], constraints: &[Vec<f64>], rhs: &[f64]) -> Option<Vec<f64>> {
+    let variables = cost.len();
+    let columns = variables + constraints.len();
+
+    // An artificial variable per constraint gives a first feasible basis
+    let rows = constraints
+        .iter()
+        .zip(rhs)
+        .enumerate()
+        .map(|(i, (constraint, &b))| {
+            let sign = if b < 0.0 { -1.0 } else { 1.0 };
+            let mut row: Vec<f64> = constraint.iter().map(|a| sign * a).collect();
+            row.resize(columns + 1, 0.0);
+            row[variables + i] = 1.0;
+            row[columns] = sign * b;
+            row
+        })
+        .collect();
+    let mut tableau = Tableau {
+        rows,
+        objective: vec![0.0; columns + 1],
+        basis: (variables..columns).collect(),
+    };
+
+    let mut artificial_cost = vec![0.0; columns];
+    artificial_cost[variables..].fill(1.0);
+    tableau.set_objective(&artificial_cost);
+    tableau.optimize(columns)?;
+    let scale = rhs.iter().map(|b| b.abs()).sum::<f64>().max(1.0);
+    if -tableau.objective[columns] > 1e-7 * scale {
+        return None;
+    }
+
+    // Artificial variables left in the basis are zero, so swap them for any
+    // real one. Rows where there's none are redundant.
+    for row in 0..tableau.rows.len() {
+        if tableau.basis[row] >= variables {
+            if let Some(column) = (0..variables).find(|&j| tableau.rows[row][j].abs() > EPSILON) {
+                tableau.pivot(row, column);
+            }
+        }
+    }
+
+    tableau.set_objective(cost);
+    tableau.optimize(variables)?;
+
+    let mut x = vec![0.0; variables];
+    for (row, &basic) in tableau.basis.iter().enumerate() {
+        if basic < variables {
+            x[basic] = tableau.rhs(row).max(0.0);
+        }
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(x: &[f64], expected: &[f64]) {
+        assert_eq!(x.len(), expected.len());
+        for (a, b) in x.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-6, "{:?} != {:?}", x, expected);
+        }
+    }
+
+    #[test]
+    fn minimizes_with_slack_variables() {
+        // Maximize x + y with x + 2y <= 4 and 3x + y <= 6, i.e. minimize
+        // -x - y with a slack variable per constraint
+        let cost = [-1.0, -1.0, 0.0, 0.0];
+        let constraints = vec![vec![1.0, 2.0, 1.0, 0.0], vec![3.0, 1.0, 0.0, 1.0]];
+        let x = minimize(&cost, &constraints, &[4.0, 6.0]).unwrap();
+        assert_close(&x, &[1.6, 1.2, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn handles_negative_right_hand_sides() {
+        // x - y = -2 with x, y >= 0, minimizing y
+        let x = minimize(&[0.0, 1.0], &[vec![1.0, -1.0]], &[-2.0]).unwrap();
+        assert_close(&x, &[0.0, 2.0]);
+    }
+
+    #[test]
+    fn same_problem_gives_same_solution() {
+        // Every point on x + y = 1 is optimal
+        let solve = || minimize(&[1.0, 1.0], &[vec![1.0, 1.0]], &[1.0]).unwrap();
+        let first = solve();
+        for _ in 0..10 {
+            assert_eq!(solve(), first);
+        }
+    }
+
+    #[test]
+    fn no_solution_when_infeasible() {
+        // x + y = 1 and x + y = 2
+        let constraints = vec![vec![1.0, 1.0], vec![1.0, 1.0]];
+        assert_eq!(minimize(&[1.0, 1.0], &constraints, &[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn no_solution_when_unbounded() {
+        // x - y = 0 minimizing -x
+        assert_eq!(minimize(&[-1.0, 0.0], &[vec![1.0, -1.0]], &[0.0]), None);
+    }
+
+    #[test]
+    fn redundant_constraints_are_fine() {
+        let constraints = vec![vec![1.0, 1.0], vec![2.0, 2.0]];
+        let x = minimize(&[1.0, 2.0], &constraints, &[3.0, 6.0]).unwrap();
+        assert_close(&x, &[3.0, 0.0]);
+    }
+}
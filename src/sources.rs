@@ -0,0 +1,60 @@
+use diesel::{
+    insert_or_ignore_into, BoolExpressionMethods, ExpressionMethods, QueryDsl, QueryResult,
+    RunQueryDsl, SqliteConnection,
+};
+
+use crate::schema::data_sources;
+
+/// Where a food's nutrient values originally came from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SourceKind {
+    Livsmedelsverket,
+    OpenFoodFacts,
+    Usda,
+    User,
+}
+
+impl SourceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceKind::Livsmedelsverket => "livsmedelsverket",
+            SourceKind::OpenFoodFacts => "open_food_facts",
+            SourceKind::Usda => "usda",
+            SourceKind::User => "user",
+        }
+    }
+
+    /// Curated reference databases are trusted, crowd sourced and user
+    /// entered values are not.
+    pub fn trusted(&self) -> bool {
+        matches!(self, SourceKind::Livsmedelsverket | SourceKind::Usda)
+    }
+}
+
+/// Display name used for every food created through the web UI.
+pub const USER_CREATED: &str = "User created";
+
+/// Returns the id of the data source, creating it the first time a food is
+/// recorded from it.
+pub fn find_or_create(
+    conn: &mut SqliteConnection,
+    kind: SourceKind,
+    name: &str,
+) -> QueryResult<i32> {
+    insert_or_ignore_into(data_sources::table)
+        .values((
+            data_sources::kind.eq(kind.as_str()),
+            data_sources::name.eq(name),
+            data_sources::trusted.eq(kind.trusted()),
+        ))
+        .execute(conn)?;
+
+    data_sources::table
+        .filter(
+            data_sources::kind
+                .eq(kind.as_str())
+                .and(data_sources::name.eq(name)),
+        )
+        .select(data_sources::id)
+        .first(conn)
+}
@@ -0,0 +1,215 @@
+use diesel::prelude::*;
+use time::{Date, Duration};
+
+use crate::diary;
+use crate::models::Target;
+use crate::nutrition::Totals;
+use crate::schema::targets;
+
+/// Weekday of the targets that apply to every day.
+pub const EVERY_DAY: i32 = 0;
+
+/// How far from a target, in percent of it, intake still counts as on target.
+pub const TOLERANCE: i32 = 10;
+
+/// Energy and the nutrients targets can be set for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Macro {
+    Calories,
+    Protein,
+    Fat,
+    Carbohydrates,
+    Fiber,
+}
+
+impl Macro {
+    pub const ALL: [Macro; 5] = [
+        Macro::Calories,
+        Macro::Protein,
+        Macro::Fat,
+        Macro::Carbohydrates,
+        Macro::Fiber,
+    ];
+
+    /// Name of the column and form field.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Macro::Calories => "calories",
+            Macro::Protein => "protein",
+            Macro::Fat => "fat",
+            Macro::Carbohydrates => "carbohydrates",
+            Macro::Fiber => "fiber",
+        }
+    }
+
+    pub fn from_value(value: &str) -> Option<Macro> {
+        Macro::ALL.into_iter().find(|m| m.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Macro::Calories => "Energy",
+            Macro::Protein => "Protein",
+            Macro::Fat => "Fat",
+            Macro::Carbohydrates => "Carbs",
+            Macro::Fiber => "Fiber",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Macro::Calories => "kcal",
+            _ => "g",
+        }
+    }
+
+    /// The amount eaten, `None` if a food lacks the value.
+    pub fn of_totals(&self, totals: &Totals) -> Option<i32> {
+        match self {
+            Macro::Calories => Some(totals.calories),
+            Macro::Protein => Some(totals.protein),
+            Macro::Fat => totals.fat,
+            Macro::Carbohydrates => totals.carbohydrates,
+            Macro::Fiber => totals.fiber,
+        }
+    }
+
+    pub fn of_target(&self, target: &Target) -> Option<i32> {
+        match self {
+            Macro::Calories => target.calories,
+            Macro::Protein => target.protein,
+            Macro::Fat => target.fat,
+            Macro::Carbohydrates => target.carbohydrates,
+            Macro::Fiber => target.fiber,
+        }
+    }
+
+    pub fn set(&self, target: &mut Target, value: Option<i32>) {
+        match self {
+            Macro::Calories => target.calories = value,
+            Macro::Protein => target.protein = value,
+            Macro::Fat => target.fat = value,
+            Macro::Carbohydrates => target.carbohydrates = value,
+            Macro::Fiber => target.fiber = value,
+        }
+    }
+}
+
+/// How an amount eaten compares to its target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Under,
+    OnTarget,
+    Over,
+}
+
+impl Status {
+    /// Compares `eaten` to `target`, allowing [`TOLERANCE`] percent either way.
+    pub fn of(eaten: i32, target: i32) -> Status {
+        let eaten = i64::from(eaten) * 100;
+        let target = i64::from(target);
+        if eaten < target * i64::from(100 - TOLERANCE) {
+            Status::Under
+        } else if eaten > target * i64::from(100 + TOLERANCE) {
+            Status::Over
+        } else {
+            Status::OnTarget
+        }
+    }
+}
+
+/// 1 for Monday to 7 for Sunday, as stored in `targets.weekday`.
+pub fn weekday(date: Date) -> i32 {
+    i32::from(date.weekday().number_from_monday())
+}
+
+/// The user's targets for every day followed by those for single weekdays.
+pub fn for_user(conn: &mut SqliteConnection, user_id: i32) -> QueryResult<Vec<Target>> {
+    targets::table
+        .filter(targets::user_id.eq(user_id))
+        .order(targets::weekday)
+        .select(Target::as_select())
+        .load(conn)
+}
+
+/// The targets that apply on `date`, those of its weekday where set and those
+/// of every day otherwise. `None` if there are no targets that day.
+pub fn on(targets: &[Target], date: Date) -> Option<Target> {
+    let find = |weekday: i32| targets.iter().find(|t| t.weekday == weekday);
+    let every_day = find(EVERY_DAY);
+    let that_day = find(weekday(date));
+
+    let mut target = Target {
+        user_id: every_day.or(that_day)?.user_id,
+        weekday: weekday(date),
+        ..Target::default()
+    };
+    for nutrient in Macro::ALL {
+        let value = that_day
+            .and_then(|t| nutrient.of_target(t))
+            .or_else(|| every_day.and_then(|t| nutrient.of_target(t)));
+        nutrient.set(&mut target, value);
+    }
+    Macro::ALL
+        .iter()
+        .any(|m| m.of_target(&target).is_some())
+        .then_some(target)
+}
+
+/// Saves targets for [`EVERY_DAY`] or single weekdays. Targets without any
+/// values are removed.
+pub fn save(conn: &mut SqliteConnection, targets: &[Target]) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        for target in targets {
+            if Macro::ALL.iter().all(|m| m.of_target(target).is_none()) {
+                diesel::delete(targets::table.find((target.user_id, target.weekday)))
+                    .execute(conn)?;
+            } else {
+                diesel::replace_into(targets::table)
+                    .values(target)
+                    .execute(conn)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// A day of a week summary.
+pub struct WeekDay {
+    pub date: Date,
+    /// What was eaten, `None` if nothing was logged.
+    pub totals: Option<Totals>,
+    pub target: Option<Target>,
+}
+
+impl WeekDay {
+    /// How the day went for a nutrient, `None` if there is nothing to compare.
+    pub fn status(&self, nutrient: Macro) -> Option<Status> {
+        let eaten = nutrient.of_totals(self.totals.as_ref()?)?;
+        let target = nutrient.of_target(self.target.as_ref()?)?;
+        Some(Status::of(eaten, target))
+    }
+}
+
+/// Monday to Sunday of the week `date` is in, with what the user ate and the
+/// targets of each day.
+pub fn week(conn: &mut SqliteConnection, user_id: i32, date: Date) -> QueryResult<Vec<WeekDay>> {
+    let monday = date - Duration::days(i64::from(date.weekday().number_days_from_monday()));
+    let sunday = monday + Duration::days(6);
+    let targets = for_user(conn, user_id)?;
+    let eaten = diary::daily_totals(conn, user_id, monday, sunday)?;
+
+    Ok((0..7)
+        .map(|n| {
+            let date = monday + Duration::days(n);
+            WeekDay {
+                date,
+                totals: eaten
+                    .iter()
+                    .find(|(day, _)| *day == date)
+                    .map(|(_, totals)| totals.clone()),
+                target: on(&targets, date),
+            }
+        })
+        .collect())
+}
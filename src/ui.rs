@@ -1,4 +1,31 @@
 use maud::{html, Markup};
+use time::macros::format_description;
+use time::{Date, Duration};
+use track_notes::diary::{self, Logged, Slot};
+use track_notes::duplicates::Candidate;
+use track_notes::energy::{self, Estimate};
+use track_notes::fixed;
+use track_notes::food_edit::Usage;
+use track_notes::language::Language;
+use track_notes::meals::{self, MealItem};
+use track_notes::measurements::{self, WeightUnit};
+use track_notes::micronutrients::{self, Coverage, Intake};
+use track_notes::models::{
+    DataSource, Food, FoodGroup, FoodPortion, Meal, MealFood, Recipe, RecipeIngredient, Target,
+    User,
+};
+use track_notes::nutrition::Totals;
+use track_notes::planner::{self, PoolItem};
+use track_notes::profile::{self, Activity, Sex};
+use track_notes::recipes;
+use track_notes::reports::{FoodTotal, Report};
+use track_notes::search::SortBy;
+use track_notes::targets::{self, Macro, Status, WeekDay};
+use track_notes::units;
+use track_notes::visibility::Visibility;
+use track_notes::week_plan::Planned;
+
+use crate::{BodyView, DiaryView, ProfileView, SearchData, WeekView, CHART_RANGES};
 
 pub fn layout(child: Markup) -> Markup {
     html! {
@@ -76,59 +103,2497 @@ pub fn sign_in_page(err: Option<&str>) -> Markup {
     }
 }
 
-pub fn food_creator() -> Markup {
+/// Bar at the top of the pages, with the language food names are shown in,
+/// saved on change, and links to the admin tools for admins.
+pub fn toolbar(user: &User) -> Markup {
+    let current = Language::from_code(&user.language).unwrap_or_default();
     html! {
-        div {
-            form
-                id="food-form"
-                hx-post="/create_food"
-                hx-target="#status"
-                hx-swap="outerHTML"
-                class=""
-            {
+        div class="flex justify-end gap-8 py-4" {
+            a href="/diary" class="text-sky-400 hover:underline" { "Diary" }
+            a href="/week" class="text-sky-400 hover:underline" { "Week" }
+            a href="/meal_builder" class="text-sky-400 hover:underline" { "Foods" }
+            a href="/meals" class="text-sky-400 hover:underline" { "Meals" }
+            a href="/planner" class="text-sky-400 hover:underline" { "Planner" }
+            a href="/recipes" class="text-sky-400 hover:underline" { "Recipes" }
+            a href="/targets" class="text-sky-400 hover:underline" { "Targets" }
+            a href="/reports" class="text-sky-400 hover:underline" { "Reports" }
+            a href="/micronutrients" class="text-sky-400 hover:underline" { "Nutrients" }
+            a href="/body" class="text-sky-400 hover:underline" { "Body" }
+            a href="/profile" class="text-sky-400 hover:underline" { "Profile" }
+            @if user.admin {
+                a href="/admin/duplicates" class="text-sky-400 hover:underline" { "Duplicate foods" }
+            }
+            label class="text-zinc-400" {
+                "Food names in "
+                select class="bg-zinc-800 px-2 py-1 rounded-lg" name="language" hx-post="/language" hx-trigger="change" {
+                    @for language in Language::ALL {
+                        option value=(language.code()) selected[language == current] { (language.label()) }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn food_creator(barcode: Option<&str>) -> Markup {
+    html! {
+        div id="food-creator" {
+            (food_form(barcode))
+        }
+    }
+}
+
+fn food_form(barcode: Option<&str>) -> Markup {
+    html! {
+        form
+            id="food-form"
+            hx-post="/create_food"
+            hx-target="#status"
+            hx-swap="outerHTML"
+            class=""
+        {
+            div class="grid grid-cols-2 gap-4" {
+                (food_fields(None, barcode))
+                input
+                    type="submit"
+                    value="Create Food"
+                    class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400 col-span-2"
+                    {}
+                p id="status" {}
+            }
+        }
+    }
+}
+
+/// Inputs of the food form, filled in with `food` when editing one.
+fn food_fields(food: Option<&Food>, barcode: Option<&str>) -> Markup {
+    let amount = |value: Option<i32>| value.map(fixed::format_short).unwrap_or_default();
+    html! {
+        label class="font-bold col-span-2" { "Food Name" }
+        input id="name" class="bg-zinc-800 px-4 py-2 rounded-lg col-span-2" name="name" value=(food.map_or("", |f| f.name.as_str())) {}
+        label for="calories" class="mb-2 font-bold" { "Calories per 100g" }
+        label for="protein" class="mb-2 font-bold" { "Protein per 100g" }
+        input id="calories" class="bg-zinc-800 px-4 py-2 rounded-lg" name="calories" inputmode="decimal" placeholder="kcal" value=(amount(food.map(|f| f.calories))) {}
+        input id="protein" class="bg-zinc-800 px-4 py-2 rounded-lg" name="protein" inputmode="decimal" placeholder="g" value=(amount(food.map(|f| f.protein))) {}
+        div class="col-span-2 grid grid-cols-4 gap-4" {
+            label for="fat" class="font-bold" { "Fat (optional)" }
+            label for="carbohydrates" class="font-bold" { "Carbs (optional)" }
+            label for="fiber" class="font-bold" { "Fiber (optional)" }
+            label for="density" class="font-bold" { "Density (optional)" }
+            input id="fat" class="bg-zinc-800 px-4 py-2 rounded-lg" name="fat" inputmode="decimal" placeholder="g" value=(amount(food.and_then(|f| f.fat))) {}
+            input id="carbohydrates" class="bg-zinc-800 px-4 py-2 rounded-lg" name="carbohydrates" inputmode="decimal" placeholder="g" value=(amount(food.and_then(|f| f.carbohydrates))) {}
+            input id="fiber" class="bg-zinc-800 px-4 py-2 rounded-lg" name="fiber" inputmode="decimal" placeholder="g" value=(amount(food.and_then(|f| f.fiber))) {}
+            input id="density" class="bg-zinc-800 px-4 py-2 rounded-lg" name="density" inputmode="decimal" placeholder="g/ml" title="Needed to enter amounts in dl, msk and other volumes" value=(amount(food.and_then(|f| f.density))) {}
+        }
+        label for="barcode" class="mb-2 font-bold col-span-2" { "Barcode (EAN/UPC, optional)" }
+        input id="barcode" class="bg-zinc-800 px-4 py-2 rounded-lg col-span-2" name="barcode" value=(barcode.or(food.and_then(|f| f.barcode.as_deref())).unwrap_or("")) {}
+        // Imported foods are always public
+        @if matches!(food, None | Some(Food { owner_id: Some(_), .. })) {
+            @let current = food.and_then(|f| Visibility::from_value(&f.visibility)).unwrap_or_default();
+            label for="visibility" class="mb-2 font-bold col-span-2" { "Visible to" }
+            select id="visibility" class="bg-zinc-800 px-4 py-2 rounded-lg col-span-2" name="visibility" {
+                @for visibility in Visibility::ALL {
+                    option value=(visibility.as_str()) selected[visibility == current] { (visibility.label()) }
+                }
+            }
+        }
+    }
+}
+
+/// Form for changing or deleting an existing food, swapped into the food
+/// creator. `usage` is set when a delete needs to be confirmed because meals
+/// or recipes contain the food, or was refused because other users use it.
+pub fn food_editor(
+    food: &Food,
+    portions: &[FoodPortion],
+    recipe: bool,
+    usage: Option<Usage>,
+) -> Markup {
+    html! {
+        form
+            id="food-form"
+            hx-post=(format!("/food/{}", food.id))
+            hx-target="#status"
+            hx-swap="outerHTML"
+        {
+            h3 class="text-xl mb-4" { "Edit " (food.name) }
+            @if recipe {
+                p class="text-zinc-400 mb-4" {
+                    "The nutrients of a recipe are worked out from its "
+                    a href=(format!("/recipe/{}", food.id)) class="text-sky-400 hover:underline" { "ingredients" }
+                    "."
+                }
+            }
+            div class="grid grid-cols-2 gap-4" {
+                (food_fields(Some(food), None))
+                input
+                    type="submit"
+                    value="Save"
+                    class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400"
+                    {}
                 div class="grid grid-cols-2 gap-4" {
-                    label class="font-bold col-span-2" { "Food Name" }
-                    input id="name" class="bg-zinc-800 px-4 py-2 rounded-lg col-span-2" name="name" {}
-                    label for="calories" class="mb-2 font-bold" { "Calories per 100g" }
-                    label for="protein" class="mb-2 font-bold" { "Protein per 100g" }
-                    input id="calories" class="bg-zinc-800 px-4 py-2 rounded-lg" name="calories" {}
-                    input id="protein" class="bg-zinc-800 px-4 py-2 rounded-lg" name="protein" {}
-                    input
-                        type="submit"
-                        value="Create Food"
-                        class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400 col-span-2"
-                        {}
-                    p id="status" {}
+                    button
+                        type="button"
+                        class="px-4 py-2 bg-red-600 rounded-lg hover:bg-red-500"
+                        hx-post=(format!("/food/{}/delete", food.id))
+                        hx-target="#food-creator"
+                        hx-confirm=(format!("Delete {}?", food.name))
+                    {
+                        "Delete"
+                    }
+                    a href="/meal_builder" class="px-4 py-2 bg-zinc-800 rounded-lg hover:bg-zinc-600 text-center" { "Cancel" }
+                }
+                @if let Some(usage) = usage.as_ref().filter(|u| u.other_users > 0) {
+                    div class="col-span-2 text-red-400" {
+                        (food.name) " is used by " (usage.other_users)
+                        @if usage.other_users == 1 { " other user" } @else { " other users" }
+                        ", so it can't be deleted."
+                    }
+                } @else if let Some(usage) = usage {
+                    div class="col-span-2 text-red-400" {
+                        (food.name) " is part of " (usage_summary(&usage))
+                        ". Deleting it removes it from them. "
+                        button
+                            type="button"
+                            class="font-bold hover:underline"
+                            hx-post=(format!("/food/{}/delete", food.id))
+                            hx-vals="{\"confirm\": true}"
+                            hx-target="#food-creator"
+                        {
+                            "Delete anyway"
+                        }
+                    }
+                }
+                p id="status" {}
+            }
+        }
+        div class="h-8" {}
+        div id="portions" {
+            (portion_list(food, portions, None))
+        }
+    }
+}
+
+/// The meal being built. Its foods and amounts are posted back with every
+/// change, so it is only stored once saved. `status` tells whether saving
+/// worked.
+pub fn meal_panel(name: &str, items: &[MealItem], status: Option<Result<&str, &str>>) -> Markup {
+    let totals = meals::totals(items);
+    html! {
+        form id="meal" class="mb-8" hx-post="/meal/items" hx-trigger="input delay:300ms" hx-swap="outerHTML" {
+            h3 class="text-xl mb-4" { "Meal" }
+            @if items.is_empty() {
+                p class="text-zinc-400 mb-4" { "Click a food in the search results to add it." }
+            } @else {
+                table class="mb-4" {
+                    thead {
+                        tr {
+                            th class="text-left pr-8" { "Food" }
+                            th class="text-left pr-8" { "Amount" }
+                            th class="text-right pr-4" { "g" }
+                            th class="text-right pr-4" { "kcal" }
+                            th class="text-right pr-4" { "Protein" }
+                            th class="text-right pr-4" { "Fat" }
+                            th class="text-right pr-4" { "Carbs" }
+                            th {}
+                        }
+                    }
+                    tbody {
+                        @for (i, item) in items.iter().enumerate() {
+                            tr {
+                                td class="pr-8 py-1" {
+                                    input type="hidden" name="food" value=(item.food.id) {}
+                                    (item.food.name)
+                                }
+                                td class="pr-8 py-1" {
+                                    input
+                                        id=(format!("meal-amount-{}", i))
+                                        class="bg-zinc-800 px-2 py-1 rounded-lg w-28"
+                                        name="amount"
+                                        value=(item.amount)
+                                        {}
+                                }
+                                @match &item.grams {
+                                    Ok(grams) => {
+                                        @let food = &item.food;
+                                        td class="text-right pr-4 py-1" { (fixed::format(*grams, 0)) }
+                                        td class="text-right pr-4 py-1" { (fixed::format(units::scale(food.calories, *grams), 0)) }
+                                        td class="text-right pr-4 py-1" { (fixed::format(units::scale(food.protein, *grams), 1)) }
+                                        td class="text-right pr-4 py-1" { (optional_amount(food.fat.map(|v| units::scale(v, *grams)))) }
+                                        td class="text-right pr-4 py-1" { (optional_amount(food.carbohydrates.map(|v| units::scale(v, *grams)))) }
+                                    }
+                                    Err(e) => {
+                                        td class="text-red-400 py-1" colspan="5" { (e) }
+                                    }
+                                }
+                                td class="py-1" {
+                                    button
+                                        type="button"
+                                        class="text-red-400 hover:underline text-sm"
+                                        hx-post="/meal/items"
+                                        hx-vals=(format!("{{\"remove\": {}}}", i))
+                                        hx-target="#meal"
+                                    {
+                                        "Remove"
+                                    }
+                                }
+                            }
+                        }
+                        tr class="font-bold" {
+                            td class="pr-8 py-1" { "Total" }
+                            td {}
+                            td class="text-right pr-4 py-1" { (fixed::format(totals.grams, 0)) }
+                            td class="text-right pr-4 py-1" { (fixed::format(totals.calories, 0)) }
+                            td class="text-right pr-4 py-1" { (fixed::format(totals.protein, 1)) }
+                            td class="text-right pr-4 py-1" { (optional_amount(totals.fat)) }
+                            td class="text-right pr-4 py-1" { (optional_amount(totals.carbohydrates)) }
+                            td {}
+                        }
+                    }
+                }
+            }
+            div class="flex gap-4" {
+                input id="meal-name" class="bg-zinc-800 px-4 py-2 rounded-lg" name="name" value=(name) placeholder="Name of the meal" {}
+                button
+                    type="button"
+                    class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400"
+                    hx-post="/meal/save"
+                    hx-target="#meal"
+                {
+                    "Save meal"
+                }
+            }
+            @match status {
+                Some(Ok(message)) => p class="text-green-400 font-bold mt-2" { (message) },
+                Some(Err(message)) => p class="text-red-400 font-bold mt-2" { (message) },
+                None => {},
+            }
+        }
+    }
+}
+
+/// E.g. "2 meals, 1 recipe and 3 diary entries".
+fn usage_summary(usage: &Usage) -> String {
+    let count = |n: i64, one: &str, many: &str| {
+        (n > 0).then(|| format!("{} {}", n, if n == 1 { one } else { many }))
+    };
+    let mut parts: Vec<String> = [
+        count(usage.meals, "meal", "meals"),
+        count(usage.recipes, "recipe", "recipes"),
+        count(usage.diary_entries, "diary entry", "diary entries"),
+        count(usage.planned_entries, "planned item", "planned items"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    match parts.pop() {
+        Some(last) if !parts.is_empty() => format!("{} and {}", parts.join(", "), last),
+        last => last.unwrap_or_default(),
+    }
+}
+
+/// Portions of a food with forms to add and remove them, and a field to try
+/// converting amounts to grams.
+pub fn portion_list(food: &Food, portions: &[FoodPortion], error: Option<&str>) -> Markup {
+    html! {
+        h3 class="text-xl mb-4" { "Portions" }
+        @if portions.is_empty() {
+            p class="text-zinc-400 mb-4" { "No portions yet, add e.g. \"slice\" or \"egg\" with its weight." }
+        } @else {
+            table class="mb-4" {
+                @for portion in portions {
+                    tr {
+                        td class="pr-8 py-1" { "1 " (portion.name) }
+                        td class="pr-8 py-1 text-right" { (fixed::format_short(portion.grams)) " g" }
+                        td class="py-1" {
+                            button
+                                class="text-red-400 hover:underline text-sm"
+                                hx-post=(format!("/food/{}/portions/{}/delete", food.id, portion.id))
+                                hx-target="#portions"
+                            {
+                                "Remove"
+                            }
+                        }
+                    }
                 }
             }
         }
+        form class="flex gap-4" hx-post=(format!("/food/{}/portions", food.id)) hx-target="#portions" {
+            input class="bg-zinc-800 px-4 py-2 rounded-lg" name="name" placeholder="Name, e.g. slice" {}
+            input class="bg-zinc-800 px-4 py-2 rounded-lg w-32" name="grams" inputmode="decimal" placeholder="g" {}
+            input type="submit" value="Add portion" class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400" {}
+        }
+        @if let Some(error) = error {
+            p class="text-red-400 font-bold mt-2" { (error) }
+        }
+        div class="h-4" {}
+        label class="font-bold" {
+            "Try an amount "
+            input
+                class="bg-zinc-800 px-4 py-2 rounded-lg ml-2 font-normal"
+                name="amount"
+                placeholder="e.g. 2 dl or 1 slice"
+                hx-get=(format!("/food/{}/amount", food.id))
+                hx-trigger="input delay:300ms"
+                hx-target="#amount-result"
+            {}
+        }
+        span id="amount-result" class="ml-4" {}
     }
 }
 
-pub fn food_searcher() -> Markup {
+/// An amount of a food converted to grams, with the energy and protein in it.
+pub fn amount_result(food: &Food, grams: i32) -> Markup {
+    html! {
+        (fixed::format_short(grams)) " g, "
+        (fixed::format(units::scale(food.calories, grams), 0)) " kcal, "
+        (fixed::format(units::scale(food.protein, grams), 1)) " g protein"
+    }
+}
+
+/// Replaces the editor of a deleted food with an empty food creator.
+pub fn food_deleted(name: &str) -> Markup {
+    html! {
+        p class="text-green-400 font-bold mb-4" { "Deleted " (name) }
+        (food_form(None))
+    }
+}
+
+pub fn food_searcher(groups: &[FoodGroup], search: &SearchData, table: Markup) -> Markup {
+    let selected_group = search.food_group.as_deref().unwrap_or("");
     html! {
         h3 class="food_title text-xl" { "Search Foods" span class="food_indicator ml-4" { "Searching..." } }
         div class="h-4" {}
-        input
-            class="food-search bg-zinc-800 px-4 py-2 rounded-lg"
-            type="search"
-            name="search-name"
-            placeholder="Begin typing to search foods"
+        form
+            id="food-search-form"
             hx-post="/search_food"
-            hx-trigger="input changed delay:200ms, search-name"
-            hx-target="#food-results"
+            hx-trigger="input delay:200ms, submit, foods-changed from:body"
+            hx-target="#food-table"
             hx-indicator=".food_indicator"
+        {
+            input
+                class="food-search bg-zinc-800 px-4 py-2 rounded-lg w-96"
+                type="search"
+                name="search-name"
+                value=(search.search_name)
+                placeholder="Search foods, e.g. kyckling protein>20 kcal<150 group:kött"
+            {}
+            select class="bg-zinc-800 px-4 py-2 rounded-lg ml-4" name="food-group" {
+                option value="" { "All groups" }
+                @for group in groups {
+                    option value=(group.id) selected[group.id.to_string() == selected_group] { (group.name) }
+                }
+            }
+            label class="ml-4" {
+                input type="checkbox" name="trusted-only" checked[search.trusted_only.is_some()] {}
+                " Trusted sources only"
+            }
+            input type="hidden" name="sort" value=(search.sort.as_deref().unwrap_or("")) {}
+            input type="hidden" name="dir" value=(search.dir.as_deref().unwrap_or("")) {}
+        }
+        div class="h-8" {}
+        div id="food-table" {
+            (table)
+        }
+    }
+}
+
+/// The search results table, with headers that sort by their column.
+pub fn food_table(sort: SortBy, descending: bool, rows: Markup) -> Markup {
+    html! {
+        table class="text-white w-full" {
+            (food_table_head(Some((sort, descending))))
+            tbody id="food-results" {
+                (rows)
+            }
+        }
+    }
+}
+
+/// Header that sorts the search by `column` when clicked, or flips the
+/// direction if the results are already sorted by it.
+fn sort_header(label: &str, column: SortBy, sorting: Option<(SortBy, bool)>) -> Markup {
+    let Some((sort, descending)) = sorting else {
+        return html! { (label) };
+    };
+    let next_descending = if sort == column {
+        !descending
+    } else {
+        column.descending_by_default()
+    };
+    html! {
+        button
+            class="font-bold hover:underline"
+            data-sort=(column.param())
+            data-dir=(if next_descending { "desc" } else { "asc" })
+            hx-on="click: const form = document.querySelector('#food-search-form'); form.elements['sort'].value = this.dataset.sort; form.elements['dir'].value = this.dataset.dir; htmx.trigger(form, 'submit')"
+        {
+            (label)
+            @if sort == column {
+                (if descending { " ▼" } else { " ▲" })
+            }
+        }
+    }
+}
+
+fn food_table_head(sorting: Option<(SortBy, bool)>) -> Markup {
+    html! {
+        thead {
+            tr {
+                th class="text-left pr-8" { (sort_header("Name", SortBy::Name, sorting)) }
+                th class="text-right" { (sort_header("Calories (kcal/100g)", SortBy::Calories, sorting)) }
+                th class="text-right pl-4" { (sort_header("Protein (g/100g)", SortBy::Protein, sorting)) }
+                th class="text-right pl-4" { (sort_header("Protein/100 kcal", SortBy::ProteinPerKcal, sorting)) }
+                th class="text-right pl-4" { "Fat" }
+                th class="text-right pl-4" { "Carbs" }
+                th class="text-right pl-4" { "Fiber" }
+                th class="text-left pl-4" { "Group" }
+                th class="text-left pl-4" { "Source" }
+                th {}
+            }
+        }
+    }
+}
+
+/// Amount in grams with one decimal, or a dash when the source doesn't have it.
+fn optional_amount(value: Option<i32>) -> String {
+    value.map_or("–".to_owned(), |v| fixed::format(v, 1))
+}
+
+/// Grams of protein per 100 kcal with one decimal, a dash for foods without
+/// energy.
+fn protein_per_100_kcal(food: &Food) -> String {
+    if food.calories > 0 {
+        let ratio = i64::from(food.protein) * 10_000 / i64::from(food.calories);
+        fixed::format(fixed::saturate(ratio), 1)
+    } else {
+        "–".to_owned()
+    }
+}
+
+/// A food in a results table, with an edit button if the user may edit it.
+pub fn food_row(
+    food: &Food,
+    group: Option<&FoodGroup>,
+    source: Option<&DataSource>,
+    editable: bool,
+) -> Markup {
+    html! {
+        tr
+            class="cursor-pointer hover:bg-zinc-600"
+            title="Add to the meal"
+            hx-post="/meal/items"
+            hx-vals=(format!("{{\"add\": {}}}", food.id))
+            hx-include="#meal"
+            hx-target="#meal"
+            hx-swap="outerHTML"
+            hx-disinherit="*"
+        {
+            td class="py-2" {
+                (food.name)
+                @if let Some(visibility) = Visibility::from_value(&food.visibility).filter(|v| *v != Visibility::Public) {
+                    span class="ml-2 text-xs text-zinc-400" { (visibility.label()) }
+                }
+            }
+            td class="text-right py-2" {(fixed::format(food.calories, 0))}
+            td class="text-right py-2" {(fixed::format(food.protein, 1))}
+            td class="text-right py-2" {(protein_per_100_kcal(food))}
+            td class="text-right py-2" {(optional_amount(food.fat))}
+            td class="text-right py-2" {(optional_amount(food.carbohydrates))}
+            td class="text-right py-2" {(optional_amount(food.fiber))}
+            td class="pl-4 py-2 text-sm" { (group.map(|g| g.name.as_str()).unwrap_or("")) }
+            td class="pl-4 py-2 text-sm text-zinc-400" title=(food.source.as_deref().unwrap_or("")) {
+                @match source {
+                    Some(source) => {
+                        (source.name)
+                        @if source.trusted { " ✓" }
+                    },
+                    None => "Unknown",
+                }
+            }
+            td class="pl-4 py-2 text-right" {
+                @if editable {
+                    button
+                        class="text-sky-400 hover:underline text-sm"
+                        hx-get=(format!("/food/{}/edit", food.id))
+                        hx-target="#food-creator"
+                        hx-on="click: event.stopPropagation()"
+                    {
+                        "Edit"
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn search_error_row(err: &str) -> Markup {
+    html! {
+        tr {
+            td colspan="10" class="py-2 text-red-400 font-bold" { (err) }
+        }
+    }
+}
+
+/// "Did you mean" prompt above fuzzy search results, clicking the suggestion
+/// searches for it.
+pub fn suggestion_row(suggestion: &str) -> Markup {
+    html! {
+        tr {
+            td colspan="10" class="py-2 text-zinc-400" {
+                "Did you mean "
+                button
+                    class="text-sky-400 hover:underline"
+                    data-suggestion=(suggestion)
+                    hx-on="click: document.querySelector('.food-search').value = this.dataset.suggestion; htmx.trigger('#food-search-form', 'submit')"
+                {
+                    (suggestion)
+                }
+                "?"
+            }
+        }
+    }
+}
+
+/// Replaces itself with the next page of search results once scrolled into
+/// view.
+pub fn load_more_row(page: i64) -> Markup {
+    html! {
+        tr
+            hx-post="/search_food"
+            hx-include="#food-search-form"
+            hx-vals=(format!("{{\"page\": {}}}", page))
+            hx-trigger="revealed"
+            hx-swap="outerHTML"
+        {
+            td colspan="10" class="py-2 text-center text-zinc-400" {
+                "Loading more..."
+            }
+        }
+    }
+}
+
+pub fn barcode_lookup() -> Markup {
+    html! {
+        h3 class="text-xl" { "Scan Barcode" }
+        div class="h-4" {}
+        input
+            class="bg-zinc-800 px-4 py-2 rounded-lg"
+            type="search"
+            name="code"
+            placeholder="Scan or type an EAN/UPC code"
+            hx-get="/food/barcode"
+            hx-trigger="keyup[key=='Enter']"
+            hx-on="htmx:configRequest: event.detail.path = '/food/barcode/' + encodeURIComponent(this.value); event.detail.parameters = {}"
+            hx-target="#barcode-result"
         {}
+        div class="h-4" {}
+        div id="barcode-result" {}
         div class="h-8" {}
+    }
+}
+
+/// Shown when a barcode lookup has no match, swaps in a food creator with the
+/// barcode already filled in.
+pub fn barcode_not_found(barcode: &str) -> Markup {
+    html! {
+        p class="text-zinc-400" { "No food with barcode " (barcode) ", fill in the form above to create it." }
+        div id="food-creator" hx-swap-oob="true" {
+            (food_form(Some(barcode)))
+        }
+    }
+}
+
+pub fn barcode_match(
+    food: &Food,
+    group: Option<&FoodGroup>,
+    source: Option<&DataSource>,
+    editable: bool,
+) -> Markup {
+    html! {
         table class="text-white w-full" {
+            (food_table_head(None))
+            tbody {
+                (food_row(food, group, source, editable))
+            }
+        }
+    }
+}
+
+pub fn duplicates_page(user: &User, candidates: &[Candidate]) -> Markup {
+    html! {
+        (toolbar(user))
+        h3 class="text-xl" { "Possible duplicate foods" }
+        p class="text-zinc-400 my-4" {
+            "Public foods with similar names and nutrients. Merging keeps one of them, "
+            "meals and recipes using the other one get the kept food instead."
+        }
+        @if candidates.is_empty() {
+            p { "No duplicates found." }
+        }
+        @for candidate in candidates {
+            (duplicate_pair(candidate))
+        }
+    }
+}
+
+fn duplicate_pair(candidate: &Candidate) -> Markup {
+    html! {
+        div class="duplicate bg-zinc-800 rounded-lg p-4 mb-4" {
+            p class="text-sm text-zinc-400 mb-2" {
+                "Names " (format!("{:.0}", candidate.name_similarity * 100.0)) "% alike, nutrients "
+                (format!("{:.0}", candidate.nutrient_distance * 100.0)) "% apart"
+            }
+            div class="grid grid-cols-2 gap-4" {
+                (duplicate_food(&candidate.first, &candidate.second))
+                (duplicate_food(&candidate.second, &candidate.first))
+            }
+        }
+    }
+}
+
+fn duplicate_food(food: &Food, other: &Food) -> Markup {
+    html! {
+        div {
+            p class="font-bold" { (food.name) }
+            p class="text-sm text-zinc-400" { (food.source.as_deref().unwrap_or("No source")) }
+            p class="text-sm" {
+                (fixed::format(food.calories, 0)) " kcal, "
+                (fixed::format(food.protein, 1)) " g protein, "
+                (optional_amount(food.fat)) " g fat, "
+                (optional_amount(food.carbohydrates)) " g carbs, "
+                (optional_amount(food.fiber)) " g fiber"
+            }
+            button
+                class="mt-2 px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400"
+                hx-post="/admin/merge"
+                hx-vals=(format!("{{\"survivor\": {}, \"duplicate\": {}}}", food.id, other.id))
+                hx-confirm=(format!("Merge {} into {}?", other.name, food.name))
+                hx-target="closest .duplicate"
+                hx-swap="outerHTML"
+            {
+                "Keep this one"
+            }
+        }
+    }
+}
+
+pub fn merge_result(message: &str, ok: bool) -> Markup {
+    html! {
+        p class=(if ok { "duplicate text-green-400 mb-4" } else { "duplicate text-red-400 mb-4" }) { (message) }
+    }
+}
+
+pub fn recipes_page(user: &User, recipes: &[Food]) -> Markup {
+    html! {
+        (toolbar(user))
+        h3 class="text-xl mb-4" { "Recipes" }
+        @if recipes.is_empty() {
+            p class="text-zinc-400 mb-4" { "No recipes yet." }
+        } @else {
+            table class="mb-8" {
+                thead {
+                    tr {
+                        th class="text-left pr-8" { "Name" }
+                        th class="text-right pr-8" { "kcal/100g" }
+                        th class="text-right" { "Protein/100g" }
+                    }
+                }
+                tbody {
+                    @for recipe in recipes {
+                        tr {
+                            td class="pr-8 py-1" {
+                                a href=(format!("/recipe/{}", recipe.id)) class="text-sky-400 hover:underline" { (recipe.name) }
+                            }
+                            td class="pr-8 py-1 text-right" { (fixed::format(recipe.calories, 0)) }
+                            td class="py-1 text-right" { (fixed::format(recipe.protein, 1)) }
+                        }
+                    }
+                }
+            }
+        }
+        form class="flex gap-4" hx-post="/recipes" hx-target="#recipe-status" {
+            input class="bg-zinc-800 px-4 py-2 rounded-lg" name="name" placeholder="Name of the recipe" {}
+            select class="bg-zinc-800 px-4 py-2 rounded-lg" name="visibility" {
+                @for visibility in Visibility::ALL {
+                    option value=(visibility.as_str()) { (visibility.label()) }
+                }
+            }
+            input type="submit" value="New recipe" class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400" {}
+        }
+        p id="recipe-status" class="mt-2" {}
+    }
+}
+
+pub fn recipe_page(
+    user: &User,
+    food: &Food,
+    recipe: &Recipe,
+    ingredients: &[(RecipeIngredient, Food)],
+) -> Markup {
+    html! {
+        (toolbar(user))
+        h3 class="text-xl mb-4" { (food.name) }
+        div id="recipe" {
+            (recipe_details(food, recipe, ingredients, None))
+        }
+        div class="h-8" {}
+        h3 class="text-xl mb-4" { "Add an ingredient" }
+        div class="flex gap-4" {
+            input
+                id="ingredient-amount"
+                class="bg-zinc-800 px-4 py-2 rounded-lg w-40"
+                name="amount"
+                placeholder="Amount, e.g. 2 dl"
+                {}
+            input
+                class="bg-zinc-800 px-4 py-2 rounded-lg grow"
+                name="q"
+                placeholder="Search for a food"
+                hx-get=(format!("/recipe/{}/ingredient-search", food.id))
+                hx-trigger="input delay:300ms"
+                hx-target="#ingredient-matches"
+                {}
+        }
+        div id="ingredient-matches" class="mt-2" {}
+    }
+}
+
+/// Ingredients, cooked weight and resulting nutrients of a recipe.
+pub fn recipe_details(
+    food: &Food,
+    recipe: &Recipe,
+    ingredients: &[(RecipeIngredient, Food)],
+    error: Option<&str>,
+) -> Markup {
+    let totals = recipes::totals(ingredients);
+    let weight = recipe.cooked_weight.unwrap_or(totals.grams);
+    let per_100g = |total: i32| Totals::per_100g(total, weight);
+    html! {
+        @if ingredients.is_empty() {
+            p class="text-zinc-400 mb-4" { "No ingredients yet." }
+        } @else {
+            table class="mb-4" {
+                thead {
+                    tr {
+                        th class="text-left pr-8" { "Ingredient" }
+                        th class="text-left pr-8" { "Amount" }
+                        th class="text-right pr-8" { "g" }
+                        th class="text-right pr-8" { "kcal" }
+                        th class="text-right pr-8" { "Protein" }
+                        th {}
+                    }
+                }
+                tbody {
+                    @for (ingredient, ingredient_food) in ingredients {
+                        tr {
+                            td class="pr-8 py-1" { (ingredient_food.name) }
+                            td class="pr-8 py-1" { (ingredient.amount) }
+                            td class="pr-8 py-1 text-right" { (fixed::format(ingredient.grams, 0)) }
+                            td class="pr-8 py-1 text-right" { (fixed::format(units::scale(ingredient_food.calories, ingredient.grams), 0)) }
+                            td class="pr-8 py-1 text-right" { (fixed::format(units::scale(ingredient_food.protein, ingredient.grams), 1)) }
+                            td class="py-1" {
+                                button
+                                    class="text-red-400 hover:underline text-sm"
+                                    hx-post=(format!("/recipe/{}/ingredients/{}/delete", food.id, ingredient.id))
+                                    hx-target="#recipe"
+                                {
+                                    "Remove"
+                                }
+                            }
+                        }
+                    }
+                    tr class="font-bold" {
+                        td class="pr-8 py-1" { "Total" }
+                        td {}
+                        td class="pr-8 py-1 text-right" { (fixed::format(totals.grams, 0)) }
+                        td class="pr-8 py-1 text-right" { (fixed::format(totals.calories, 0)) }
+                        td class="pr-8 py-1 text-right" { (fixed::format(totals.protein, 1)) }
+                        td {}
+                    }
+                }
+            }
+        }
+        @if let Some(error) = error {
+            p class="text-red-400 font-bold mb-4" { (error) }
+        }
+        form class="flex gap-4 items-center mb-4" hx-post=(format!("/recipe/{}/cooked-weight", food.id)) hx-target="#recipe" {
+            label for="cooked-weight" class="font-bold" { "Weight when cooked" }
+            input
+                id="cooked-weight"
+                class="bg-zinc-800 px-4 py-2 rounded-lg w-32"
+                name="cooked_weight"
+                inputmode="decimal"
+                placeholder=(fixed::format(totals.grams, 0))
+                title="Leave blank if it weighs as much as the raw ingredients"
+                value=(recipe.cooked_weight.map(fixed::format_short).unwrap_or_default())
+                {}
+            " g"
+            input type="submit" value="Save" class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400" {}
+        }
+        p {
+            "Per 100 g: "
+            (fixed::format(per_100g(totals.calories), 0)) " kcal, "
+            (fixed::format(per_100g(totals.protein), 1)) " g protein, "
+            (optional_amount(totals.fat.map(per_100g))) " g fat, "
+            (optional_amount(totals.carbohydrates.map(per_100g))) " g carbs, "
+            (optional_amount(totals.fiber.map(per_100g))) " g fiber"
+        }
+    }
+}
+
+/// Foods matching the ingredient search, each added with the amount typed
+/// next to the search box.
+pub fn ingredient_matches(recipe_id: i32, foods: &[Food]) -> Markup {
+    html! {
+        @if foods.is_empty() {
+            p class="text-zinc-400" { "No matching foods" }
+        }
+        @for food in foods {
+            button
+                class="block text-left px-4 py-1 rounded-lg hover:bg-zinc-600 w-full"
+                hx-post=(format!("/recipe/{}/ingredients", recipe_id))
+                hx-vals=(format!("{{\"food_id\": {}}}", food.id))
+                hx-include="#ingredient-amount"
+                hx-target="#recipe"
+            {
+                (food.name) span class="text-zinc-400 ml-4" { (fixed::format(food.calories, 0)) " kcal/100g" }
+            }
+        }
+    }
+}
+
+/// One line of nutrients, e.g. in the meal library.
+fn totals_summary(totals: &Totals) -> Markup {
+    html! {
+        (fixed::format(totals.grams, 0)) " g, "
+        (fixed::format(totals.calories, 0)) " kcal, "
+        (fixed::format(totals.protein, 1)) " g protein, "
+        (optional_amount(totals.fat)) " g fat, "
+        (optional_amount(totals.carbohydrates)) " g carbs"
+    }
+}
+
+pub fn meals_page(user: &User, meals: &[(Meal, Totals)]) -> Markup {
+    html! {
+        (toolbar(user))
+        h3 class="text-xl mb-4" { "My meals" }
+        @if meals.is_empty() {
+            p class="text-zinc-400" {
+                "No meals yet, build one on the "
+                a href="/meal_builder" class="text-sky-400 hover:underline" { "foods page" }
+                "."
+            }
+        }
+        @for (meal, totals) in meals {
+            div class="meal flex items-center gap-4 bg-zinc-800 rounded-lg p-4 mb-4" {
+                div class="grow" {
+                    a href=(format!("/meal/{}", meal.id)) class="font-bold text-sky-400 hover:underline" { (meal.name) }
+                    p class="text-sm text-zinc-400" { (totals_summary(totals)) }
+                }
+                (meal_actions(meal))
+            }
+        }
+    }
+}
+
+fn meal_actions(meal: &Meal) -> Markup {
+    html! {
+        button
+            class="px-4 py-2 bg-zinc-700 rounded-lg hover:bg-zinc-600"
+            hx-post=(format!("/meal/{}/duplicate", meal.id))
+        {
+            "Duplicate"
+        }
+        button
+            class="px-4 py-2 bg-red-600 rounded-lg hover:bg-red-500"
+            hx-post=(format!("/meal/{}/delete", meal.id))
+            hx-confirm=(format!("Delete {}?", meal.name))
+        {
+            "Delete"
+        }
+    }
+}
+
+pub fn meal_page(user: &User, meal: &Meal, contents: &[(MealFood, Food)]) -> Markup {
+    let totals = contents
+        .iter()
+        .fold(Totals::default(), |totals, (item, food)| {
+            totals.add(food, item.grams)
+        });
+    html! {
+        (toolbar(user))
+        div id="meal-header" {
+            (meal_header(meal, None))
+        }
+        table class="my-4" {
             thead {
                 tr {
-                    th class="text-left pr-8" { "Name" }
-                    th class="text-right" { "Calories (g/100g)" }
-                    th class="text-right pl-4" { "Protein (g/100g)" }
+                    th class="text-left pr-8" { "Food" }
+                    th class="text-left pr-8" { "Amount" }
+                    th class="text-right pr-4" { "g" }
+                    th class="text-right pr-4" { "kcal" }
+                    th class="text-right pr-4" { "Protein" }
+                    th class="text-right pr-4" { "Fat" }
+                    th class="text-right pr-4" { "Carbs" }
                 }
             }
-            tbody id="food-results" {
+            tbody {
+                @for (item, food) in contents {
+                    tr {
+                        td class="pr-8 py-1" { (food.name) }
+                        td class="pr-8 py-1" { (item.amount) }
+                        td class="text-right pr-4 py-1" { (fixed::format(item.grams, 0)) }
+                        td class="text-right pr-4 py-1" { (fixed::format(units::scale(food.calories, item.grams), 0)) }
+                        td class="text-right pr-4 py-1" { (fixed::format(units::scale(food.protein, item.grams), 1)) }
+                        td class="text-right pr-4 py-1" { (optional_amount(food.fat.map(|v| units::scale(v, item.grams)))) }
+                        td class="text-right pr-4 py-1" { (optional_amount(food.carbohydrates.map(|v| units::scale(v, item.grams)))) }
+                    }
+                }
+                tr class="font-bold" {
+                    td class="pr-8 py-1" { "Total" }
+                    td {}
+                    td class="text-right pr-4 py-1" { (fixed::format(totals.grams, 0)) }
+                    td class="text-right pr-4 py-1" { (fixed::format(totals.calories, 0)) }
+                    td class="text-right pr-4 py-1" { (fixed::format(totals.protein, 1)) }
+                    td class="text-right pr-4 py-1" { (optional_amount(totals.fat)) }
+                    td class="text-right pr-4 py-1" { (optional_amount(totals.carbohydrates)) }
+                }
+            }
+        }
+        div class="flex gap-4" {
+            (meal_actions(meal))
+        }
+    }
+}
+
+/// Name of a meal with a form to rename it. `error` is set when renaming
+/// failed.
+pub fn meal_header(meal: &Meal, error: Option<&str>) -> Markup {
+    html! {
+        h3 class="text-xl mb-4" { (meal.name) }
+        form class="flex gap-4" hx-post=(format!("/meal/{}/rename", meal.id)) hx-target="#meal-header" {
+            input class="bg-zinc-800 px-4 py-2 rounded-lg" name="name" value=(meal.name) {}
+            input type="submit" value="Rename" class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400" {}
+        }
+        @if let Some(error) = error {
+            p class="text-red-400 font-bold mt-2" { (error) }
+        }
+    }
+}
+
+/// E.g. "Monday 15 January 2024".
+fn long_date(date: Date) -> String {
+    date.format(format_description!(
+        "[weekday] [day padding:none] [month repr:long] [year]"
+    ))
+    .unwrap_or_else(|_| date.to_string())
+}
+
+pub fn diary_page(user: &User, view: &DiaryView) -> Markup {
+    let date = view.day.date;
+    let day_link = |date: Option<Date>, label: &str| {
+        html! {
+            @if let Some(date) = date {
+                a href=(format!("/diary/{}", date)) class="text-sky-400 hover:underline" { (label) }
+            }
+        }
+    };
+    html! {
+        (toolbar(user))
+        div class="flex items-center gap-8 mb-4" {
+            h3 class="text-xl grow" { (long_date(date)) }
+            (day_link(date.previous_day(), "Previous day"))
+            (day_link(Some(diary::today()), "Today"))
+            (day_link(date.next_day(), "Next day"))
+        }
+        div id="diary-day" {
+            (diary_day(view, None))
+        }
+        div class="h-8" {}
+        h3 class="text-xl mb-4" { "Log food" }
+        div class="flex gap-4" {
+            select id="diary-slot" class="bg-zinc-800 px-4 py-2 rounded-lg" name="slot" {
+                @for slot in Slot::ALL {
+                    option value=(slot.as_str()) { (slot.label()) }
+                }
+            }
+            input
+                id="diary-amount"
+                class="bg-zinc-800 px-4 py-2 rounded-lg w-48"
+                name="amount"
+                placeholder="Amount or servings"
+                title="E.g. 150 g or 2 dl for a food, 0.5 for half a meal"
+                {}
+            input
+                class="bg-zinc-800 px-4 py-2 rounded-lg grow"
+                name="q"
+                placeholder="Search for a food or meal"
+                hx-get=(format!("/diary/{}/search", date))
+                hx-trigger="input delay:300ms"
+                hx-target="#diary-matches"
+                {}
+        }
+        div id="diary-matches" class="mt-2" {}
+    }
+}
+
+/// What was logged on a day by part of the day, with totals, energy by food
+/// group and earlier items to log again. `error` is set when a change failed.
+pub fn diary_day(view: &DiaryView, error: Option<&str>) -> Markup {
+    let DiaryView {
+        day,
+        target,
+        week,
+        recent,
+    } = view;
+    let date = day.date;
+    html! {
+        @if let Some(error) = error {
+            p class="text-red-400 font-bold mb-4" { (error) }
+        }
+        @if let Some(target) = target {
+            (target_progress(&day.totals, target))
+        }
+        @for slot in Slot::ALL {
+            div class="diary-slot bg-zinc-800 rounded-lg p-4 mb-4" {
+                div class="flex items-center gap-4 mb-2" {
+                    h4 class="font-bold grow" {
+                        (slot.label())
+                        span class="text-zinc-400 font-normal ml-4" {
+                            (fixed::format(day.slot_totals(slot).calories, 0)) " kcal"
+                        }
+                    }
+                    button
+                        class="text-sky-400 hover:underline text-sm"
+                        hx-post=(format!("/diary/{}/copy", date))
+                        hx-vals=(format!("{{\"slot\": \"{}\"}}", slot.as_str()))
+                        hx-target="#diary-day"
+                    {
+                        "Copy from the day before"
+                    }
+                }
+                @if day.in_slot(slot).next().is_some() {
+                    table {
+                        tbody {
+                            @for logged in day.in_slot(slot) {
+                                (diary_row(date, logged))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        p class="font-bold mb-4" { "Total: " (totals_summary(&day.totals)) }
+        @if !day.energy_by_group.is_empty() {
+            h4 class="font-bold mb-2" { "Energy by food group" }
+            table class="mb-4" {
+                tbody {
+                    @for (group, calories) in &day.energy_by_group {
+                        tr {
+                            td class="pr-8 py-1" { (group) }
+                            td class="pr-8 py-1 text-right" { (fixed::format(*calories, 0)) " kcal" }
+                            td class="py-1 text-right text-zinc-400" {
+                                ((i64::from(*calories) * 100 / i64::from(day.totals.calories.max(1)))) " %"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        @if week.iter().any(|day| day.target.is_some()) {
+            (week_summary(week))
+        }
+        @if !recent.is_empty() {
+            h4 class="font-bold mb-2" { "Log again" }
+            div class="flex flex-wrap gap-2" {
+                @for logged in recent {
+                    button
+                        class="px-4 py-1 bg-zinc-800 rounded-lg hover:bg-zinc-600 text-sm"
+                        hx-post=(format!("/diary/{}/entries/{}/relog", date, logged.entry.id))
+                        hx-target="#diary-day"
+                    {
+                        (logged.name) " "
+                        span class="text-zinc-400" {
+                            (entry_amount(logged))
+                            @if let Some(slot) = logged.slot() {
+                                ", " (slot.label().to_lowercase())
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The amount of a food, or the servings of a meal, of a diary or planned
+/// entry.
+fn amount_label(meal_id: Option<i32>, quantity: i32, amount: &str) -> String {
+    match meal_id {
+        Some(_) if quantity == fixed::SCALE => "1 serving".to_owned(),
+        Some(_) => format!("{} servings", amount),
+        None => amount.to_owned(),
+    }
+}
+
+fn entry_amount(logged: &Logged) -> String {
+    let entry = &logged.entry;
+    amount_label(entry.meal_id, entry.quantity, &entry.amount)
+}
+
+fn diary_row(date: Date, logged: &Logged) -> Markup {
+    let totals = logged.totals();
+    html! {
+        tr {
+            td class="pr-8 py-1" {
+                (logged.name)
+                @if logged.entry.meal_id.is_some() {
+                    span class="text-zinc-400 text-sm ml-2" { "meal" }
+                }
+            }
+            td class="pr-8 py-1" { (entry_amount(logged)) }
+            td class="text-right pr-4 py-1" { (fixed::format(totals.grams, 0)) " g" }
+            td class="text-right pr-4 py-1" { (fixed::format(totals.calories, 0)) " kcal" }
+            td class="text-right pr-4 py-1" { (fixed::format(totals.protein, 1)) " g protein" }
+            td class="py-1" {
+                button
+                    class="text-red-400 hover:underline text-sm"
+                    hx-post=(format!("/diary/{}/entries/{}/delete", date, logged.entry.id))
+                    hx-target="#diary-day"
+                {
+                    "Remove"
+                }
+            }
+        }
+    }
+}
+
+/// Meals and foods matching the diary search, each logged with the part of
+/// the day and amount chosen next to the search box.
+pub fn diary_matches(date: Date, meals: &[(Meal, Totals)], foods: &[Food]) -> Markup {
+    let log_button = |vals: String, name: &str, detail: String| {
+        html! {
+            button
+                class="block text-left px-4 py-1 rounded-lg hover:bg-zinc-600 w-full"
+                hx-post=(format!("/diary/{}/entries", date))
+                hx-vals=(vals)
+                hx-include="#diary-slot, #diary-amount"
+                hx-target="#diary-day"
+            {
+                (name) span class="text-zinc-400 ml-4" { (detail) }
+            }
+        }
+    };
+    html! {
+        @if meals.is_empty() && foods.is_empty() {
+            p class="text-zinc-400" { "No matching foods or meals" }
+        }
+        @for (meal, totals) in meals {
+            (log_button(
+                format!("{{\"meal_id\": {}}}", meal.id),
+                &meal.name,
+                format!("meal, {} kcal", fixed::format(totals.calories, 0)),
+            ))
+        }
+        @for food in foods {
+            (log_button(
+                format!("{{\"food_id\": {}}}", food.id),
+                &food.name,
+                format!("{} kcal/100g", fixed::format(food.calories, 0)),
+            ))
+        }
+    }
+}
+
+pub fn week_page(user: &User, view: &WeekView) -> Markup {
+    let monday = view.monday;
+    let week_link = |days: i64, label: &str| {
+        html! {
+            a href=(format!("/week/{}", monday + Duration::days(days))) class="text-sky-400 hover:underline" { (label) }
+        }
+    };
+    html! {
+        (toolbar(user))
+        div class="flex items-center gap-8 mb-4" {
+            h3 class="text-xl grow" { "Week of " (long_date(monday)) }
+            (week_link(-7, "Previous week"))
+            a href="/week" class="text-sky-400 hover:underline" { "This week" }
+            (week_link(7, "Next week"))
+        }
+        div id="week" {
+            (week_grid(view, None))
+        }
+        div class="h-8" {}
+        h3 class="text-xl mb-4" { "Plan a meal" }
+        @if view.meals.is_empty() && view.recipes.is_empty() {
+            p class="text-zinc-400" { "Save a meal or create a recipe to plan it." }
+        } @else {
+            p class="text-zinc-400 mb-4" { "Drag a meal or recipe onto a day, or choose where it goes below." }
+            div class="flex flex-wrap gap-2 mb-4" {
+                @for (meal, totals) in &view.meals {
+                    (plan_chip(&format!("meal-{}", meal.id), &meal.name, totals.calories, "meal"))
+                }
+                @for recipe in &view.recipes {
+                    (plan_chip(&format!("food-{}", recipe.id), &recipe.name, recipe.calories, "recipe, per 100 g"))
+                }
+            }
+            form class="flex gap-4" hx-post=(format!("/week/{}/entries", monday)) hx-target="#week" {
+                select class="bg-zinc-800 px-4 py-2 rounded-lg" name="day" {
+                    @for day in &view.days {
+                        option value=(day.date) { (day.date.weekday()) }
+                    }
+                }
+                select class="bg-zinc-800 px-4 py-2 rounded-lg" name="slot" {
+                    @for slot in Slot::ALL {
+                        option value=(slot.as_str()) { (slot.label()) }
+                    }
+                }
+                select class="bg-zinc-800 px-4 py-2 rounded-lg grow" name="item" {
+                    @if !view.meals.is_empty() {
+                        optgroup label="Meals" {
+                            @for (meal, _) in &view.meals {
+                                option value=(format!("meal-{}", meal.id)) { (meal.name) }
+                            }
+                        }
+                    }
+                    @if !view.recipes.is_empty() {
+                        optgroup label="Recipes" {
+                            @for recipe in &view.recipes {
+                                option value=(format!("food-{}", recipe.id)) { (recipe.name) }
+                            }
+                        }
+                    }
+                }
+                input
+                    class="bg-zinc-800 px-4 py-2 rounded-lg w-48"
+                    name="amount"
+                    placeholder="Amount or servings"
+                    title="E.g. 300 g for a recipe, 0.5 for half a meal"
+                    {}
+                input type="submit" value="Plan" class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400" {}
+            }
+        }
+    }
+}
+
+/// A meal or recipe that can be dragged onto the week, planned with its
+/// default amount.
+fn plan_chip(item: &str, name: &str, calories: i32, detail: &str) -> Markup {
+    html! {
+        div
+            class="px-4 py-1 bg-zinc-800 rounded-lg cursor-grab text-sm"
+            draggable="true"
+            data-item=(item)
+            hx-on="dragstart: event.dataTransfer.setData('text/plain', this.dataset.item)"
+        {
+            (name) " "
+            span class="text-zinc-400" { (fixed::format(calories, 0)) " kcal, " (detail) }
+        }
+    }
+}
+
+/// The plan of a week as a day per column and a part of the day per row, with
+/// totals of each day compared to its targets. `status` is the outcome of the
+/// last change.
+pub fn week_grid(view: &WeekView, status: Option<Result<&str, &str>>) -> Markup {
+    let entries_path = format!("/week/{}/entries", view.monday);
+    let drop = format!(
+        "dragover: event.preventDefault()\n\
+         drop: event.preventDefault(); htmx.ajax('POST', '{}', {{target: '#week', values: {{day: this.dataset.day, slot: this.dataset.slot, item: event.dataTransfer.getData('text/plain'), amount: ''}}}})",
+        entries_path
+    );
+    html! {
+        @match status {
+            Some(Ok(message)) => p class="text-green-400 font-bold mb-4" { (message) },
+            Some(Err(message)) => p class="text-red-400 font-bold mb-4" { (message) },
+            None => {},
+        }
+        table class="w-full table-fixed mb-4 text-sm" {
+            thead {
+                tr {
+                    th class="w-20" {}
+                    @for day in &view.days {
+                        th class="text-left px-1" {
+                            a href=(format!("/diary/{}", day.date)) class="hover:underline" {
+                                (day.date.weekday()) br;
+                                span class="text-zinc-400 font-normal" { (day.date.day()) " " (day.date.month()) }
+                            }
+                        }
+                    }
+                }
+            }
+            tbody {
+                @for slot in Slot::ALL {
+                    tr {
+                        td class="font-bold align-top py-1" { (slot.label()) }
+                        @for day in &view.days {
+                            td
+                                class="align-top p-1"
+                                data-day=(day.date)
+                                data-slot=(slot.as_str())
+                                hx-on=(drop)
+                            {
+                                div class="bg-zinc-800 rounded-lg p-2 min-h-16" {
+                                    @for planned in day.in_slot(slot) {
+                                        (planned_item(view.monday, planned))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                tr {
+                    td class="font-bold align-top py-1" { "Total" }
+                    @for day in &view.days {
+                        td class="align-top p-1" {
+                            @for nutrient in [Macro::Calories, Macro::Protein] {
+                                @let amount = nutrient.of_totals(&day.totals).unwrap_or_default();
+                                @let status = day
+                                    .target
+                                    .as_ref()
+                                    .and_then(|t| nutrient.of_target(t))
+                                    .filter(|_| !day.entries.is_empty())
+                                    .map(|target| Status::of(amount, target));
+                                div class=(status_color(status)) {
+                                    (fixed::format(amount, 0)) " " (nutrient.unit())
+                                    @if nutrient == Macro::Protein { " protein" }
+                                }
+                            }
+                        }
+                    }
+                }
+                tr {
+                    td {}
+                    @for day in &view.days {
+                        td class="p-1" {
+                            @if day.has_unlogged() {
+                                button
+                                    class="text-sky-400 hover:underline"
+                                    hx-post=(format!("/week/{}/log", view.monday))
+                                    hx-vals=(format!("{{\"day\": \"{}\"}}", day.date))
+                                    hx-target="#week"
+                                    hx-disabled-elt="this"
+                                {
+                                    "Log as eaten"
+                                }
+                            } @else if !day.entries.is_empty() {
+                                span class="text-green-400" { "Logged" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn planned_item(monday: Date, planned: &Planned) -> Markup {
+    let entry = &planned.entry;
+    html! {
+        div class="flex gap-1 mb-1" {
+            div class="grow" {
+                (planned.name)
+                div class="text-zinc-400" {
+                    (amount_label(entry.meal_id, entry.quantity, &entry.amount)) ", "
+                    (fixed::format(planned.totals().calories, 0)) " kcal"
+                    @if entry.logged_entry_id.is_some() {
+                        ", logged"
+                    }
+                }
+            }
+            button
+                class="text-red-400 hover:underline self-start"
+                title="Remove"
+                hx-post=(format!("/week/{}/entries/{}/delete", monday, entry.id))
+                hx-target="#week"
+            {
+                "×"
+            }
+        }
+    }
+}
+
+/// Text color of a status in the week summary.
+fn status_color(status: Option<Status>) -> &'static str {
+    match status {
+        Some(Status::Under) => "text-amber-400",
+        Some(Status::OnTarget) => "text-green-400",
+        Some(Status::Over) => "text-red-400",
+        None => "",
+    }
+}
+
+/// A bar per nutrient with a target showing how much of it was eaten.
+fn target_progress(totals: &Totals, target: &Target) -> Markup {
+    html! {
+        div class="bg-zinc-800 rounded-lg p-4 mb-4" {
+            @for nutrient in Macro::ALL {
+                @if let Some(goal) = nutrient.of_target(target) {
+                    @let eaten = nutrient.of_totals(totals);
+                    @let percent = eaten.map_or(0, |eaten| {
+                        (i64::from(eaten) * 100 / i64::from(goal.max(1))).min(100)
+                    });
+                    @let bar = match eaten.map(|eaten| Status::of(eaten, goal)) {
+                        Some(Status::Over) => "bg-red-500",
+                        Some(Status::OnTarget) => "bg-green-500",
+                        _ => "bg-sky-500",
+                    };
+                    div class="flex items-center gap-4 py-1" {
+                        span class="w-16" { (nutrient.label()) }
+                        div class="w-64 h-3 bg-zinc-700 rounded-full" {
+                            div class=(format!("h-3 rounded-full {}", bar)) style=(format!("width: {}%", percent)) {}
+                        }
+                        span class="text-sm" {
+                            @match eaten {
+                                Some(eaten) => {
+                                    (fixed::format(eaten, 0)) " of " (fixed::format(goal, 0)) " " (nutrient.unit()) ", "
+                                    @if eaten > goal {
+                                        (fixed::format(eaten - goal, 0)) " " (nutrient.unit()) " over"
+                                    } @else {
+                                        (fixed::format(goal - eaten, 0)) " " (nutrient.unit()) " left"
+                                    }
+                                }
+                                None => span class="text-zinc-400" {
+                                    "Unknown, some foods lack it. Target " (fixed::format(goal, 0)) " " (nutrient.unit())
+                                },
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Each day of a week compared to its targets, with the days over and under
+/// target counted per nutrient.
+fn week_summary(week: &[WeekDay]) -> Markup {
+    let nutrients: Vec<Macro> = Macro::ALL
+        .into_iter()
+        .filter(|m| {
+            week.iter()
+                .any(|day| day.target.as_ref().and_then(|t| m.of_target(t)).is_some())
+        })
+        .collect();
+    let count = |nutrient: Macro, status: Status| {
+        week.iter()
+            .filter(|day| day.status(nutrient) == Some(status))
+            .count()
+    };
+    html! {
+        h4 class="font-bold mb-2" { "This week" }
+        table class="mb-4 week-summary" {
+            thead {
+                tr {
+                    th {}
+                    @for nutrient in &nutrients {
+                        th class="text-right pl-8" { (nutrient.label()) }
+                    }
+                }
+            }
+            tbody {
+                @for day in week {
+                    tr {
+                        td class="pr-4 py-1" {
+                            a href=(format!("/diary/{}", day.date)) class="text-sky-400 hover:underline" {
+                                (day.date.weekday()) " " (day.date.day())
+                            }
+                        }
+                        @for nutrient in &nutrients {
+                            @let goal = day.target.as_ref().and_then(|t| nutrient.of_target(t));
+                            td class=(format!("text-right pl-8 py-1 {}", status_color(day.status(*nutrient)))) {
+                                @if let Some(totals) = &day.totals {
+                                    @match nutrient.of_totals(totals) {
+                                        Some(eaten) => (fixed::format(eaten, 0)),
+                                        None => "–",
+                                    }
+                                    @if let Some(goal) = goal {
+                                        span class="text-zinc-400" { " / " (fixed::format(goal, 0)) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                tr {
+                    td class="pr-4 py-1 text-zinc-400" { "Off target" }
+                    @for nutrient in &nutrients {
+                        td class="text-right pl-8 py-1 text-sm" {
+                            @let over = count(*nutrient, Status::Over);
+                            @let under = count(*nutrient, Status::Under);
+                            @if over > 0 {
+                                span class="text-red-400" { (over) " over" }
+                            }
+                            @if over > 0 && under > 0 { ", " }
+                            @if under > 0 {
+                                span class="text-amber-400" { (under) " under" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        p class="text-sm text-zinc-400 mb-4" {
+            "Within " (targets::TOLERANCE) " % of a target counts as on target."
+        }
+    }
+}
+
+/// A row of target inputs for one weekday, or every day.
+fn target_inputs(
+    label: &str,
+    weekday: i32,
+    target: Option<&Target>,
+    every_day: Option<&Target>,
+) -> Markup {
+    html! {
+        tr {
+            td class="pr-4 py-1" { (label) }
+            @for nutrient in Macro::ALL {
+                td class="pr-2 py-1" {
+                    input
+                        class="bg-zinc-800 px-2 py-1 rounded-lg w-24"
+                        name=(format!("{}-{}", weekday, nutrient.as_str()))
+                        inputmode="decimal"
+                        value=(target.and_then(|t| nutrient.of_target(t)).map(fixed::format_short).unwrap_or_default())
+                        placeholder=(every_day.and_then(|t| nutrient.of_target(t)).map(fixed::format_short).unwrap_or_default())
+                        {}
+                }
+            }
+        }
+    }
+}
+
+pub fn targets_page(user: &User, targets: &[Target]) -> Markup {
+    html! {
+        (toolbar(user))
+        h3 class="text-xl mb-4" { "Daily targets" }
+        p class="text-zinc-400 mb-4" {
+            "Targets for a weekday replace those for every day where they are filled in. Leave a field blank for no target."
+        }
+        (targets_form(targets, None))
+    }
+}
+
+/// Inputs for the targets of every day and each weekday, with the outcome of
+/// saving them.
+pub fn targets_form(targets: &[Target], status: Option<Result<&str, &str>>) -> Markup {
+    let find = |weekday: i32| targets.iter().find(|t| t.weekday == weekday);
+    let every_day = find(targets::EVERY_DAY);
+    let weekdays = [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ];
+    html! {
+        form id="targets" hx-post="/targets" hx-swap="outerHTML" {
+            table class="mb-4" {
+                thead {
+                    tr {
+                        th {}
+                        @for nutrient in Macro::ALL {
+                            th class="text-left pr-2" { (nutrient.label()) " (" (nutrient.unit()) ")" }
+                        }
+                    }
+                }
+                tbody {
+                    (target_inputs("Every day", targets::EVERY_DAY, every_day, None))
+                    @for (i, label) in weekdays.iter().enumerate() {
+                        @let weekday = i as i32 + 1;
+                        (target_inputs(label, weekday, find(weekday), every_day))
+                    }
+                }
+            }
+            input type="submit" value="Save" class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400" {}
+            @match status {
+                Some(Ok(message)) => p class="text-green-400 font-bold mt-2" { (message) },
+                Some(Err(message)) => p class="text-red-400 font-bold mt-2" { (message) },
+                None => {},
+            }
+        }
+    }
+}
+
+pub fn planner_page(user: &User, calories: &str, protein: &str) -> Markup {
+    html! {
+        (toolbar(user))
+        h3 class="text-xl mb-4" { "Meal plan" }
+        p class="text-zinc-400 mb-4" {
+            "Pick the foods and recipes a day may use and how many grams of each, and a plan is made that gets as close to the targets as they allow."
+        }
+        (plan_form(calories, protein, &[]))
+        input
+            class="bg-zinc-800 px-4 py-2 rounded-lg w-full"
+            name="q"
+            placeholder="Search for a food or recipe to use"
+            hx-get="/planner/search"
+            hx-trigger="input delay:300ms"
+            hx-target="#plan-matches"
+            {}
+        div id="plan-matches" class="mt-2" {}
+        div class="h-8" {}
+        div id="plan-result" {}
+    }
+}
+
+/// Targets of the plan and the foods it may use with the least and most grams
+/// of each.
+pub fn plan_form(calories: &str, protein: &str, pool: &[PoolItem]) -> Markup {
+    html! {
+        form id="plan" class="mb-4" hx-post="/planner/pool" hx-swap="outerHTML" {
+            div class="flex gap-4 items-center mb-4" {
+                label for="plan-calories" class="font-bold" { "Energy" }
+                input id="plan-calories" class="bg-zinc-800 px-2 py-1 rounded-lg w-24" name="calories" inputmode="decimal" value=(calories) {}
+                " kcal"
+                label for="plan-protein" class="font-bold ml-4" { "Protein" }
+                input id="plan-protein" class="bg-zinc-800 px-2 py-1 rounded-lg w-24" name="protein" inputmode="decimal" value=(protein) {}
+                " g"
+            }
+            @if pool.is_empty() {
+                p class="text-zinc-400 mb-4" { "Search for the foods the plan may use below." }
+            } @else {
+                table class="mb-4" {
+                    thead {
+                        tr {
+                            th class="text-left pr-8" { "Food" }
+                            th class="text-right pr-4" { "kcal/100g" }
+                            th class="text-right pr-4" { "Protein/100g" }
+                            th class="text-left pr-4" { "Least (g)" }
+                            th class="text-left pr-4" { "Most (g)" }
+                            th {}
+                        }
+                    }
+                    tbody {
+                        @for (i, item) in pool.iter().enumerate() {
+                            tr {
+                                td class="pr-8 py-1" {
+                                    input type="hidden" name="food" value=(item.food.id) {}
+                                    (item.food.name)
+                                }
+                                td class="text-right pr-4 py-1" { (fixed::format(item.food.calories, 0)) }
+                                td class="text-right pr-4 py-1" { (fixed::format(item.food.protein, 1)) }
+                                td class="pr-4 py-1" {
+                                    input class="bg-zinc-800 px-2 py-1 rounded-lg w-20" name="min" inputmode="decimal" value=(item.min) placeholder="0" {}
+                                }
+                                td class="pr-4 py-1" {
+                                    input class="bg-zinc-800 px-2 py-1 rounded-lg w-20" name="max" inputmode="decimal" value=(item.max) placeholder=(planner::DEFAULT_MAX) {}
+                                }
+                                td class="py-1" {
+                                    button
+                                        type="button"
+                                        class="text-red-400 hover:underline text-sm"
+                                        hx-post="/planner/pool"
+                                        hx-vals=(format!("{{\"remove\": {}}}", i))
+                                        hx-target="#plan"
+                                    {
+                                        "Remove"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            button
+                type="button"
+                class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400"
+                hx-post="/planner/solve"
+                hx-target="#plan-result"
+                hx-swap="innerHTML"
+            {
+                "Make a plan"
+            }
+        }
+    }
+}
+
+/// Foods matching the planner search, each added to the pool when clicked.
+pub fn planner_matches(foods: &[Food]) -> Markup {
+    html! {
+        @if foods.is_empty() {
+            p class="text-zinc-400" { "No matching foods" }
+        }
+        @for food in foods {
+            button
+                class="block text-left px-4 py-1 rounded-lg hover:bg-zinc-600 w-full"
+                hx-post="/planner/pool"
+                hx-vals=(format!("{{\"add\": {}}}", food.id))
+                hx-include="#plan"
+                hx-target="#plan"
+                hx-swap="outerHTML"
+            {
+                (food.name) span class="text-zinc-400 ml-4" { (fixed::format(food.calories, 0)) " kcal/100g" }
+            }
+        }
+    }
+}
+
+/// A plan made by the planner compared to its targets, as a meal that can be
+/// changed further and saved.
+pub fn plan_result(plan: Result<(&[MealItem], &Target), &str>) -> Markup {
+    match plan {
+        Ok((items, target)) => html! {
+            (target_progress(&meals::totals(items), target))
+            (meal_panel("Meal plan", items, None))
+        },
+        Err(error) => html! {
+            p class="text-red-400 font-bold" { (error) }
+        },
+    }
+}
+
+/// A weight in hundredths of a kg in `unit`, e.g. "82.4 kg".
+fn weight_label(unit: WeightUnit, weight: i32) -> String {
+    format!("{} {}", fixed::format(unit.from_kg(weight), 1), unit.code())
+}
+
+/// E.g. "Lose 0.5 kg a week".
+fn weekly_change_label(unit: WeightUnit, change: i32) -> String {
+    let amount = |c: i32| format!("{} {}", fixed::format_short(unit.from_kg(c)), unit.code());
+    match change {
+        0 => "Keep my weight".to_owned(),
+        c if c < 0 => format!("Lose {} a week", amount(-c)),
+        c => format!("Gain {} a week", amount(c)),
+    }
+}
+
+pub fn profile_page(user: &User, view: &ProfileView) -> Markup {
+    html! {
+        (toolbar(user))
+        h3 class="text-xl mb-4" { "Profile" }
+        div id="profile" {
+            (profile_details(view, None))
+        }
+    }
+}
+
+/// The profile form, latest weight and energy expenditure estimated from
+/// them, with the outcome of the last change.
+pub fn profile_details(view: &ProfileView, status: Option<Result<&str, &str>>) -> Markup {
+    let profile = view.profile.as_ref();
+    let input = "bg-zinc-800 px-4 py-2 rounded-lg";
+    html! {
+        @match status {
+            Some(Ok(message)) => p class="text-green-400 font-bold mb-4" { (message) },
+            Some(Err(message)) => p class="text-red-400 font-bold mb-4" { (message) },
+            None => {},
+        }
+        form class="grid grid-cols-2 gap-4 mb-8 max-w-xl" hx-post="/profile" hx-target="#profile" {
+            label for="sex" { "Sex" }
+            select id="sex" class=(input) name="sex" {
+                @for sex in Sex::ALL {
+                    option value=(sex.as_str()) selected[profile.is_some_and(|p| p.sex == sex.as_str())] { (sex.label()) }
+                }
+            }
+            label for="birth-date" { "Date of birth" }
+            input id="birth-date" class=(input) type="date" name="birth_date" value=(profile.map(|p| p.birth_date.to_string()).unwrap_or_default()) {}
+            label for="height" { "Height (cm)" }
+            input id="height" class=(input) name="height" inputmode="decimal" value=(profile.map(|p| fixed::format_short(p.height)).unwrap_or_default()) {}
+            label for="activity" { "Activity" }
+            select id="activity" class=(input) name="activity" {
+                @for activity in Activity::ALL {
+                    option value=(activity.as_str()) selected[profile.is_some_and(|p| p.activity == activity.as_str())] { (activity.label()) }
+                }
+            }
+            label for="body-fat" { "Body fat % (optional)" }
+            input id="body-fat" class=(input) name="body_fat" inputmode="decimal" value=(profile.and_then(|p| p.body_fat).map(fixed::format_short).unwrap_or_default()) {}
+            label for="weekly-change" { "Goal" }
+            select id="weekly-change" class=(input) name="weekly_change" {
+                @for change in profile::WEEKLY_CHANGES {
+                    option value=(change) selected[profile.map_or(change == 0, |p| p.weekly_change == change)] { (weekly_change_label(view.unit, change)) }
+                }
+            }
+            div {}
+            input type="submit" value="Save" class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400" {}
+        }
+
+        h3 class="text-xl mb-4" { "Weight" }
+        p class="mb-8" {
+            @match view.weight {
+                Some(weight) => { "Latest " span class="font-bold" { (weight_label(view.unit, weight)) } ". " },
+                None => "No weight logged yet. ",
+            }
+            a href="/body" class="text-sky-400 hover:underline" { "Log your weight and measurements" }
+        }
+
+        h3 class="text-xl mb-4" { "Energy" }
+        @match &view.estimate {
+            Some(estimate) => (energy_estimate(view.unit, estimate)),
+            None => p class="text-zinc-400" {
+                "Fill in your profile and log your weight to estimate how much energy you use."
+            },
+        }
+    }
+}
+
+fn energy_estimate(unit: WeightUnit, estimate: &Estimate) -> Markup {
+    let kcal = |value: i32| format!("{} kcal", fixed::format(value, 0));
+    html! {
+        table class="mb-4" {
+            tbody {
+                tr {
+                    td class="pr-8 py-1" { "Basal metabolic rate (Mifflin-St Jeor)" }
+                    td class="py-1 text-right" { (kcal(estimate.mifflin_st_jeor)) }
+                }
+                tr {
+                    td class="pr-8 py-1" { "Basal metabolic rate (Katch-McArdle)" }
+                    td class="py-1 text-right" {
+                        @match estimate.katch_mcardle {
+                            Some(value) => (kcal(value)),
+                            None => span class="text-zinc-400" { "needs your body fat" },
+                        }
+                    }
+                }
+                tr {
+                    td class="pr-8 py-1" { "Total daily energy expenditure" }
+                    td class="py-1 text-right" { (kcal(estimate.tdee)) }
+                }
+                tr {
+                    td class="pr-8 py-1" { "Measured from your diary and weight" }
+                    td class="py-1 text-right" {
+                        @match &estimate.adaptive {
+                            Some(adaptive) => (kcal(adaptive.expenditure)),
+                            None => span class="text-zinc-400" { "not enough data" },
+                        }
+                    }
+                }
+            }
+        }
+        @match &estimate.adaptive {
+            Some(adaptive) => p class="text-sm text-zinc-400 mb-4" {
+                "From " (adaptive.logged_days) " days of logged food in the last "
+                (energy::WINDOW_DAYS) " days, during which your weight changed by "
+                (fixed::format(unit.from_kg(adaptive.weekly_change), 2)) " " (unit.code()) " a week."
+            },
+            None => p class="text-sm text-zinc-400 mb-4" {
+                "Log your food on at least " (energy::MIN_LOGGED_DAYS) " of the last "
+                (energy::WINDOW_DAYS) " days and your weight at least " (energy::MIN_WEIGHT_SPAN)
+                " days apart to measure how much energy you actually use."
+            },
+        }
+        div class="flex items-center gap-4" {
+            p class="font-bold" { "Suggested daily intake: " (kcal(estimate.suggestion)) }
+            button
+                class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400"
+                hx-post="/profile/energy-target"
+                hx-target="#profile"
+            {
+                "Use as energy target"
+            }
+        }
+    }
+}
+
+pub fn body_page(user: &User, view: &BodyView) -> Markup {
+    html! {
+        (toolbar(user))
+        h3 class="text-xl mb-4" { "Body" }
+        div id="body" {
+            (body_details(view, None))
+        }
+    }
+}
+
+/// E.g. "losing 0.45 kg a week".
+fn weekly_rate_label(unit: WeightUnit, rate: i32) -> String {
+    let amount = |r: i32| format!("{} {}", fixed::format(unit.from_kg(r), 2), unit.code());
+    match rate {
+        0 => "holding steady".to_owned(),
+        r if r < 0 => format!("losing {} a week", amount(-r)),
+        r => format!("gaining {} a week", amount(r)),
+    }
+}
+
+/// E.g. "90 days".
+fn chart_range_label(days: Option<i64>) -> String {
+    match days {
+        Some(365) => "Year".to_owned(),
+        Some(days) => format!("{} days", days),
+        None => "All".to_owned(),
+    }
+}
+
+/// The weight trend, chart and log of measurements, with the outcome of the
+/// last change. Changes re-render it in the range of the chart, which the
+/// requests include from the hidden `days` field.
+pub fn body_details(view: &BodyView, status: Option<Result<&str, &str>>) -> Markup {
+    let unit = view.unit;
+    let input = "bg-zinc-800 px-4 py-2 rounded-lg";
+    let trend_on = |date: Date| view.trend.iter().find(|(day, _)| *day == date).map(|t| t.1);
+    html! {
+        @match status {
+            Some(Ok(message)) => p class="text-green-400 font-bold mb-4" { (message) },
+            Some(Err(message)) => p class="text-red-400 font-bold mb-4" { (message) },
+            None => {},
+        }
+        input id="chart-days" type="hidden" name="days" value=(view.days.map_or("all".to_owned(), |d| d.to_string())) {}
+        div class="flex justify-between items-center mb-4" {
+            p {
+                @if let Some(&(_, trend)) = view.trend.last() {
+                    "Trend " span class="font-bold" { (weight_label(unit, trend)) }
+                    @match view.weekly_rate {
+                        Some(rate) => { ", " (weekly_rate_label(unit, rate)) },
+                        None => span class="text-zinc-400" {
+                            ", log your weight over " (measurements::MIN_RATE_SPAN) " days to see how fast it changes"
+                        },
+                    }
+                }
+            }
+            div class="flex items-center gap-4" {
+                @for days in CHART_RANGES {
+                    @if days == view.days {
+                        span class="font-bold" { (chart_range_label(days)) }
+                    } @else {
+                        a href=(format!("/body?days={}", days.map_or("all".to_owned(), |d| d.to_string()))) class="text-sky-400 hover:underline" {
+                            (chart_range_label(days))
+                        }
+                    }
+                }
+                select class="bg-zinc-800 px-2 py-1 rounded-lg" name="weight_unit" hx-post="/body/unit" hx-trigger="change" hx-target="#body" hx-include="#chart-days" {
+                    @for option in WeightUnit::ALL {
+                        option value=(option.code()) selected[option == unit] { (option.code()) }
+                    }
+                }
+            }
+        }
+        (weight_chart(view))
+
+        form class="flex gap-4 mb-4" hx-post="/body/measurements" hx-target="#body" hx-include="#chart-days" {
+            input class=(input) type="date" name="date" value=(diary::today().to_string()) {}
+            input class=(format!("{} w-32", input)) name="weight" inputmode="decimal" placeholder=(format!("Weight ({})", unit.code())) {}
+            input class=(format!("{} w-32", input)) name="waist" inputmode="decimal" placeholder="Waist (cm)" {}
+            input class=(format!("{} w-32", input)) name="body_fat" inputmode="decimal" placeholder="Body fat %" {}
+            input type="submit" value="Log" class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400" {}
+        }
+        @if !view.measurements.is_empty() {
+            table class="mb-8" {
+                thead {
+                    tr class="text-left text-zinc-400" {
+                        th class="pr-8 py-1" { "Date" }
+                        th class="pr-8 py-1 text-right" { "Weight" }
+                        th class="pr-8 py-1 text-right" { "Trend" }
+                        th class="pr-8 py-1 text-right" { "Waist" }
+                        th class="pr-8 py-1 text-right" { "Body fat" }
+                        th {}
+                    }
+                }
+                tbody {
+                    @for measurement in view.measurements.iter().rev() {
+                        tr {
+                            td class="pr-8 py-1" { (measurement.date) }
+                            td class="pr-8 py-1 text-right" {
+                                (measurement.weight.map(|w| weight_label(unit, w)).unwrap_or_default())
+                            }
+                            td class="pr-8 py-1 text-right text-zinc-400" {
+                                (trend_on(measurement.date).map(|t| weight_label(unit, t)).unwrap_or_default())
+                            }
+                            td class="pr-8 py-1 text-right" {
+                                (measurement.waist.map(|w| format!("{} cm", fixed::format(w, 1))).unwrap_or_default())
+                            }
+                            td class="pr-8 py-1 text-right" {
+                                (measurement.body_fat.map(|b| format!("{} %", fixed::format(b, 1))).unwrap_or_default())
+                            }
+                            td class="py-1" {
+                                button
+                                    class="text-red-400 hover:underline text-sm"
+                                    hx-post=(format!("/body/measurements/{}/delete", measurement.id))
+                                    hx-target="#body"
+                                    hx-include="#chart-days"
+                                {
+                                    "Remove"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Size of the weight chart in SVG units.
+const CHART_WIDTH: f64 = 720.0;
+const CHART_HEIGHT: f64 = 240.0;
+
+/// Room around the plot, on the left for the weights and below for the dates.
+const CHART_LEFT: f64 = 48.0;
+const CHART_BOTTOM: f64 = 24.0;
+const CHART_MARGIN: f64 = 8.0;
+
+/// Horizontal lines across the weight chart.
+const CHART_GRID_LINES: i32 = 5;
+
+/// The weights of the range as points and their trend as a line.
+fn weight_chart(view: &BodyView) -> Markup {
+    let unit = view.unit;
+    let weights = measurements::weights(&view.measurements);
+    let (Some(first), Some(last)) = (weights.first(), weights.last()) else {
+        return html! { p class="text-zinc-400 mb-8" { "Log your weight to see it charted." } };
+    };
+
+    let start = view
+        .days
+        .map_or(first.0, |days| diary::today() - Duration::days(days));
+    let days = (last.0 - start).whole_days().max(1) as f64;
+    let values = weights
+        .iter()
+        .chain(&view.trend)
+        .map(|&(_, w)| unit.from_kg(w));
+    let (low, high) = values.fold((i32::MAX, i32::MIN), |(low, high), w| {
+        (low.min(w), high.max(w))
+    });
+    // Whole units with one to spare on either side
+    let low = (f64::from(low) / 100.0).floor() - 1.0;
+    let high = (f64::from(high) / 100.0).ceil() + 1.0;
+
+    let x = |date: Date| {
+        let day = (date - start).whole_days() as f64;
+        CHART_LEFT + day / days * (CHART_WIDTH - CHART_LEFT - CHART_MARGIN)
+    };
+    let y = |value: f64| {
+        CHART_MARGIN + (high - value) / (high - low) * (CHART_HEIGHT - CHART_BOTTOM - CHART_MARGIN)
+    };
+    let y_of = |weight: i32| y(f64::from(unit.from_kg(weight)) / 100.0);
+    let trend: Vec<String> = view
+        .trend
+        .iter()
+        .map(|&(date, weight)| format!("{:.1},{:.1}", x(date), y_of(weight)))
+        .collect();
+
+    let grid: Vec<(f64, f64)> = (0..CHART_GRID_LINES)
+        .map(|line| {
+            let value = low + (high - low) * f64::from(line) / f64::from(CHART_GRID_LINES - 1);
+            (y(value), value)
+        })
+        .collect();
+    let right = CHART_WIDTH - CHART_MARGIN;
+    let bottom = CHART_HEIGHT - 6.0;
+
+    html! {
+        svg class="w-full mb-8" viewBox=(format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)) {
+            @for (line_y, value) in grid {
+                line class="stroke-zinc-600" x1=(CHART_LEFT) x2=(right) y1=(line_y) y2=(line_y) {}
+                text class="fill-zinc-400 text-xs" x=((CHART_LEFT - 6.0)) y=((line_y + 4.0)) text-anchor="end" {
+                    (format!("{:.1}", value))
+                }
+            }
+            text class="fill-zinc-400 text-xs" x=(CHART_LEFT) y=(bottom) { (start) }
+            text class="fill-zinc-400 text-xs" x=(right) y=(bottom) text-anchor="end" { (last.0) }
+            @for &(date, weight) in &weights {
+                circle class="fill-zinc-400" cx=(format!("{:.1}", x(date))) cy=(format!("{:.1}", y_of(weight))) r="3" {}
+            }
+            polyline class="stroke-sky-400" fill="none" stroke-width="2" points=(trend.join(" ")) {}
+        }
+    }
+}
+
+/// Link to the report of `from` to `to`, in bold if it is the one shown.
+fn report_link(report: &Report, label: &str, from: Date, to: Date) -> Markup {
+    html! {
+        @if (from, to) == (report.from, report.to) {
+            span class="font-bold" { (label) }
+        } @else {
+            a href=(format!("/reports?from={}&to={}", from, to)) class="text-sky-400 hover:underline" { (label) }
+        }
+    }
+}
+
+pub fn report_page(user: &User, report: &Report, error: Option<&str>) -> Markup {
+    let today = diary::today();
+    let month = today.replace_day(1).unwrap_or(today);
+    let last_month_end = month - Duration::days(1);
+    let last_month = last_month_end.replace_day(1).unwrap_or(last_month_end);
+    let input = "bg-zinc-800 px-4 py-2 rounded-lg";
+    html! {
+        (toolbar(user))
+        h3 class="text-xl mb-4" { "Report" }
+        @if let Some(error) = error {
+            p class="text-red-400 font-bold mb-4" { (error) }
+        }
+        div class="flex items-center gap-4 mb-4" {
+            (report_link(report, "Last 7 days", today - Duration::days(6), today))
+            (report_link(report, "Last 30 days", today - Duration::days(29), today))
+            (report_link(report, "This month", month, today))
+            (report_link(report, "Last month", last_month, last_month_end))
+        }
+        form class="flex items-center gap-4 mb-8" action="/reports" method="get" {
+            input class=(input) type="date" name="from" value=(report.from.to_string()) {}
+            "to"
+            input class=(input) type="date" name="to" value=(report.to.to_string()) {}
+            input type="submit" value="Show" class="px-4 py-2 bg-sky-500 rounded-lg hover:bg-sky-400" {}
+        }
+        p class="mb-4" {
+            (long_date(report.from)) " to " (long_date(report.to)) ": "
+            span class="font-bold" { (report.daily.len()) " of " (report.days()) }
+            " days logged"
+        }
+        @match &report.average {
+            Some(average) => (report_details(report, average)),
+            None => p class="text-zinc-400" { "Nothing was logged in these days." },
+        }
+    }
+}
+
+/// The parts of a report with something logged, `average` being the average
+/// day.
+fn report_details(report: &Report, average: &Totals) -> Markup {
+    let goals = |nutrient: Macro| -> Vec<i32> {
+        report
+            .targets
+            .iter()
+            .flatten()
+            .filter_map(|t| nutrient.of_target(t))
+            .collect()
+    };
+    let total_calories: i32 = report.daily.iter().map(|(_, t)| t.calories).sum();
+    let total_protein: i32 = report.daily.iter().map(|(_, t)| t.protein).sum();
+    html! {
+        h4 class="font-bold mb-2" { "Average day" }
+        table class="mb-8" {
+            thead {
+                tr class="text-left text-zinc-400" {
+                    th {}
+                    th class="text-right pl-8" { "Eaten" }
+                    th class="text-right pl-8" { "Target" }
+                }
+            }
+            tbody {
+                @for nutrient in Macro::ALL {
+                    @let goals = goals(nutrient);
+                    tr {
+                        td class="pr-4 py-1" { (nutrient.label()) }
+                        td class="text-right pl-8 py-1" {
+                            @match nutrient.of_totals(average) {
+                                Some(eaten) => { (fixed::format(eaten, 0)) " " (nutrient.unit()) },
+                                None => span class="text-zinc-400" { "unknown" },
+                            }
+                        }
+                        td class="text-right pl-8 py-1 text-zinc-400" {
+                            @if !goals.is_empty() {
+                                (fixed::format(goals.iter().sum::<i32>() / goals.len() as i32, 0)) " " (nutrient.unit())
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        @if average.fat.is_none() || average.carbohydrates.is_none() || average.fiber.is_none() {
+            p class="text-sm text-zinc-400 -mt-6 mb-8" {
+                "Some nutrients are unknown as foods that were eaten lack them."
+            }
+        }
+
+        h4 class="font-bold mb-2" { "Energy by day" }
+        (energy_chart(report))
+
+        @if !report.adherence.is_empty() {
+            h4 class="font-bold mb-2" { "Targets" }
+            div class="bg-zinc-800 rounded-lg p-4 mb-2" {
+                @for adherence in &report.adherence {
+                    @let days = adherence.days().max(1);
+                    div class="flex items-center gap-4 py-1" {
+                        span class="w-16" { (adherence.nutrient.label()) }
+                        div class="w-64 h-3 bg-zinc-700 rounded-full flex overflow-hidden" {
+                            div class="h-3 bg-green-500" style=(format!("width: {}%", adherence.on_target * 100 / days)) {}
+                            div class="h-3 bg-amber-400" style=(format!("width: {}%", adherence.under * 100 / days)) {}
+                            div class="h-3 bg-red-500" style=(format!("width: {}%", adherence.over * 100 / days)) {}
+                        }
+                        span class="text-sm" {
+                            (adherence.on_target) " of " (adherence.days()) " days on target"
+                            @if adherence.under > 0 {
+                                ", " span class="text-amber-400" { (adherence.under) " under" }
+                            }
+                            @if adherence.over > 0 {
+                                ", " span class="text-red-400" { (adherence.over) " over" }
+                            }
+                        }
+                    }
+                }
+            }
+            p class="text-sm text-zinc-400 mb-8" {
+                "Within " (targets::TOLERANCE) " % of a target counts as on target."
+            }
+        }
+
+        h4 class="font-bold mb-2" { "Day of the week" }
+        (weekday_table(report))
+
+        div class="grid grid-cols-2 gap-8 mb-8" {
+            div { (top_foods("Most energy", &report.top_by_calories, total_calories, |f| f.calories, "kcal", 0)) }
+            div { (top_foods("Most protein", &report.top_by_protein, total_protein, |f| f.protein, "g", 1)) }
+        }
+    }
+}
+
+/// Bars of the energy eaten each logged day, colored by how it compares to
+/// the energy target of the day, which is marked across the bar.
+fn energy_chart(report: &Report) -> Markup {
+    let targets: Vec<Option<i32>> = report
+        .targets
+        .iter()
+        .map(|t| t.as_ref().and_then(|t| t.calories))
+        .collect();
+    let most = report
+        .daily
+        .iter()
+        .map(|(_, t)| t.calories)
+        .chain(targets.iter().flatten().copied())
+        .max()
+        .unwrap_or(0);
+    // Whole 500 kcal above the most eaten
+    let high = ((f64::from(most) / 100.0 / 500.0).ceil() * 500.0).max(500.0);
+
+    let slot = (CHART_WIDTH - CHART_LEFT - CHART_MARGIN) / report.days() as f64;
+    let bar = (slot * 0.7).max(1.0);
+    let base = CHART_HEIGHT - CHART_BOTTOM;
+    let y = |kcal: f64| CHART_MARGIN + (1.0 - kcal / high) * (base - CHART_MARGIN);
+    let y_of = |calories: i32| y(f64::from(calories) / 100.0);
+    let x_of = |date: Date| {
+        let day = (date - report.from).whole_days() as f64;
+        CHART_LEFT + day * slot + (slot - bar) / 2.0
+    };
+    let grid: Vec<(f64, f64)> = (0..CHART_GRID_LINES)
+        .map(|line| {
+            let kcal = high * f64::from(line) / f64::from(CHART_GRID_LINES - 1);
+            (y(kcal), kcal)
+        })
+        .collect();
+    let right = CHART_WIDTH - CHART_MARGIN;
+    let bottom = CHART_HEIGHT - 6.0;
+
+    html! {
+        svg class="w-full mb-8" viewBox=(format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)) {
+            @for (line_y, kcal) in grid {
+                line class="stroke-zinc-600" x1=(CHART_LEFT) x2=(right) y1=(line_y) y2=(line_y) {}
+                text class="fill-zinc-400 text-xs" x=((CHART_LEFT - 6.0)) y=((line_y + 4.0)) text-anchor="end" {
+                    (format!("{:.0}", kcal))
+                }
+            }
+            text class="fill-zinc-400 text-xs" x=(CHART_LEFT) y=(bottom) { (report.from) }
+            text class="fill-zinc-400 text-xs" x=(right) y=(bottom) text-anchor="end" { (report.to) }
+            @for ((date, totals), target) in report.daily.iter().zip(&targets) {
+                @let x = x_of(*date);
+                @let top = y_of(totals.calories);
+                @let fill = match target.map(|t| Status::of(totals.calories, t)) {
+                    Some(Status::Over) => "fill-red-500",
+                    Some(Status::OnTarget) => "fill-green-500",
+                    _ => "fill-sky-500",
+                };
+                rect class=(fill) x=(format!("{:.1}", x)) y=(format!("{:.1}", top)) width=(format!("{:.1}", bar)) height=(format!("{:.1}", base - top)) {
+                    title { (date) ": " (fixed::format(totals.calories, 0)) " kcal" }
+                }
+                @if let Some(target) = target {
+                    @let target_y = format!("{:.1}", y_of(*target));
+                    line class="stroke-zinc-100" stroke-width="2" x1=(format!("{:.1}", x)) x2=(format!("{:.1}", x + bar)) y1=(target_y) y2=(target_y) {}
+                }
+            }
+        }
+    }
+}
+
+/// Average energy and macros of each day of the week, with bars of energy.
+fn weekday_table(report: &Report) -> Markup {
+    let most = report
+        .weekdays
+        .iter()
+        .filter_map(|w| w.average.as_ref().map(|a| a.calories))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    html! {
+        table class="mb-8" {
+            thead {
+                tr class="text-left text-zinc-400" {
+                    th {}
+                    th class="text-right pl-8" { "Days" }
+                    th class="pl-8" { "Energy" }
+                    th class="text-right pl-8" { "Protein" }
+                    th class="text-right pl-8" { "Carbs" }
+                    th class="text-right pl-8" { "Fat" }
+                }
+            }
+            tbody {
+                @for weekday in &report.weekdays {
+                    tr {
+                        td class="pr-4 py-1" { (weekday.weekday) }
+                        td class="text-right pl-8 py-1 text-zinc-400" { (weekday.days) }
+                        @if let Some(average) = &weekday.average {
+                            td class="pl-8 py-1" {
+                                div class="flex items-center gap-4" {
+                                    div class="w-48 h-3 bg-zinc-800 rounded-full" {
+                                        div class="h-3 rounded-full bg-sky-500" style=(format!("width: {}%", i64::from(average.calories) * 100 / i64::from(most))) {}
+                                    }
+                                    span class="text-sm" { (fixed::format(average.calories, 0)) " kcal" }
+                                }
+                            }
+                            td class="text-right pl-8 py-1" { (fixed::format(average.protein, 1)) " g" }
+                            td class="text-right pl-8 py-1" { (optional_amount(average.carbohydrates)) " g" }
+                            td class="text-right pl-8 py-1" { (optional_amount(average.fat)) " g" }
+                        } @else {
+                            td class="pl-8 py-1 text-zinc-400" colspan="4" { "Nothing logged" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The foods of a top list with how much of `total` of a nutrient each gave.
+fn top_foods(
+    title: &str,
+    foods: &[FoodTotal],
+    total: i32,
+    amount: fn(&FoodTotal) -> i32,
+    unit: &str,
+    decimals: u32,
+) -> Markup {
+    html! {
+        h4 class="font-bold mb-2" { (title) }
+        table {
+            tbody {
+                @for food in foods {
+                    tr {
+                        td class="pr-4 py-1" { (food.food.name) }
+                        td class="text-right pl-4 py-1 text-zinc-400" { (fixed::format(food.grams, 0)) " g" }
+                        td class="text-right pl-4 py-1" { (fixed::format(amount(food), decimals)) " " (unit) }
+                        td class="text-right pl-4 py-1 text-zinc-400" {
+                            ((i64::from(amount(food)) * 100 / i64::from(total.max(1)))) " %"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An amount of a micronutrient, with a decimal when it's small enough for it
+/// to matter.
+fn micronutrient_amount(amount: i32, unit: &str) -> String {
+    let decimals = if amount.abs() < 100 * fixed::SCALE {
+        1
+    } else {
+        0
+    };
+    format!("{} {}", fixed::format(amount, decimals), unit)
+}
+
+/// An average intake of a micronutrient with its share of the recommendation,
+/// flagged when low.
+fn micronutrient_cell(intake: &Intake, amount: Option<i32>) -> Markup {
+    html! {
+        td class="text-right pl-8" {
+            @match amount {
+                Some(amount) => {
+                    @let percent = intake.percent(amount);
+                    span class=(if percent < micronutrients::LOW_PERCENT { "text-amber-400" } else { "" }) {
+                        (micronutrient_amount(amount, &intake.nutrient.unit))
+                        span class="text-zinc-400" { " (" (percent) " %)" }
+                    }
+                }
+                None => span class="text-zinc-400" { "-" },
+            }
+        }
+    }
+}
+
+pub fn micronutrients_page(user: &User, coverage: Option<&Coverage>) -> Markup {
+    html! {
+        (toolbar(user))
+        h3 class="text-xl mb-4" { "Vitamins and minerals" }
+        @match coverage {
+            Some(coverage) => (micronutrient_details(coverage)),
+            None => p class="text-zinc-400" {
+                "Fill in your sex and date of birth on your "
+                a href="/profile" class="text-sky-400 hover:underline" { "profile" }
+                " to compare what you eat to the recommended intakes."
+            },
+        }
+    }
+}
+
+fn micronutrient_details(coverage: &Coverage) -> Markup {
+    let group = match coverage.sex {
+        Sex::Female => "women",
+        Sex::Male => "men",
+    };
+    let low: Vec<&Intake> = coverage.chronically_low().collect();
+    html! {
+        p class="mb-4" {
+            "Compared to the Nordic Nutrition Recommendations 2012 for " (group)
+            " aged " (coverage.age.max(18)) ". "
+            span class="font-bold" { (coverage.logged_days) " of " (micronutrients::WINDOW_DAYS) }
+            " days logged in the last four weeks."
+        }
+        @if coverage.intakes.is_empty() {
+            p class="text-zinc-400" {
+                "No vitamins or minerals have been imported. Run populate_db to import them from the food database."
+            }
+        } @else {
+            table class="mb-4" {
+                thead {
+                    tr class="text-left text-zinc-400" {
+                        th {}
+                        th class="text-right pl-8" { "Recommended" }
+                        th class="text-right pl-8" { "Today" }
+                        th class="text-right pl-8" { "7 days" }
+                        th class="text-right pl-8" { "28 days" }
+                        th class="text-right pl-8" { "Known" }
+                    }
+                }
+                tbody {
+                    @for intake in &coverage.intakes {
+                        tr {
+                            td { (intake.reference.label) }
+                            td class="text-right pl-8" { (micronutrient_amount(intake.recommended, &intake.nutrient.unit)) }
+                            (micronutrient_cell(intake, intake.today))
+                            (micronutrient_cell(intake, intake.week))
+                            (micronutrient_cell(intake, intake.window))
+                            td class="text-right pl-8 text-zinc-400" { (intake.known) " %" }
+                        }
+                    }
+                }
+            }
+            p class="text-sm text-zinc-400 mb-8" {
+                "Averages are over the days with logged food. Known is how much of what was eaten, by weight, has an amount of the nutrient; foods without one count as having none."
+            }
+            h4 class="font-bold mb-2" { "Chronically low" }
+            @if !coverage.enough_days() {
+                p class="text-zinc-400" {
+                    "Log food on at least " (micronutrients::MIN_LOGGED_DAYS)
+                    " days of the last four weeks to find out what you are short of."
+                }
+            } @else if low.is_empty() {
+                p class="text-zinc-400" {
+                    "Nothing averaged below " (micronutrients::LOW_PERCENT) " % of the recommendation in the last four weeks."
+                }
+            } @else {
+                @for intake in low {
+                    div class="bg-zinc-800 rounded-lg p-4 mb-4" {
+                        p class="mb-2" {
+                            span class="font-bold text-amber-400" { (intake.reference.label) }
+                            @if let Some(window) = intake.window {
+                                " averaged " (intake.percent(window)) " % of the recommendation. Foods with the most of it:"
+                            }
+                        }
+                        table {
+                            tbody {
+                                @for (food, amount) in &intake.sources {
+                                    tr {
+                                        td { (food.name) }
+                                        td class="text-right pl-8" { (micronutrient_amount(*amount, &intake.nutrient.unit)) " per 100 g" }
+                                        td class="text-right pl-8 text-zinc-400" { (intake.percent(*amount)) " % of a day" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
             }
         }
     }
@@ -0,0 +1,150 @@
+use simple_error::{SimpleError, SimpleResult};
+
+use crate::fixed;
+use crate::models::{Food, FoodPortion};
+
+/// Units that work for every food. Volumes need the density of the food.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    Gram,
+    Hectogram,
+    Kilogram,
+    Milliliter,
+    Centiliter,
+    Deciliter,
+    Liter,
+    /// Kryddmått, 1 ml
+    Pinch,
+    /// Tesked, 5 ml
+    Teaspoon,
+    /// Matsked, 15 ml
+    Tablespoon,
+}
+
+impl Unit {
+    pub fn from_name(name: &str) -> Option<Unit> {
+        match name {
+            "g" | "gr" | "gram" | "grams" => Some(Unit::Gram),
+            "hg" => Some(Unit::Hectogram),
+            "kg" => Some(Unit::Kilogram),
+            "ml" => Some(Unit::Milliliter),
+            "cl" => Some(Unit::Centiliter),
+            "dl" => Some(Unit::Deciliter),
+            "l" | "liter" | "litre" => Some(Unit::Liter),
+            "krm" => Some(Unit::Pinch),
+            "tsk" | "tsp" | "teaspoon" | "teaspoons" => Some(Unit::Teaspoon),
+            "msk" | "tbsp" | "tablespoon" | "tablespoons" => Some(Unit::Tablespoon),
+            _ => None,
+        }
+    }
+
+    /// Hundredths of a gram in one unit of mass.
+    fn grams(&self) -> Option<i64> {
+        match self {
+            Unit::Gram => Some(100),
+            Unit::Hectogram => Some(100 * 100),
+            Unit::Kilogram => Some(1000 * 100),
+            _ => None,
+        }
+    }
+
+    /// Milliliters in one unit of volume.
+    fn milliliters(&self) -> Option<i64> {
+        match self {
+            Unit::Milliliter | Unit::Pinch => Some(1),
+            Unit::Teaspoon => Some(5),
+            Unit::Centiliter => Some(10),
+            Unit::Tablespoon => Some(15),
+            Unit::Deciliter => Some(100),
+            Unit::Liter => Some(1000),
+            _ => None,
+        }
+    }
+}
+
+/// An amount of food as typed, such as "150", "2 dl" or "1 egg".
+#[derive(Debug, PartialEq)]
+pub struct Amount {
+    /// Number of units in hundredths, see [`crate::fixed`].
+    pub quantity: i32,
+    /// Lowercased unit, a [`Unit`] or the name of a portion. Empty for grams.
+    pub unit: String,
+}
+
+/// Splits an amount into its number and unit, which may be written together
+/// as in "2dl".
+pub fn parse(input: &str) -> SimpleResult<Amount> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+
+    if number.is_empty() {
+        return Err(SimpleError::new(format!(
+            "\"{}\" doesn't start with a number, write e.g. 150 g, 2 dl or 1 egg",
+            input
+        )));
+    }
+
+    Ok(Amount {
+        quantity: fixed::parse(number)?,
+        unit: unit.trim().to_lowercase(),
+    })
+}
+
+/// Finds the portion a unit refers to, ignoring case and a plural "s" or "es".
+fn find_portion<'a>(unit: &str, portions: &'a [FoodPortion]) -> Option<&'a FoodPortion> {
+    portions.iter().find(|p| {
+        let name = p.name.to_lowercase();
+        name == unit
+            || unit.strip_suffix('s') == Some(name.as_str())
+            || unit.strip_suffix("es") == Some(name.as_str())
+    })
+}
+
+/// Converts an amount of `food` to hundredths of a gram, using the density of
+/// the food for volumes and its `portions` for other units.
+pub fn to_grams(amount: &Amount, food: &Food, portions: &[FoodPortion]) -> SimpleResult<i32> {
+    let quantity = i64::from(amount.quantity);
+
+    let hundredths = if amount.unit.is_empty() {
+        quantity
+    } else if let Some(unit) = Unit::from_name(&amount.unit) {
+        match (unit.grams(), unit.milliliters(), food.density) {
+            (Some(grams), _, _) => quantity * grams / 100,
+            (_, Some(ml), Some(density)) => quantity * ml * i64::from(density) / 100,
+            _ => {
+                return Err(SimpleError::new(format!(
+                    "The density of {} is unknown, enter the amount in grams{}",
+                    food.name,
+                    if portions.is_empty() {
+                        ""
+                    } else {
+                        " or portions"
+                    }
+                )))
+            }
+        }
+    } else if let Some(portion) = find_portion(&amount.unit, portions) {
+        quantity * i64::from(portion.grams) / 100
+    } else {
+        let mut units = vec!["g", "dl", "msk"];
+        units.extend(portions.iter().map(|p| p.name.as_str()));
+        return Err(SimpleError::new(format!(
+            "Unknown unit \"{}\" for {}, use {}",
+            amount.unit,
+            food.name,
+            units.join(", ")
+        )));
+    };
+
+    i32::try_from(hundredths).map_err(|_| SimpleError::new("The amount is too large"))
+}
+
+/// Amount of a nutrient in `grams` of a food, both in hundredths, given the
+/// amount per 100 g. Amounts too large for an `i32` are clamped.
+pub fn scale(per_100g: i32, grams: i32) -> i32 {
+    let scaled = i64::from(per_100g) * i64::from(grams) / (100 * fixed::SCALE as i64);
+    fixed::saturate(scaled)
+}
@@ -0,0 +1,97 @@
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Nullable};
+use diesel::sqlite::Sqlite;
+
+use crate::models::{Food, User};
+use crate::schema::{foods, users};
+
+/// Who can see a food besides its owner. Imported foods have no owner and are
+/// always public.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Visibility {
+    #[default]
+    Private,
+    Household,
+    Public,
+}
+
+impl Visibility {
+    pub const ALL: [Visibility; 3] = [
+        Visibility::Private,
+        Visibility::Household,
+        Visibility::Public,
+    ];
+
+    /// Value stored in `foods.visibility`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Private => "private",
+            Visibility::Household => "household",
+            Visibility::Public => "public",
+        }
+    }
+
+    pub fn from_value(value: &str) -> Option<Visibility> {
+        Visibility::ALL.into_iter().find(|v| v.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Visibility::Private => "Only me",
+            Visibility::Household => "My household",
+            Visibility::Public => "Everyone",
+        }
+    }
+}
+
+/// The user foods are shown to, with the members of their household.
+pub struct Viewer {
+    pub user_id: i32,
+    /// Users in the same household, including the viewer.
+    pub household: Vec<i32>,
+}
+
+impl Viewer {
+    pub fn load(conn: &mut SqliteConnection, user: &User) -> QueryResult<Viewer> {
+        let household = match user.household_id {
+            Some(household_id) => users::table
+                .filter(users::household_id.eq(household_id))
+                .select(users::id)
+                .load(conn)?,
+            None => vec![user.id],
+        };
+
+        Ok(Viewer {
+            user_id: user.id,
+            household,
+        })
+    }
+
+    pub fn can_view(&self, food: &Food) -> bool {
+        match (food.owner_id, Visibility::from_value(&food.visibility)) {
+            (None, _) | (_, Some(Visibility::Public)) => true,
+            (Some(owner), Some(Visibility::Household)) => self.household.contains(&owner),
+            (Some(owner), _) => owner == self.user_id,
+        }
+    }
+}
+
+/// SQL condition matching the foods `viewer` can see, the same rules as
+/// [`Viewer::can_view`].
+pub fn visible_to<QS>(
+    viewer: &Viewer,
+) -> Box<dyn BoxableExpression<QS, Sqlite, SqlType = Nullable<Bool>>>
+where
+    foods::owner_id: SelectableExpression<QS>,
+    foods::visibility: SelectableExpression<QS>,
+{
+    Box::new(
+        foods::owner_id
+            .eq(viewer.user_id)
+            .or(foods::owner_id.is_null())
+            .or(foods::visibility.eq(Visibility::Public.as_str()))
+            .or(foods::owner_id
+                .eq_any(viewer.household.clone())
+                .and(foods::visibility.eq(Visibility::Household.as_str()))),
+    )
+}
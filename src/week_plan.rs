@@ -0,0 +1,205 @@
+use diesel::prelude::*;
+use simple_error::{SimpleError, SimpleResult};
+use time::{Date, Duration};
+
+use crate::diary::{self, MealCache, Slot};
+use crate::language::Language;
+use crate::models::{Food, Meal, PlannedEntry, Target};
+use crate::nutrition::Totals;
+use crate::schema::{diary_entries, planned_entries};
+use crate::targets;
+
+/// A planned entry with what is to be eaten.
+pub struct Planned {
+    pub entry: PlannedEntry,
+    /// Name of the food or meal.
+    pub name: String,
+    /// The foods with their weights in hundredths of a gram. A meal has its
+    /// foods scaled to the number of servings.
+    pub foods: Vec<(Food, i32)>,
+}
+
+impl Planned {
+    pub fn totals(&self) -> Totals {
+        self.foods
+            .iter()
+            .fold(Totals::default(), |totals, (food, grams)| {
+                totals.add(food, *grams)
+            })
+    }
+
+    pub fn slot(&self) -> Option<Slot> {
+        Slot::from_value(&self.entry.slot)
+    }
+}
+
+/// A day of the week plan.
+pub struct PlanDay {
+    pub date: Date,
+    /// Entries in the order they were planned.
+    pub entries: Vec<Planned>,
+    pub totals: Totals,
+    pub target: Option<Target>,
+}
+
+impl PlanDay {
+    pub fn in_slot(&self, slot: Slot) -> impl Iterator<Item = &Planned> {
+        self.entries.iter().filter(move |e| e.slot() == Some(slot))
+    }
+
+    /// Whether some of what is planned hasn't been logged in the diary yet.
+    pub fn has_unlogged(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|planned| planned.entry.logged_entry_id.is_none())
+    }
+}
+
+/// Monday of the week `date` is in.
+pub fn monday(date: Date) -> Date {
+    date - Duration::days(i64::from(date.weekday().number_days_from_monday()))
+}
+
+/// Monday to Sunday of the week `date` is in, with what the user plans to eat
+/// named in `language` and the targets of each day.
+pub fn week(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    date: Date,
+    language: Language,
+) -> QueryResult<Vec<PlanDay>> {
+    let monday = monday(date);
+    let sunday = monday + Duration::days(6);
+    let entries: Vec<PlannedEntry> = planned_entries::table
+        .filter(planned_entries::user_id.eq(user_id))
+        .filter(planned_entries::date.between(monday, sunday))
+        .order((planned_entries::date, planned_entries::id))
+        .select(PlannedEntry::as_select())
+        .load(conn)?;
+    let targets = targets::for_user(conn, user_id)?;
+
+    let mut meal_contents = MealCache::new();
+    let mut planned = vec![];
+    for entry in entries {
+        if let Some((name, foods)) = diary::entry_foods(
+            conn,
+            entry.food_id,
+            entry.meal_id,
+            entry.quantity,
+            language,
+            &mut meal_contents,
+        )? {
+            planned.push(Planned { entry, name, foods });
+        }
+    }
+
+    let mut days: Vec<PlanDay> = (0..7)
+        .map(|n| {
+            let date = monday + Duration::days(n);
+            PlanDay {
+                date,
+                entries: vec![],
+                totals: Totals::default(),
+                target: targets::on(&targets, date),
+            }
+        })
+        .collect();
+    for planned in planned {
+        let day = &mut days[(planned.entry.date - monday).whole_days() as usize];
+        day.totals = day.totals.clone().plus(&planned.totals());
+        day.entries.push(planned);
+    }
+    Ok(days)
+}
+
+/// Plans `amount` of a food, usually a recipe, e.g. "2 portions".
+pub fn plan_food(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    date: Date,
+    slot: Slot,
+    food: &Food,
+    amount: &str,
+) -> SimpleResult<()> {
+    let grams = diary::food_quantity(conn, food, amount)?;
+    diesel::insert_into(planned_entries::table)
+        .values((
+            planned_entries::user_id.eq(user_id),
+            planned_entries::date.eq(date),
+            planned_entries::slot.eq(slot.as_str()),
+            planned_entries::food_id.eq(food.id),
+            planned_entries::amount.eq(amount.trim()),
+            planned_entries::quantity.eq(grams),
+        ))
+        .execute(conn)
+        .map_err(SimpleError::from)?;
+    Ok(())
+}
+
+/// Plans a number of servings, e.g. "0.5", of a saved meal.
+pub fn plan_meal(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    date: Date,
+    slot: Slot,
+    meal: &Meal,
+    servings: &str,
+) -> SimpleResult<()> {
+    let quantity = diary::meal_quantity(servings)?;
+    diesel::insert_into(planned_entries::table)
+        .values((
+            planned_entries::user_id.eq(user_id),
+            planned_entries::date.eq(date),
+            planned_entries::slot.eq(slot.as_str()),
+            planned_entries::meal_id.eq(meal.id),
+            planned_entries::amount.eq(servings.trim()),
+            planned_entries::quantity.eq(quantity),
+        ))
+        .execute(conn)
+        .map_err(SimpleError::from)?;
+    Ok(())
+}
+
+pub fn remove(conn: &mut SqliteConnection, user_id: i32, entry_id: i32) -> QueryResult<()> {
+    diesel::delete(planned_entries::table.find(entry_id))
+        .filter(planned_entries::user_id.eq(user_id))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Logs what is planned on `date` in the diary, in the same parts of the day,
+/// and returns the number of entries logged. The plan is kept, and entries
+/// already logged are left out so that logging a day twice doesn't log it
+/// twice.
+pub fn log_day(conn: &mut SqliteConnection, user_id: i32, date: Date) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let entries: Vec<PlannedEntry> = planned_entries::table
+            .filter(planned_entries::user_id.eq(user_id))
+            .filter(planned_entries::date.eq(date))
+            .filter(planned_entries::logged_entry_id.is_null())
+            .order(planned_entries::id)
+            .select(PlannedEntry::as_select())
+            .load(conn)?;
+        for entry in &entries {
+            diesel::insert_into(diary_entries::table)
+                .values((
+                    diary_entries::user_id.eq(entry.user_id),
+                    diary_entries::date.eq(entry.date),
+                    diary_entries::slot.eq(&entry.slot),
+                    diary_entries::food_id.eq(entry.food_id),
+                    diary_entries::meal_id.eq(entry.meal_id),
+                    diary_entries::amount.eq(&entry.amount),
+                    diary_entries::quantity.eq(entry.quantity),
+                ))
+                .execute(conn)?;
+            // The entry just logged has the largest id
+            let logged_id: Option<i32> = diary_entries::table
+                .select(diesel::dsl::max(diary_entries::id))
+                .first(conn)?;
+            diesel::update(planned_entries::table.find(entry.id))
+                .set(planned_entries::logged_entry_id.eq(logged_id))
+                .execute(conn)?;
+        }
+        Ok(entries.len())
+    })
+}